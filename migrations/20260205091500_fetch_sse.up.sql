@@ -0,0 +1,5 @@
+-- Add up migration script here
+ALTER TYPE fetch_api_type ADD VALUE IF NOT EXISTS 'sse';
+
+-- Per type options (sse, ...)
+ALTER TABLE fetch_api ADD COLUMN options JSONB DEFAULT '{}'::jsonb;
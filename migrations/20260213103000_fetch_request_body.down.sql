@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS body;
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS query;
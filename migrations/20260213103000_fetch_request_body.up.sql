@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Query parameters map & structured body (json, form, multipart), NULL body use raw payload
+ALTER TABLE fetch_api ADD COLUMN query JSONB DEFAULT '{}'::jsonb;
+ALTER TABLE fetch_api ADD COLUMN body JSONB;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS custom_method;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+ALTER TYPE fetch_api_method ADD VALUE IF NOT EXISTS 'head';
+ALTER TYPE fetch_api_method ADD VALUE IF NOT EXISTS 'options';
+ALTER TYPE fetch_api_method ADD VALUE IF NOT EXISTS 'custom';
+
+-- Verb used when method = 'custom' (PROPFIND, MKCOL, ...)
+ALTER TABLE fetch_api ADD COLUMN custom_method VARCHAR(32);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE fetch_api DROP CONSTRAINT IF EXISTS fk_fetch_auth;
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS auth_id;
+DROP TABLE IF EXISTS fetch_api_auth_token;
+DROP TABLE IF EXISTS fetch_api_auth;
+DROP TYPE IF EXISTS fetch_auth_type;
@@ -0,0 +1,50 @@
+-- Add up migration script here
+CREATE TYPE fetch_auth_type AS ENUM (
+    'client_credentials',
+    'password',
+    'bearer',
+    'basic',
+    'api_key'
+);
+
+-- CREATE TABLE fetch_api_auth
+CREATE TABLE fetch_api_auth (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    type fetch_auth_type NOT NULL DEFAULT 'bearer',
+    config JSONB NOT NULL DEFAULT '{}'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_fetch_auth_user
+        FOREIGN KEY (user_id)
+        REFERENCES users(id)
+        ON DELETE CASCADE
+);
+
+CREATE TRIGGER trg_set_timestamp_auth
+BEFORE UPDATE ON fetch_api_auth
+FOR EACH ROW
+EXECUTE PROCEDURE update_updated_at_column();
+
+-- Cached OAuth2 token, shared by every worker
+CREATE TABLE fetch_api_auth_token (
+    auth_id INTEGER PRIMARY KEY,
+    access_token TEXT NOT NULL,
+    token_type VARCHAR(64) NOT NULL DEFAULT 'Bearer',
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_auth_token_auth
+        FOREIGN KEY (auth_id)
+        REFERENCES fetch_api_auth(id)
+        ON DELETE CASCADE
+);
+
+ALTER TABLE fetch_api ADD COLUMN auth_id INTEGER;
+ALTER TABLE fetch_api ADD CONSTRAINT fk_fetch_auth
+    FOREIGN KEY (auth_id)
+    REFERENCES fetch_api_auth(id)
+    ON DELETE SET NULL;
+
+CREATE INDEX idx_fetch_api_auth_id ON fetch_api(auth_id);
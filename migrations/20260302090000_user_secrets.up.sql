@@ -0,0 +1,21 @@
+-- Add up migration script here
+-- Encrypted user secrets (AES-256-GCM, nonce prepended to ciphertext)
+CREATE TABLE user_secrets (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL,
+    description TEXT,
+    ciphertext BYTEA NOT NULL,
+    key_version INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT uq_user_secrets_name UNIQUE (user_id, name)
+);
+
+CREATE INDEX idx_user_secrets_key_version ON user_secrets(key_version);
+
+CREATE TRIGGER trg_set_timestamp_secret
+BEFORE UPDATE ON user_secrets
+FOR EACH ROW
+EXECUTE PROCEDURE update_updated_at_column();
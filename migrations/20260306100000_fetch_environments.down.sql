@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE fetch_api DROP CONSTRAINT IF EXISTS fk_fetch_environment;
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS environment_id;
+DROP TABLE IF EXISTS fetch_api_environment;
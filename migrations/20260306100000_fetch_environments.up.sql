@@ -0,0 +1,28 @@
+-- Add up migration script here
+-- CREATE TABLE fetch_api_environment
+CREATE TABLE fetch_api_environment (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    variables JSONB NOT NULL DEFAULT '{}'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_fetch_environment_user
+        FOREIGN KEY (user_id)
+        REFERENCES users(id)
+        ON DELETE CASCADE
+);
+
+CREATE TRIGGER trg_set_timestamp_environment
+BEFORE UPDATE ON fetch_api_environment
+FOR EACH ROW
+EXECUTE PROCEDURE update_updated_at_column();
+
+ALTER TABLE fetch_api ADD COLUMN environment_id INTEGER;
+ALTER TABLE fetch_api ADD CONSTRAINT fk_fetch_environment
+    FOREIGN KEY (environment_id)
+    REFERENCES fetch_api_environment(id)
+    ON DELETE SET NULL;
+
+CREATE INDEX idx_fetch_api_environment_id ON fetch_api(environment_id);
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TYPE fetch_api_type ADD VALUE IF NOT EXISTS 'workflow';
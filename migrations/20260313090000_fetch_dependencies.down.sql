@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fetch_api_dependency;
+DROP TYPE IF EXISTS fetch_dependency_trigger;
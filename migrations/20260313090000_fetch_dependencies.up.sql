@@ -0,0 +1,28 @@
+-- Add up migration script here
+CREATE TYPE fetch_dependency_trigger AS ENUM (
+    'success',
+    'failure',
+    'always'
+);
+
+-- CREATE TABLE fetch_api_dependency, edge upstream -> downstream
+CREATE TABLE fetch_api_dependency (
+    id SERIAL PRIMARY KEY,
+    upstream_id INTEGER NOT NULL,
+    downstream_id INTEGER NOT NULL,
+    trigger fetch_dependency_trigger NOT NULL DEFAULT 'success',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_dependency_upstream
+        FOREIGN KEY (upstream_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_dependency_downstream
+        FOREIGN KEY (downstream_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE,
+    CONSTRAINT uq_fetch_dependency UNIQUE (upstream_id, downstream_id),
+    CONSTRAINT chk_fetch_dependency_self CHECK (upstream_id <> downstream_id)
+);
+
+CREATE INDEX idx_fetch_dependency_downstream ON fetch_api_dependency(downstream_id);
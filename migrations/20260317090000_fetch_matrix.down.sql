@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fetch_api_matrix_run;
+DROP TABLE IF EXISTS fetch_api_dataset;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- CREATE TABLE fetch_api_dataset, rows used by matrix fetches
+CREATE TABLE fetch_api_dataset (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    rows JSONB NOT NULL DEFAULT '[]'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_fetch_dataset_user
+        FOREIGN KEY (user_id)
+        REFERENCES users(id)
+        ON DELETE CASCADE
+);
+
+CREATE TRIGGER trg_set_timestamp_dataset
+BEFORE UPDATE ON fetch_api_dataset
+FOR EACH ROW
+EXECUTE PROCEDURE update_updated_at_column();
+
+-- Aggregate of one fan-out run, each row job records its result
+CREATE TABLE fetch_api_matrix_run (
+    id SERIAL PRIMARY KEY,
+    fetch_id INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    succeeded INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    results JSONB NOT NULL DEFAULT '[]'::jsonb,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+
+    CONSTRAINT fk_matrix_run_fetch
+        FOREIGN KEY (fetch_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_matrix_run_fetch_id ON fetch_api_matrix_run(fetch_id, started_at DESC);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data
+    DROP COLUMN IF EXISTS passed,
+    DROP COLUMN IF EXISTS assertions;
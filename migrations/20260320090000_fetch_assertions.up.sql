@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE fetch_api_data
+    ADD COLUMN assertions JSONB,
+    ADD COLUMN passed BOOLEAN;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data
+    DROP COLUMN IF EXISTS schema_errors;
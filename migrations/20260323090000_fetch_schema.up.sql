@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE fetch_api_data
+    ADD COLUMN schema_errors JSONB;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fetch_runs;
+DROP TYPE IF EXISTS fetch_run_outcome;
+DROP TYPE IF EXISTS fetch_run_trigger;
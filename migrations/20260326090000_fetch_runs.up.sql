@@ -0,0 +1,41 @@
+-- Add up migration script here
+CREATE TYPE fetch_run_trigger AS ENUM (
+    'schedule',
+    'dependency',
+    'matrix'
+);
+
+CREATE TYPE fetch_run_outcome AS ENUM (
+    'success',
+    'failure',
+    'error'
+);
+
+-- CREATE TABLE fetch_runs, one row per worker attempt
+CREATE TABLE fetch_runs (
+    id BIGSERIAL PRIMARY KEY,
+    fetch_id INTEGER NOT NULL,
+    job_id VARCHAR(64),
+    attempt INTEGER NOT NULL DEFAULT 1,
+    trigger fetch_run_trigger NOT NULL DEFAULT 'schedule',
+    outcome fetch_run_outcome NOT NULL,
+    status_code SMALLINT,
+    error_kind VARCHAR(32),
+    error_message TEXT,
+    worker_id VARCHAR(255),
+    data_id INTEGER,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL,
+    duration_ms BIGINT NOT NULL,
+
+    CONSTRAINT fk_fetch_run_fetch
+        FOREIGN KEY (fetch_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_fetch_run_data
+        FOREIGN KEY (data_id)
+        REFERENCES fetch_api_data(id)
+        ON DELETE SET NULL
+);
+
+CREATE INDEX idx_fetch_runs_fetch_id ON fetch_runs(fetch_id, started_at DESC);
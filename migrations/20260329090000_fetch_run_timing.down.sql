@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE fetch_runs
+    DROP COLUMN IF EXISTS timing;
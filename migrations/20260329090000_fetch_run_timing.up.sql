@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE fetch_runs
+    ADD COLUMN timing JSONB;
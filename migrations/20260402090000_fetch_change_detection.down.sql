@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data
+    DROP COLUMN IF EXISTS changed,
+    DROP COLUMN IF EXISTS body_hash;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE fetch_api_data
+    ADD COLUMN body_hash VARCHAR(64),
+    ADD COLUMN changed BOOLEAN;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+-- CREATE TABLE fetch_metrics, one row per extracted value
+CREATE TABLE fetch_metrics (
+    id BIGSERIAL PRIMARY KEY,
+    fetch_id INTEGER NOT NULL,
+    data_id INTEGER,
+    name VARCHAR(64) NOT NULL,
+    value_num DOUBLE PRECISION,
+    value_text TEXT,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_fetch_metric_fetch
+        FOREIGN KEY (fetch_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE,
+    CONSTRAINT fk_fetch_metric_data
+        FOREIGN KEY (data_id)
+        REFERENCES fetch_api_data(id)
+        ON DELETE SET NULL
+);
+
+CREATE INDEX idx_fetch_metrics_series ON fetch_metrics(fetch_id, name, recorded_at);
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Pruning walks the newest rows of each fetch first
+CREATE INDEX idx_fetch_api_data_fetch_created ON fetch_api_data(fetch_id, created_at DESC, id DESC);
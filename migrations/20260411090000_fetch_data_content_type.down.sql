@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data
+    DROP COLUMN IF EXISTS encoding,
+    DROP COLUMN IF EXISTS content_type;
+
+DROP TYPE IF EXISTS fetch_body_encoding;
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TYPE fetch_body_encoding AS ENUM (
+    'text',
+    'base64'
+);
+
+-- Binary bodies kept as base64 text in response
+ALTER TABLE fetch_api_data
+    ADD COLUMN content_type VARCHAR(255),
+    ADD COLUMN encoding fetch_body_encoding NOT NULL DEFAULT 'text';
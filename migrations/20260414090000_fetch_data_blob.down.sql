@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data
+    DROP COLUMN IF EXISTS blob_size,
+    DROP COLUMN IF EXISTS blob_key,
+    DROP COLUMN IF EXISTS truncated;
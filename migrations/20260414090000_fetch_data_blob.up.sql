@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- Large bodies live in the blob store, only the key stays here
+ALTER TABLE fetch_api_data
+    ADD COLUMN truncated BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN blob_key VARCHAR(512),
+    ADD COLUMN blob_size BIGINT;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE fetch_api DROP CONSTRAINT IF EXISTS fk_fetch_created_by;
+ALTER TABLE fetch_api DROP COLUMN IF EXISTS created_by;
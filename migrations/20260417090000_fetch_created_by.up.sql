@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Secrets of a fetch resolve against its creator only
+ALTER TABLE fetch_api ADD COLUMN created_by INTEGER;
+ALTER TABLE fetch_api ADD CONSTRAINT fk_fetch_created_by
+    FOREIGN KEY (created_by)
+    REFERENCES users(id)
+    ON DELETE SET NULL;
+
+UPDATE fetch_api f SET created_by = (
+    SELECT m.user_id FROM fetch_api_members m
+    WHERE m.fetch_id = f.id AND m.role = 'owner'
+    ORDER BY m.created_at ASC, m.user_id ASC
+    LIMIT 1
+);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS fetch_api_matrix_row;
+DROP INDEX IF EXISTS idx_matrix_run_job_id;
+ALTER TABLE fetch_api_matrix_run DROP COLUMN IF EXISTS job_id;
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- One matrix run per fan out job, a retried fan out finds its run again
+ALTER TABLE fetch_api_matrix_run ADD COLUMN job_id VARCHAR(64);
+CREATE UNIQUE INDEX idx_matrix_run_job_id ON fetch_api_matrix_run(job_id);
+
+-- Row jobs enqueued for a run, each row claimed once
+CREATE TABLE fetch_api_matrix_row (
+    run_id INTEGER NOT NULL,
+    row_index INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (run_id, row_index),
+    CONSTRAINT fk_matrix_row_run
+        FOREIGN KEY (run_id)
+        REFERENCES fetch_api_matrix_run(id)
+        ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Variables saved by post-response scripts, kept apart from the fetch definition
+CREATE TABLE fetch_api_script_state (
+    fetch_id INTEGER PRIMARY KEY,
+    variables JSONB NOT NULL DEFAULT '{}'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fk_script_state_fetch
+        FOREIGN KEY (fetch_id)
+        REFERENCES fetch_api(id)
+        ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_matrix_row DROP COLUMN IF EXISTS recorded_at;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Row counted in the run summary, a retried row job is not counted twice
+ALTER TABLE fetch_api_matrix_row ADD COLUMN recorded_at TIMESTAMPTZ;
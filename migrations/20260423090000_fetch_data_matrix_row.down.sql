@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE fetch_api_data DROP COLUMN IF EXISTS matrix_row;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Matrix row index of the stored response, change detection compares rows of the same index
+ALTER TABLE fetch_api_data ADD COLUMN matrix_row INTEGER;
@@ -13,9 +13,33 @@ pub struct Config {
     pub log_level: Level,
     pub min_job_interval: u64,
     pub ws_timeout: u64,
+    pub sse_timeout: u64,
+    pub sse_max_events: usize,
+    pub probe_timeout: u64,
+    pub secret_key: Option<String>,
+    pub secret_key_version: i32,
+    pub secret_old_keys: String,
     pub root_username: String,
     pub root_email: String,
     pub root_password: String,
+    pub retention_keep_last: Option<i64>,
+    pub retention_max_age_days: Option<i64>,
+    pub retention_max_bytes: Option<i64>,
+    pub retention_interval: u64,
+    pub retention_batch: i64,
+    pub export_dir: Option<String>,
+    pub max_body_bytes: u64,
+    pub blob_store: Option<String>,
+    pub blob_threshold: u64,
+    pub blob_dir: String,
+    pub s3_endpoint: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    pub redact_headers: Vec<String>,
+    pub redact_paths: Vec<String>,
+    pub redact_patterns: Vec<String>,
 }
 
 impl Config {
@@ -24,7 +48,7 @@ impl Config {
         let port = port_str
             .trim()
             .parse::<u16>()
-            .expect(&format!("Invalid APP_PORT: '{}'", port_str));
+            .unwrap_or_else(|_| panic!("Invalid APP_PORT: '{}'", port_str));
 
         let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET required");
         let access_ttl = env::var("ACCESS_TTL_IN_MINUTES").ok().and_then(|v| v.parse::<u32>().map(|v| v.max(1)).ok()).unwrap_or(15) * 60;
@@ -35,9 +59,36 @@ impl Config {
         let log_level_str = env::var("LOG_LEVEL").unwrap_or_else(|_| "INFO".to_string()).to_uppercase();
         let min_job_interval = env::var("MIN_JOB_INTERVAL").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(1)).unwrap_or(10);
         let ws_timeout = env::var("WS_TIMEOUT").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(1)).unwrap_or(10);
+        let sse_timeout = env::var("SSE_TIMEOUT").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(1)).unwrap_or(30);
+        let sse_max_events = env::var("SSE_MAX_EVENTS").ok().and_then(|v| v.parse::<usize>().ok()).map(|v| v.max(1)).unwrap_or(100);
+        let probe_timeout = env::var("PROBE_TIMEOUT").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(1)).unwrap_or(10);
+        let secret_key = env::var("SECRET_KEY").ok().filter(|v| !v.trim().is_empty());
+        let secret_key_version = env::var("SECRET_KEY_VERSION").ok().and_then(|v| v.parse::<i32>().ok()).map(|v| v.max(1)).unwrap_or(1);
+        let secret_old_keys = env::var("SECRET_OLD_KEYS").unwrap_or_default();
         let root_username = env::var("ROOT_USERNAME").expect("ROOT_USERNAME required");
         let root_email = env::var("ROOT_EMAIL").expect("ROOT_EMAIL required");
         let root_password = env::var("ROOT_PASSWORD").expect("ROOT_PASSWORD required");
+        let retention_keep_last = env::var("RETENTION_KEEP_LAST").ok().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(1));
+        let retention_max_age_days = env::var("RETENTION_MAX_AGE_DAYS").ok().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(1));
+        let retention_max_bytes = env::var("RETENTION_MAX_BYTES").ok().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(1));
+        let retention_interval = env::var("RETENTION_INTERVAL").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(60)).unwrap_or(3600);
+        let export_dir = env::var("EXPORT_DIR").ok().filter(|v| !v.trim().is_empty());
+        let max_body_bytes = env::var("MAX_BODY_BYTES").ok().and_then(|v| v.parse::<u64>().ok()).map(|v| v.max(1)).unwrap_or(10 * 1024 * 1024);
+        let blob_store = env::var("BLOB_STORE").ok().map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty());
+        let blob_threshold = env::var("BLOB_THRESHOLD").ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(1024 * 1024);
+        let blob_dir = env::var("BLOB_DIR").unwrap_or_else(|_| "./blobs".to_string());
+        let s3_endpoint = env::var("S3_ENDPOINT").unwrap_or_default();
+        let s3_bucket = env::var("S3_BUCKET").unwrap_or_default();
+        let s3_region = env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let s3_access_key = env::var("S3_ACCESS_KEY").unwrap_or_default();
+        let s3_secret_key = env::var("S3_SECRET_KEY").unwrap_or_default();
+        let redact_headers = split_list(&env::var("REDACT_HEADERS").unwrap_or_else(|_| "authorization,proxy-authorization,cookie,set-cookie".to_string()));
+        let redact_paths = split_list(&env::var("REDACT_PATHS").unwrap_or_default());
+        let redact_patterns = env::var("REDACT_PATTERNS").ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| serde_json::from_str::<Vec<String>>(&v).unwrap_or_else(|e| panic!("REDACT_PATTERNS must be a JSON array of strings: {}", e)))
+            .unwrap_or_default();
+        let retention_batch = env::var("RETENTION_BATCH").ok().and_then(|v| v.parse::<i64>().ok()).map(|v| v.max(1)).unwrap_or(1000);
         
         let log_level = match log_level_str.as_str() {
             "TRACE" => Level::TRACE,
@@ -58,9 +109,41 @@ impl Config {
             log_level,
             min_job_interval,
             ws_timeout,
+            sse_timeout,
+            sse_max_events,
+            probe_timeout,
+            secret_key,
+            secret_key_version,
+            secret_old_keys,
             root_username,
             root_email,
             root_password,
+            retention_keep_last,
+            retention_max_age_days,
+            retention_max_bytes,
+            retention_interval,
+            retention_batch,
+            export_dir,
+            max_body_bytes,
+            blob_store,
+            blob_threshold,
+            blob_dir,
+            s3_endpoint,
+            s3_bucket,
+            s3_region,
+            s3_access_key,
+            s3_secret_key,
+            redact_headers,
+            redact_paths,
+            redact_patterns,
         }
     }
+}
+
+/// Comma separated values, blanks dropped
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
 }
\ No newline at end of file
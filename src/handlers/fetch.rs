@@ -1,8 +1,10 @@
-use axum::{http::Uri, response::IntoResponse};
-use crate::middleware::auth::{AuthUser}; 
+use axum::{body::Body, http::{Uri, header}, response::IntoResponse};
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use crate::middleware::auth::{AuthAdmin, AuthUser}; 
 use crate::services::fetch::FetchService;
-use crate::utils::{requests::{ValidatedJson, ValidatedPath}, response::{ApiError, WebResponse}};
-use crate::models::fetch::{CreateApiMembers, ReqCreateApi, ReqCreateApiData, ReqCreateApiExecute, ReqCreateApiHeader, UpdateApi, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers};
+use crate::utils::{requests::{ValidatedJson, ValidatedPath, ValidatedQuery}, response::{ApiError, WebResponse}};
+use crate::models::fetch::{ApiDataExport, ApiDataFilter, ApiMetricFilter, ApiRunFilter, ApiTimingFilter, CreateApiMembers, ReqCreateApiDataset, UpdateApiDataset, ReqCreateApiDependency, ReqCloneApi, ReqCreateApiEnvironment, UpdateApiEnvironment, ReqCreateApiAuth, UpdateApiAuth, ReqCreateApi, ReqCreateApiData, ReqCreateApiExecute, ReqCreateApiHeader, UpdateApi, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers};
 
 pub async fn get_all(
     uri: Uri,
@@ -70,6 +72,29 @@ pub async fn delete_fetch_api(
     Ok(WebResponse::ok(&uri, "Fetch Api Deleted!", response))
 }
 
+pub async fn render_fetch_api(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.render_fetch(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Rendered request", response))
+}
+
+pub async fn clone_fetch_api(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<ReqCloneApi>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.clone_fetch(user, id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Fetch Api Cloned!", response))
+}
+
 pub async fn get_fetch_member(
     ValidatedPath((fetch_id, id)): ValidatedPath<(i32, i32)>,
     uri: Uri,
@@ -244,15 +269,91 @@ pub async fn delete_fetch_header(
     Ok(WebResponse::ok(&uri, "Header deleted!", response))
 }
 
+pub async fn get_all_auth(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_all_auth(user).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List auth profiles", response))
+}
+
+pub async fn create_fetch_auth(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<ReqCreateApiAuth>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.create_auth(user, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Auth profile created!", response))
+}
+
+pub async fn get_fetch_auth(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_auth(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Success!", response))
+}
+
+pub async fn update_fetch_auth(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<UpdateApiAuth>
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.update_auth(user, id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Auth profile updated!", response))
+}
+
+pub async fn delete_fetch_auth(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.delete_auth(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Auth profile deleted!", response))
+}
+
 pub async fn get_all_data(
     ValidatedPath(fetch_id): ValidatedPath<i32>,
+    ValidatedQuery(filter): ValidatedQuery<ApiDataFilter>,
     uri: Uri,
     AuthUser(user): AuthUser,
     service: FetchService,
 ) -> Result<impl IntoResponse, ApiError> {
-    let response = service.get_all_data(user, fetch_id).await.map_err(|e|e.with_path(&uri))?;
+    let (response, pagination) = service.get_all_data(user, fetch_id, filter).await.map_err(|e|e.with_path(&uri))?;
 
-    Ok(WebResponse::ok(&uri, "List fetch data", response))
+    Ok(WebResponse::paginated(&uri, "List fetch data", response, pagination))
+}
+
+pub async fn export_fetch_data(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    ValidatedQuery(query): ValidatedQuery<ApiDataExport>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let format = query.format.unwrap_or_default();
+    let stream = service.export_data(user, fetch_id, query).await.map_err(|e|e.with_path(&uri))?;
+    let filename = format!("fetch-{}-{}.{}", fetch_id, Utc::now().format("%Y%m%d%H%M%S"), format.extension());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(stream.map_ok(axum::body::Bytes::from)),
+    ))
 }
 
 pub async fn create_fetch_data(
@@ -300,3 +401,266 @@ pub async fn delete_fetch_data(
 
     Ok(WebResponse::ok(&uri, "Fetch data deleted!", response))
 }
+
+pub async fn get_all_environment(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_all_environment(user).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List environments", response))
+}
+
+pub async fn create_fetch_environment(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<ReqCreateApiEnvironment>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.create_environment(user, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Environment created!", response))
+}
+
+pub async fn get_fetch_environment(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_environment(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Success", response))
+}
+
+pub async fn update_fetch_environment(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<UpdateApiEnvironment>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.update_environment(user, id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Environment updated!", response))
+}
+
+pub async fn delete_fetch_environment(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.delete_environment(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Environment deleted!", response))
+}
+
+pub async fn get_all_dependency(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_dependencies(user, fetch_id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List dependencies", response))
+}
+
+pub async fn create_fetch_dependency(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<ReqCreateApiDependency>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.add_dependency(user, fetch_id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Dependency created!", response))
+}
+
+pub async fn delete_fetch_dependency(
+    ValidatedPath((fetch_id, id)): ValidatedPath<(i32, i32)>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.delete_dependency(user, fetch_id, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Dependency deleted!", response))
+}
+
+pub async fn get_fetch_dag(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_dag(user, fetch_id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Dependency graph", response))
+}
+
+pub async fn get_all_dataset(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_all_dataset(user).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List datasets", response))
+}
+
+pub async fn create_fetch_dataset(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<ReqCreateApiDataset>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.create_dataset(user, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Dataset created!", response))
+}
+
+pub async fn get_fetch_dataset(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_dataset(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Success", response))
+}
+
+pub async fn update_fetch_dataset(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+    ValidatedJson(data): ValidatedJson<UpdateApiDataset>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.update_dataset(user, id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Dataset updated!", response))
+}
+
+pub async fn delete_fetch_dataset(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.delete_dataset(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Dataset deleted!", response))
+}
+
+pub async fn get_all_matrix_run(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_matrix_runs(user, fetch_id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List matrix runs", response))
+}
+
+pub async fn get_fetch_matrix_run(
+    ValidatedPath((fetch_id, id)): ValidatedPath<(i32, i32)>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_matrix_run(user, fetch_id, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Success", response))
+}
+
+pub async fn get_all_fetch_run(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    ValidatedQuery(filter): ValidatedQuery<ApiRunFilter>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_runs(user, fetch_id, filter).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List runs", response))
+}
+
+pub async fn get_fetch_run_stats(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    ValidatedQuery(filter): ValidatedQuery<ApiTimingFilter>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_timing_stats(user, fetch_id, filter).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Run timing stats", response))
+}
+
+pub async fn get_fetch_data_raw(
+    ValidatedPath((fetch_id, id)): ValidatedPath<(i32, i32)>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let (content_type, bytes) = service.get_data_raw(user, fetch_id, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"fetch-{}-data-{}\"", fetch_id, id)),
+        ],
+        bytes,
+    ))
+}
+
+pub async fn get_fetch_data_diff(
+    ValidatedPath((fetch_id, id)): ValidatedPath<(i32, i32)>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_data_diff(user, fetch_id, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Data diff", response))
+}
+
+pub async fn get_all_fetch_metric(
+    ValidatedPath(fetch_id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_metric_series(user, fetch_id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List metrics", response))
+}
+
+pub async fn get_fetch_metric(
+    ValidatedPath((fetch_id, name)): ValidatedPath<(i32, String)>,
+    ValidatedQuery(filter): ValidatedQuery<ApiMetricFilter>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_metric_points(user, fetch_id, name, filter).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Metric series", response))
+}
+
+pub async fn get_fetch_storage(
+    uri: Uri,
+    AuthAdmin(_): AuthAdmin,
+    service: FetchService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_storage_report().await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Storage report", response))
+}
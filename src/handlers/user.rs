@@ -3,7 +3,8 @@ use crate::middleware::auth::*;
 use crate::models::auth::{ReqCreateApiKey, ReqUpdateApiKey};
 use crate::utils::{response::*, requests::*};
 use crate::models::user::*;
-use crate::services::{user::UserService, auth::AuthService};
+use crate::models::secret::{ReqCreateSecret, ReqUpdateSecret};
+use crate::services::{user::UserService, auth::AuthService, secret::SecretService};
 
 pub async fn get_profile(
     uri: Uri,
@@ -137,4 +138,69 @@ pub async fn rotate_api_key(
     let response = service.rotate_api_key(user, id).await.map_err(|e|e.with_path(&uri))?;
 
     Ok(WebResponse::created(&uri, "Api key rotated!", response))
-}
\ No newline at end of file
+}
+
+pub async fn get_all_secret(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: SecretService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_all_secrets(user).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "List secrets", response))
+}
+
+pub async fn create_secret(
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: SecretService,
+    ValidatedJson(data): ValidatedJson<ReqCreateSecret>,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.create_secret(user, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::created(&uri, "Secret created!", response))
+}
+
+pub async fn get_secret(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: SecretService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.get_secret(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Success!", response))
+}
+
+pub async fn update_secret(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: SecretService,
+    ValidatedJson(data): ValidatedJson<ReqUpdateSecret>
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.update_secret(user, id, data).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Secret updated!", response))
+}
+
+pub async fn delete_secret(
+    ValidatedPath(id): ValidatedPath<i32>,
+    uri: Uri,
+    AuthUser(user): AuthUser,
+    service: SecretService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.delete_secret(user, id).await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Secret deleted!", response))
+}
+
+pub async fn rotate_secrets(
+    uri: Uri,
+    AuthAdmin(_): AuthAdmin,
+    service: SecretService,
+) -> Result<impl IntoResponse, ApiError> {
+    let response = service.rotate_secrets().await.map_err(|e|e.with_path(&uri))?;
+
+    Ok(WebResponse::ok(&uri, "Secrets re-encrypted with current key!", response))
+}
@@ -0,0 +1,89 @@
+use regex::Regex;
+use serde_json::Value;
+use std::time::Duration;
+use crate::{models::fetch::{Assertion, AssertionResult, FetchResult, JsonPathOp}, utils::jsonpath};
+
+pub const MAX_ASSERTIONS: usize = 50;
+
+/// Evaluate every assertion against the run result
+pub fn evaluate(assertions: &[Assertion], result: &FetchResult, latency: Duration) -> Vec<AssertionResult> {
+    let body: Option<Value> = serde_json::from_str(&result.response).ok();
+
+    assertions.iter()
+        .map(|assertion| {
+            let (passed, actual, message) = check(assertion, result, body.as_ref(), latency);
+            AssertionResult { assertion: assertion.clone(), passed, actual, message }
+        })
+        .collect()
+}
+
+fn check(assertion: &Assertion, result: &FetchResult, body: Option<&Value>, latency: Duration) -> (bool, Option<Value>, Option<String>) {
+    match assertion {
+        Assertion::Status { codes } => (codes.contains(&result.status_code), Some(Value::from(result.status_code)), None),
+        Assertion::Latency { max_ms } => {
+            let elapsed = latency.as_millis() as u64;
+            (elapsed <= *max_ms, Some(Value::from(elapsed)), None)
+        },
+        Assertion::Jsonpath { path, op, value } => {
+            let Some(body) = body else {
+                return (false, None, Some("Body is not JSON".to_string()));
+            };
+            let actual = match jsonpath::select_first(body, path) {
+                Ok(actual) => actual,
+                Err(e) => return (false, None, Some(e)),
+            };
+            let passed = match (op, actual, value) {
+                (JsonPathOp::Exists, actual, _) => actual.is_some(),
+                (JsonPathOp::Equals, Some(actual), Some(expected)) => actual == expected,
+                (JsonPathOp::Contains, Some(Value::String(actual)), Some(Value::String(expected))) => actual.contains(expected.as_str()),
+                (JsonPathOp::Contains, Some(Value::Array(items)), Some(expected)) => items.contains(expected),
+                _ => false,
+            };
+            (passed, actual.cloned(), None)
+        },
+        Assertion::Regex { pattern } => match Regex::new(pattern) {
+            Ok(re) => (re.is_match(&result.response), None, None),
+            Err(e) => (false, None, Some(e.to_string())),
+        },
+        Assertion::Header { name, value } => {
+            let actual = result.headers.as_object()
+                .and_then(|headers| headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)))
+                .map(|(_, v)| v.clone());
+            let passed = match (&actual, value) {
+                (Some(actual), Some(expected)) => actual.as_str() == Some(expected.as_str()),
+                (actual, None) => actual.is_some(),
+                (None, Some(_)) => false,
+            };
+            (passed, actual, None)
+        },
+    }
+}
+
+/// Patterns, paths and operands checked on save
+pub fn validate(assertions: &[Assertion]) -> Result<(), String> {
+    if assertions.len() > MAX_ASSERTIONS {
+        return Err(format!("Fetch can have at most {} assertions", MAX_ASSERTIONS));
+    }
+    for (index, assertion) in assertions.iter().enumerate() {
+        match assertion {
+            Assertion::Status { codes } if codes.is_empty() => {
+                return Err(format!("Assertion {} require at least one status code", index));
+            },
+            Assertion::Jsonpath { path, op, value } => {
+                jsonpath::parse(path).map_err(|e| format!("Assertion {}: {}", index, e))?;
+                if *op != JsonPathOp::Exists && value.is_none() {
+                    return Err(format!("Assertion {} require a value", index));
+                }
+            },
+            Assertion::Regex { pattern } => {
+                Regex::new(pattern).map_err(|e| format!("Assertion {}: {}", index, e))?;
+            },
+            Assertion::Header { name, .. } if name.trim().is_empty() => {
+                return Err(format!("Assertion {} require a header name", index));
+            },
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{Duration, Utc};
+use reqwest::{Client, Request};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::time::Duration as StdDuration;
+use tracing::{debug, info};
+use crate::{jobs::error::{ErrorKind, FetchError}, models::fetch::{Api, ApiAuth, AuthConfig, AuthType, ClientAuthMethod, FetchResult, KeyLocation}, repository::fetch::FetchAuthRepository};
+
+/// Cached token must still be valid this long, otherwise request a new one
+const TOKEN_MIN_VALID_SECONDS: i64 = 30;
+const DEFAULT_EXPIRES_IN: i64 = 3600;
+
+/// Credentials resolved from auth profile, ready to put into request
+#[derive(Debug, Default, Clone)]
+pub struct AuthApply {
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+}
+
+impl AuthApply {
+    /// Merge into fetch headers and query, auth profile wins on conflict
+    pub fn apply(&self, fetch: &mut Api, headers: &mut Option<Value>) {
+        if !self.headers.is_empty() {
+            let mut map = match headers.take() {
+                Some(Value::Object(map)) => map,
+                _ => Map::new(),
+            };
+            for (name, value) in &self.headers {
+                map.retain(|k, _| !k.eq_ignore_ascii_case(name));
+                map.insert(name.clone(), Value::String(value.clone()));
+            }
+            *headers = Some(Value::Object(map));
+        }
+
+        if !self.query.is_empty() {
+            let mut map = match fetch.query.take() {
+                Some(Value::Object(map)) => map,
+                _ => Map::new(),
+            };
+            for (name, value) in &self.query {
+                map.insert(name.clone(), Value::String(value.clone()));
+            }
+            fetch.query = Some(Value::Object(map));
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: Option<String>,
+    expires_in: Option<i64>,
+}
+
+pub fn is_oauth(profile: &ApiAuth) -> bool {
+    matches!(profile.r#type, AuthType::ClientCredentials | AuthType::Password)
+}
+
+/// Send with the profile applied, a token rejected with 401 is refreshed and sent once more.
+/// `attempt` returns the result with its latency.
+pub async fn send<F, Fut>(http_client: &Client, auth_repo: &FetchAuthRepository, profile: Option<&ApiAuth>, mut attempt: F) -> Result<(FetchResult, StdDuration), FetchError>
+where
+    F: FnMut(AuthApply) -> Fut,
+    Fut: Future<Output = Result<(FetchResult, StdDuration), FetchError>>,
+{
+    let Some(profile) = profile else {
+        return attempt(AuthApply::default()).await;
+    };
+
+    let auth = resolve(http_client, auth_repo, profile, false)
+        .await.map_err(|e| FetchError::new(ErrorKind::Auth, e))?;
+    let (result, latency) = attempt(auth).await?;
+    if result.status_code != 401 || !is_oauth(profile) {
+        return Ok((result, latency));
+    }
+
+    info!("[AUTH] Token of auth profile {} rejected, refreshing token", profile.id);
+    let auth = resolve(http_client, auth_repo, profile, true)
+        .await.map_err(|e| FetchError::new(ErrorKind::Auth, e))?;
+    attempt(auth).await
+}
+
+/// Resolve profile into headers/query, OAuth2 token taken from cache unless `force_refresh`
+pub async fn resolve(http_client: &Client, auth_repo: &FetchAuthRepository, profile: &ApiAuth, force_refresh: bool) -> Result<AuthApply, String> {
+    let config = profile.parsed_config()?;
+    config.validate(&profile.r#type)?;
+    let mut apply = AuthApply::default();
+
+    match profile.r#type {
+        AuthType::Bearer => {
+            let token = config.token.unwrap_or_default();
+            apply.headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        },
+        AuthType::Basic => {
+            let credentials = format!("{}:{}", config.username.unwrap_or_default(), config.password.unwrap_or_default());
+            apply.headers.push(("Authorization".to_string(), format!("Basic {}", STANDARD.encode(credentials))));
+        },
+        AuthType::ApiKey => {
+            let pair = (config.key_name.unwrap_or_default(), config.key_value.unwrap_or_default());
+            match config.key_in.unwrap_or_default() {
+                KeyLocation::Header => apply.headers.push(pair),
+                KeyLocation::Query => apply.query.push(pair),
+            }
+        },
+        AuthType::ClientCredentials | AuthType::Password => {
+            let (token_type, access_token) = oauth_token(http_client, auth_repo, profile, &config, force_refresh).await?;
+            apply.headers.push(("Authorization".to_string(), format!("{} {}", token_type, access_token)));
+        },
+    }
+
+    Ok(apply)
+}
+
+async fn oauth_token(http_client: &Client, auth_repo: &FetchAuthRepository, profile: &ApiAuth, config: &AuthConfig, force_refresh: bool) -> Result<(String, String), String> {
+    if force_refresh {
+        auth_repo.delete_token(profile.id)
+            .await.map_err(|e| format!("Failed clear cached token: {}", e))?;
+    } else if let Some(cached) = auth_repo.find_token(profile.id, TOKEN_MIN_VALID_SECONDS)
+        .await.map_err(|e| format!("Failed read cached token: {}", e))? {
+        return Ok((cached.token_type, cached.access_token));
+    }
+
+    debug!("[AUTH] Requesting new token for auth profile {}", profile.id);
+    let request = token_request(http_client, profile, config)?;
+    let response = http_client.execute(request)
+        .await.map_err(|e| format!("Failed request token: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Token endpoint returned {}: {}", status, body));
+    }
+    let token: TokenResponse = response.json()
+        .await.map_err(|e| format!("Invalid token response: {}", e))?;
+
+    let token_type = match token.token_type {
+        Some(t) if !t.eq_ignore_ascii_case("bearer") => t,
+        _ => "Bearer".to_string(),
+    };
+    let expires_at = Utc::now() + Duration::seconds(token.expires_in.unwrap_or(DEFAULT_EXPIRES_IN).max(1));
+    auth_repo.save_token(profile.id, &token.access_token, &token_type, expires_at)
+        .await.map_err(|e| format!("Failed cache token: {}", e))?;
+
+    Ok((token_type, token.access_token))
+}
+
+/// Token endpoint request of an OAuth2 profile, client secret in the form or as basic auth
+pub fn token_request(http_client: &Client, profile: &ApiAuth, config: &AuthConfig) -> Result<Request, String> {
+    let client_id = config.client_id.clone().unwrap_or_default();
+    let mut form: Vec<(&str, String)> = Vec::new();
+    match profile.r#type {
+        AuthType::Password => {
+            form.push(("grant_type", "password".to_string()));
+            form.push(("username", config.username.clone().unwrap_or_default()));
+            form.push(("password", config.password.clone().unwrap_or_default()));
+        },
+        _ => form.push(("grant_type", "client_credentials".to_string())),
+    }
+    if let Some(scope) = &config.scope {
+        form.push(("scope", scope.clone()));
+    }
+    if let Some(audience) = &config.audience {
+        form.push(("audience", audience.clone()));
+    }
+
+    let mut request_builder = http_client.post(config.token_url.clone().unwrap_or_default());
+    match (config.client_auth.unwrap_or_default(), &config.client_secret) {
+        (ClientAuthMethod::Basic, Some(secret)) => {
+            request_builder = request_builder.basic_auth(&client_id, Some(secret));
+        },
+        (_, secret) => {
+            form.push(("client_id", client_id.clone()));
+            if let Some(secret) = secret {
+                form.push(("client_secret", secret.clone()));
+            }
+        },
+    }
+
+    request_builder.form(&form)
+        .build()
+        .map_err(|e| format!("Invalid token request: {}", e))
+}
@@ -0,0 +1,271 @@
+use chrono::Utc;
+use reqwest::{Client, Method, Url};
+use ring::hmac;
+use std::path::{Component, Path, PathBuf};
+use crate::{models::fetch::{Api, ApiData, BodyEncoding, BodyLimitOptions, FetchResult}, state::AppState, utils::{body, crypto::{hex, sha256_hex}}};
+
+/// Store for response bodies too large for `fetch_api_data`
+#[derive(Clone)]
+pub enum BlobStore {
+    Fs(FsStore),
+    S3(S3Store),
+}
+
+impl BlobStore {
+    pub async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), String> {
+        match self {
+            BlobStore::Fs(store) => store.put(key, bytes).await,
+            BlobStore::S3(store) => store.put(key, bytes).await,
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        match self {
+            BlobStore::Fs(store) => store.get(key).await,
+            BlobStore::S3(store) => store.get(key).await,
+        }
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), String> {
+        match self {
+            BlobStore::Fs(store) => store.delete(key).await,
+            BlobStore::S3(store) => store.delete(key).await,
+        }
+    }
+
+    /// Delete every key, failures are logged only
+    pub async fn delete_all(&self, keys: &[String]) {
+        for key in keys {
+            if let Err(e) = self.delete(key).await {
+                tracing::warn!("[BLOB] Failed delete {}: {}", key, e);
+            }
+        }
+    }
+}
+
+/// New key of a body of the fetch
+pub fn key(fetch_id: i32) -> String {
+    format!("fetch-{}/{}", fetch_id, uuid::Uuid::now_v7())
+}
+
+/// Body of the row to store, large bodies moved to the blob store: (response, blob_key, blob_size)
+pub async fn spill(state: &AppState, fetch: &Api, result: &FetchResult) -> (String, Option<String>, Option<i64>) {
+    let spill = fetch.parsed_options().body_limit.and_then(|limit| limit.spill).unwrap_or(true);
+    let Some(store) = state.blob_store.as_ref().filter(|_| spill) else {
+        return (result.response.clone(), None, None);
+    };
+    if result.response.len() as u64 <= state.app_config.blob_threshold {
+        return (result.response.clone(), None, None);
+    }
+
+    let key = key(fetch.id);
+    let bytes = body::encode(result.content_type.as_deref(), &result.response, result.encoding);
+    let size = bytes.len() as i64;
+    match store.put(&key, bytes).await {
+        Ok(()) => (String::new(), Some(key), Some(size)),
+        Err(e) => {
+            tracing::warn!("[BLOB] Failed store body of fetch {}, kept inline: {}", fetch.id, e);
+            (result.response.clone(), None, None)
+        }
+    }
+}
+
+/// Original bytes of a stored body, from the blob store when it was spilled
+pub async fn load(state: &AppState, data: &ApiData) -> Result<Vec<u8>, String> {
+    match &data.blob_key {
+        Some(key) => match &state.blob_store {
+            Some(store) => store.get(key).await,
+            None => Err("Blob store is not configured".to_string()),
+        },
+        None => Ok(body::encode(data.content_type.as_deref(), &data.response, data.encoding)),
+    }
+}
+
+/// Stored body as text with its encoding, loaded from the blob store when it was spilled
+pub async fn body(state: &AppState, data: &ApiData) -> Result<(String, BodyEncoding), String> {
+    if data.blob_key.is_none() {
+        return Ok((data.response.clone(), data.encoding));
+    }
+    let bytes = load(state, data).await?;
+
+    Ok(body::decode(data.content_type.as_deref(), &bytes))
+}
+
+/// Spilled body put back inline, the row reads as if it was never spilled
+pub async fn hydrate(state: &AppState, data: &mut ApiData) -> Result<(), String> {
+    if data.blob_key.is_some() {
+        (data.response, data.encoding) = body(state, data).await?;
+    }
+
+    Ok(())
+}
+
+/// Rows whose body holds `needle`, spilled rows come from the query unmatched and are checked here
+pub async fn retain_contains(state: &AppState, rows: Vec<ApiData>, needle: &str) -> Vec<ApiData> {
+    let mut kept = Vec::with_capacity(rows.len());
+    for data in rows {
+        if data.blob_key.is_none() {
+            kept.push(data);
+            continue;
+        }
+        match body(state, &data).await {
+            Ok((text, _)) if text.contains(needle) => kept.push(data),
+            Ok(_) => {},
+            Err(e) => tracing::warn!("[BLOB] Failed load body of data {}: {}", data.id, e),
+        }
+    }
+
+    kept
+}
+
+/// Limit must be positive
+pub fn validate(options: &BodyLimitOptions) -> Result<(), String> {
+    if options.max_bytes == Some(0) {
+        return Err("Body limit max_bytes must be at least 1".to_string());
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Keys stay inside the root
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(format!("Invalid blob key '{}'", key));
+        }
+
+        Ok(self.root.join(relative))
+    }
+
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+        }
+        tokio::fs::write(&path, bytes).await.map_err(|e| e.to_string())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        tokio::fs::read(self.path(key)?).await.map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// S3 compatible store, path style requests signed with SigV4 (works with MinIO)
+#[derive(Clone)]
+pub struct S3Store {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Store {
+    pub fn new(client: Client, endpoint: &str, bucket: &str, region: &str, access_key: &str, secret_key: &str) -> Result<Self, String> {
+        let endpoint = Url::parse(endpoint).map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+        if bucket.is_empty() {
+            return Err("S3 bucket is empty".to_string());
+        }
+
+        Ok(Self {
+            client,
+            endpoint,
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        })
+    }
+
+    async fn send(&self, method: Method, key: &str, body: Vec<u8>) -> Result<reqwest::Response, String> {
+        let path = format!("/{}/{}", uri_encode(&self.bucket), key.split('/').map(uri_encode).collect::<Vec<_>>().join("/"));
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(&body);
+        let canonical = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, sha256_hex(canonical.as_bytes()));
+        let key = signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex(hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &key), to_sign.as_bytes()).as_ref());
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+
+        let response = self.client.request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await.map_err(|e| format!("S3 request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("S3 responded {}", response.status()));
+        }
+
+        Ok(response)
+    }
+
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), String> {
+        self.send(Method::PUT, key, bytes).await.map(|_| ())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        let response = self.send(Method::GET, key, Vec::new()).await?;
+        let bytes = response.bytes().await.map_err(|e| format!("S3 read failed: {}", e))?;
+
+        Ok(bytes.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.send(Method::DELETE, key, Vec::new()).await.map(|_| ())
+    }
+}
+
+/// SigV4 key of a day, region and service
+pub fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let sign = |key: &[u8], data: &str| hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes()).as_ref().to_vec();
+    let k_date = sign(format!("AWS4{}", secret_key).as_bytes(), date);
+    let k_region = sign(&k_date, region);
+    let k_service = sign(&k_region, service);
+    sign(&k_service, "aws4_request")
+}
+
+/// Percent encode all but the unreserved characters
+fn uri_encode(segment: &str) -> String {
+    segment.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
@@ -0,0 +1,103 @@
+use serde_json::{Map, Value};
+use crate::{
+    models::fetch::{Api, ChangeMode, ChangeOptions, DiffChange, DiffOp, FetchResult},
+    repository::fetch::FetchDataRepository,
+    utils::{crypto::sha256_hex, jsonpath},
+};
+
+/// Hash of the status and body, JSON is hashed with sorted keys and ignored paths removed
+pub fn fingerprint(status_code: i16, body: &str, ignore: &[String]) -> String {
+    let body = match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            for path in ignore {
+                let _ = jsonpath::remove(&mut value, path);
+            }
+            canonical(&value).to_string()
+        },
+        Err(_) => body.to_string(),
+    };
+
+    sha256_hex(format!("{}\n{}", status_code, body).as_bytes())
+}
+
+/// Body hash, changed flag and whether to store, compared with the latest entry of the same matrix row
+pub async fn detect(data_repo: &FetchDataRepository, fetch: &Api, result: &FetchResult, matrix_row: Option<i32>) -> Result<(Option<String>, Option<bool>, bool), sqlx::Error> {
+    let Some(change) = fetch.parsed_options().change_detection else {
+        return Ok((None, None, true));
+    };
+    let hash = fingerprint(result.status_code, &result.response, &change.ignore);
+    let previous = data_repo.find_latest(fetch.id, matrix_row).await?.and_then(|d| d.body_hash);
+    let changed = previous.as_deref() != Some(hash.as_str());
+    let store = changed || change.mode == ChangeMode::Flag;
+    if !store {
+        tracing::info!("[CHANGE] Fetch {} response unchanged, not stored", fetch.id);
+    }
+
+    Ok((Some(hash), Some(changed), store))
+}
+
+/// Same value with object keys in sorted order
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(keys.into_iter().map(|k| (k.clone(), canonical(&map[k]))).collect::<Map<_, _>>())
+        },
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Stored body as JSON, plain text kept as a string like `ApiDataResponse`
+pub fn body_value(body: &str) -> Value {
+    serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string()))
+}
+
+/// JSON aware diff, paths are JSON pointers
+pub fn diff(old: &Value, new: &Value) -> Vec<DiffChange> {
+    let mut changes = Vec::new();
+    diff_into(old, new, String::new(), &mut changes);
+
+    changes
+}
+
+fn diff_into(old: &Value, new: &Value, path: String, changes: &mut Vec<DiffChange>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys().filter(|k| !a.contains_key(*k))).collect();
+            keys.sort();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_into(x, y, child, changes),
+                    (Some(x), None) => changes.push(DiffChange { op: DiffOp::Remove, path: child, old: Some(x.clone()), new: None }),
+                    (None, Some(y)) => changes.push(DiffChange { op: DiffOp::Add, path: child, old: None, new: Some(y.clone()) }),
+                    (None, None) => {},
+                }
+            }
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let child = format!("{}/{}", path, index);
+                match (a.get(index), b.get(index)) {
+                    (Some(x), Some(y)) => diff_into(x, y, child, changes),
+                    (Some(x), None) => changes.push(DiffChange { op: DiffOp::Remove, path: child, old: Some(x.clone()), new: None }),
+                    (None, Some(y)) => changes.push(DiffChange { op: DiffOp::Add, path: child, old: None, new: Some(y.clone()) }),
+                    (None, None) => {},
+                }
+            }
+        },
+        (a, b) if a != b => changes.push(DiffChange { op: DiffOp::Replace, path, old: Some(a.clone()), new: Some(b.clone()) }),
+        _ => {},
+    }
+}
+
+/// Ignore paths must parse
+pub fn validate(options: &ChangeOptions) -> Result<(), String> {
+    for path in &options.ignore {
+        jsonpath::parse(path).map_err(|e| format!("Change detection: {}", e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use reqwest::{Certificate, Client, ClientBuilder, Identity, Proxy, redirect};
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+use crate::{jobs::timing::TimingLayer, models::fetch::{ClientOptions, RedirectMode}};
+
+const DEFAULT_USER_AGENT: &str = "Teknohole/1.0";
+const MAX_CACHED_CLIENTS: usize = 256;
+
+/// Default http client settings
+pub fn base_builder() -> ClientBuilder {
+    Client::builder()
+        .user_agent(DEFAULT_USER_AGENT)
+        .timeout(Duration::from_secs(10))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(10)
+        .connector_layer(TimingLayer)
+}
+
+/// Build http client from fetch client options
+pub fn build_client(options: &ClientOptions) -> Result<Client, String> {
+    let mut builder = base_builder();
+
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout.max(1)));
+    }
+
+    if let Some(user_agent) = &options.user_agent {
+        builder = builder.user_agent(user_agent.clone());
+    }
+
+    builder = match options.redirect {
+        Some(RedirectMode::None) => builder.redirect(redirect::Policy::none()),
+        Some(RedirectMode::Limit) => builder.redirect(redirect::Policy::limited(options.max_redirects.unwrap_or(10))),
+        Some(RedirectMode::Follow) | None => builder,
+    };
+
+    if let Some(proxy) = &options.proxy {
+        let proxy = Proxy::all(proxy).map_err(|e| format!("Invalid proxy: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_bundle) = &options.ca_bundle {
+        let certs = Certificate::from_pem_bundle(ca_bundle.as_bytes())
+            .map_err(|e| format!("Invalid CA bundle: {}", e))?;
+        if certs.is_empty() {
+            return Err("Invalid CA bundle: no certificate found".to_string());
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&options.client_cert, &options.client_key) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pkcs8_pem(cert.as_bytes(), key.as_bytes())
+                .map_err(|e| format!("Invalid client certificate: {}", e))?;
+            builder = builder.identity(identity);
+        },
+        (None, None) => {},
+        _ => return Err("Client certificate and client key must be set together".to_string()),
+    }
+
+    if options.skip_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder.build().map_err(|e| format!("Failed build http client: {}", e))
+}
+
+/// Http clients keyed by settings, reuse client so connection pooling still works
+#[derive(Clone)]
+pub struct HttpClients {
+    default_client: Client,
+    clients: Arc<Mutex<HashMap<String, Client>>>,
+}
+
+impl HttpClients {
+    pub fn new(default_client: Client) -> Self {
+        Self {
+            default_client,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn get(&self, options: &Option<ClientOptions>) -> Result<Client, String> {
+        let options = match options {
+            Some(options) if options != &ClientOptions::default() => options,
+            _ => return Ok(self.default_client.clone()),
+        };
+
+        let key = serde_json::to_string(options)
+            .map_err(|e| format!("Invalid client options: {}", e))?;
+        if let Some(client) = self.clients.lock().map_err(|e| e.to_string())?.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let client = build_client(options)?;
+        let mut clients = self.clients.lock().map_err(|e| e.to_string())?;
+        if clients.len() >= MAX_CACHED_CLIENTS {
+            clients.clear();
+        }
+        clients.insert(key, client.clone());
+
+        Ok(client)
+    }
+}
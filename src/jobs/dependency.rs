@@ -0,0 +1,109 @@
+use apalis::prelude::Storage;
+use std::collections::{HashSet, VecDeque};
+use crate::{jobs::redact, models::fetch::{Api, ApiMatrixRun, FetchResult, RedactionOptions, UpstreamResult}, repository::fetch::{FetchDependencyRepository, FetchRepository}, state::AppState};
+
+/// Upstream response passed to downstream job is cut to this size
+const MAX_UPSTREAM_RESPONSE: usize = 64 * 1024;
+
+/// Adding `upstream -> downstream` closes a cycle when upstream is reachable from downstream
+pub fn creates_cycle(edges: &[(i32, i32)], upstream: i32, downstream: i32) -> bool {
+    let mut queue = VecDeque::from([downstream]);
+    let mut visited = HashSet::new();
+
+    while let Some(node) = queue.pop_front() {
+        if node == upstream {
+            return true;
+        }
+        if !visited.insert(node) {
+            continue;
+        }
+        queue.extend(edges.iter().filter(|(from, _)| *from == node).map(|(_, to)| *to));
+    }
+
+    false
+}
+
+/// Dependents fire once per run: an outcome that is kept right away, a failure only when no retry follows
+pub fn is_final(failed: bool, attempt: usize, max_attempts: usize) -> bool {
+    !failed || attempt >= max_attempts
+}
+
+/// Enqueue dependents matching the run outcome, failures are logged only
+pub async fn trigger_downstream(state: &AppState, fetch: &Api, upstream: UpstreamResult, success: bool) {
+    let dependency_repo = FetchDependencyRepository::new(state.database.clone());
+    let fetch_repo = FetchRepository::new(state.database.clone());
+
+    let edges = match dependency_repo.find_downstream(fetch.id).await {
+        Ok(edges) => edges,
+        Err(e) => {
+            tracing::warn!("[DEPENDENCY] Failed load dependents of fetch {}: {:?}", fetch.id, e);
+            return;
+        }
+    };
+    if edges.is_empty() {
+        return;
+    }
+
+    for edge in edges.iter().filter(|e| e.trigger.matches(success)) {
+        let mut downstream = match fetch_repo.get_by_id(&edge.downstream_id).await {
+            Ok(downstream) if downstream.is_active => downstream,
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!("[DEPENDENCY] Downstream fetch {} not found: {:?}", edge.downstream_id, e);
+                continue;
+            }
+        };
+        downstream.triggered_by = Some(upstream.clone());
+
+        match state.job_queue.clone().push(downstream).await {
+            Ok(_) => tracing::info!("[DEPENDENCY] Fetch {} triggered fetch {}", fetch.id, edge.downstream_id),
+            Err(e) => tracing::error!("[DEPENDENCY] Failed enqueue fetch {}: {}", edge.downstream_id, e),
+        }
+    }
+}
+
+/// Run outcome as seen by downstream templates, redacted and cut to `MAX_UPSTREAM_RESPONSE`
+pub fn upstream_result(rules: &RedactionOptions, fetch: &Api, response: Result<&FetchResult, &str>) -> UpstreamResult {
+    let (status_code, mut body, error) = match response {
+        Ok(result) => {
+            let mut result = result.clone();
+            redact::apply(rules, &mut result);
+            (Some(result.status_code), result.response, None)
+        },
+        Err(e) => (None, String::new(), Some(redact::text(rules, e))),
+    };
+    if body.len() > MAX_UPSTREAM_RESPONSE {
+        let mut end = MAX_UPSTREAM_RESPONSE;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+
+    UpstreamResult {
+        id: fetch.id,
+        name: fetch.name.clone(),
+        status_code,
+        response: body,
+        error,
+    }
+}
+
+/// Finished matrix run as one upstream outcome, the response holds the run summary
+pub fn matrix_result(fetch: &Api, run: &ApiMatrixRun) -> UpstreamResult {
+    let summary = serde_json::json!({
+        "run_id": run.id,
+        "total": run.total,
+        "succeeded": run.succeeded,
+        "failed": run.failed,
+    });
+    let error = (run.failed > 0).then(|| format!("{} of {} rows failed", run.failed, run.total));
+
+    UpstreamResult {
+        id: fetch.id,
+        name: fetch.name.clone(),
+        status_code: None,
+        response: summary.to_string(),
+        error,
+    }
+}
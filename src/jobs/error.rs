@@ -0,0 +1,97 @@
+use std::fmt;
+
+/// Stage a run failed in, stored as `error_kind` in the run history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Timeout,
+    /// DNS, TCP or TLS
+    Connection,
+    Request,
+    Script,
+    Template,
+    Secret,
+    Auth,
+    Database,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Connection => "connection",
+            ErrorKind::Request => "request",
+            ErrorKind::Script => "script",
+            ErrorKind::Template => "template",
+            ErrorKind::Secret => "secret",
+            ErrorKind::Auth => "auth",
+            ErrorKind::Database => "database",
+        }
+    }
+}
+
+/// Failed run with the kind it is recorded under
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl FetchError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+
+    /// Kind from the reqwest error itself, sending and reading the body alike
+    pub fn from_reqwest(context: &str, e: &reqwest::Error) -> Self {
+        let kind = if e.is_timeout() {
+            ErrorKind::Timeout
+        } else if e.is_connect() {
+            ErrorKind::Connection
+        } else {
+            ErrorKind::Request
+        };
+
+        Self::new(kind, format!("{}: {}", context, e))
+    }
+
+    /// Kind of an io error, a timed out read or connect is a timeout
+    pub fn from_io(context: &str, e: &std::io::Error) -> Self {
+        let kind = match e.kind() {
+            std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            _ => ErrorKind::Connection,
+        };
+
+        Self::new(kind, format!("{}: {}", context, e))
+    }
+
+    /// Same kind, message prefixed with where it happened
+    pub fn context(self, prefix: impl fmt::Display) -> Self {
+        Self { kind: self.kind, message: format!("{}: {}", prefix, self.message) }
+    }
+}
+
+/// Plain messages are request errors, e.g. an invalid method or body
+impl From<String> for FetchError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Request, message)
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Kind of a failed attempt, errors outside the request stages are database or request errors
+pub fn kind_of(e: &anyhow::Error) -> ErrorKind {
+    if let Some(e) = e.downcast_ref::<FetchError>() {
+        e.kind
+    } else if e.downcast_ref::<sqlx::Error>().is_some() {
+        ErrorKind::Database
+    } else {
+        ErrorKind::Request
+    }
+}
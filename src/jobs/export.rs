@@ -0,0 +1,220 @@
+use chrono::{Duration, NaiveDate, Utc};
+use futures_util::{Stream, StreamExt};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use crate::{jobs::blob, models::fetch::{Api, ApiData, ApiDataFilter, DataCursor, DataSort, ExportColumn, ExportFormat, ExportOptions, SortOrder}, repository::fetch::{FetchDataRepository, FetchRepository}, state::AppState, utils::{body, jsonpath}};
+
+/// Rows read per query while streaming
+pub const BATCH: i64 = 500;
+const BASE_COLUMNS: [&str; 5] = ["id", "created_at", "status_code", "passed", "changed"];
+
+/// Parse `name=$.path,$.other` from the export query
+pub fn parse_columns(spec: &str) -> Result<Vec<ExportColumn>, String> {
+    let columns: Vec<ExportColumn> = spec.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once('=') {
+            Some((name, path)) if !item.starts_with('$') => ExportColumn { name: name.trim().to_string(), path: path.trim().to_string() },
+            _ => ExportColumn { name: item.to_string(), path: item.to_string() },
+        })
+        .collect();
+    validate(&columns)?;
+
+    Ok(columns)
+}
+
+/// Paths parse, names unique and apart from the base columns
+pub fn validate(columns: &[ExportColumn]) -> Result<(), String> {
+    for (index, column) in columns.iter().enumerate() {
+        if column.name.is_empty() || column.name == "response" || BASE_COLUMNS.contains(&column.name.as_str()) {
+            return Err(format!("Export column name '{}' is empty or reserved", column.name));
+        }
+        if columns[..index].iter().any(|c| c.name == column.name) {
+            return Err(format!("Export column '{}' is used more than once", column.name));
+        }
+        jsonpath::parse(&column.path).map_err(|e| format!("Export column '{}': {}", column.name, e))?;
+    }
+
+    Ok(())
+}
+
+/// CSV header line, NDJSON has none
+pub fn header(format: ExportFormat, columns: &[ExportColumn]) -> Vec<u8> {
+    match format {
+        ExportFormat::Csv => {
+            let mut names: Vec<&str> = BASE_COLUMNS.to_vec();
+            if columns.is_empty() {
+                names.push("response");
+            }
+            names.extend(columns.iter().map(|c| c.name.as_str()));
+            csv_line(&names)
+        },
+        ExportFormat::Ndjson => Vec::new(),
+    }
+}
+
+/// One encoded row, projected columns replace the raw body
+pub fn row(format: ExportFormat, columns: &[ExportColumn], data: &ApiData) -> Vec<u8> {
+    let body = body::view(data.content_type.as_deref(), &data.response, data.encoding);
+    let projected: Vec<(&str, Value)> = columns.iter()
+        .map(|c| (c.name.as_str(), project(&body, &c.path)))
+        .collect();
+
+    match format {
+        ExportFormat::Csv => {
+            let mut cells = vec![
+                data.id.to_string(),
+                data.created_at.to_rfc3339(),
+                data.status_code.to_string(),
+                data.passed.map(|p| p.to_string()).unwrap_or_default(),
+                data.changed.map(|c| c.to_string()).unwrap_or_default(),
+            ];
+            if columns.is_empty() {
+                cells.push(data.response.clone());
+            }
+            cells.extend(projected.iter().map(|(_, value)| match value {
+                Value::Null => String::new(),
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            }));
+            csv_line(&cells)
+        },
+        ExportFormat::Ndjson => {
+            let mut object = Map::new();
+            object.insert("id".to_string(), Value::from(data.id));
+            object.insert("created_at".to_string(), Value::from(data.created_at.to_rfc3339()));
+            object.insert("status_code".to_string(), Value::from(data.status_code));
+            object.insert("passed".to_string(), data.passed.map_or(Value::Null, Value::from));
+            object.insert("changed".to_string(), data.changed.map_or(Value::Null, Value::from));
+            if columns.is_empty() {
+                object.insert("response".to_string(), body);
+            }
+            for (name, value) in projected {
+                object.insert(name.to_string(), value);
+            }
+            let mut line = serde_json::to_vec(&Value::Object(object)).unwrap_or_default();
+            line.push(b'\n');
+            line
+        },
+    }
+}
+
+/// Single match as is, several as an array
+fn project(body: &Value, path: &str) -> Value {
+    match jsonpath::select(body, path) {
+        Ok(values) if values.len() == 1 => values[0].clone(),
+        Ok(values) if !values.is_empty() => Value::Array(values.into_iter().cloned().collect()),
+        _ => Value::Null,
+    }
+}
+
+fn csv_line<T: AsRef<[u8]>>(cells: &[T]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let _ = writer.write_record(cells);
+    writer.into_inner().unwrap_or_default()
+}
+
+struct Page {
+    state: AppState,
+    repo: FetchDataRepository,
+    fetch_id: i32,
+    filter: ApiDataFilter,
+    format: ExportFormat,
+    columns: Vec<ExportColumn>,
+    cursor: Option<DataCursor>,
+    started: bool,
+}
+
+/// Encoded rows in chunks of `BATCH`, only one chunk held in memory
+pub fn stream(state: AppState, fetch_id: i32, filter: ApiDataFilter, format: ExportFormat, columns: Vec<ExportColumn>) -> impl Stream<Item = Result<Vec<u8>, anyhow::Error>> + Send + 'static {
+    let sort = filter.sort.unwrap_or_default();
+    let order = filter.order.unwrap_or_default();
+    let page = Page { repo: FetchDataRepository::new(state.database.clone()), state, fetch_id, filter, format, columns, cursor: None, started: false };
+
+    futures_util::stream::unfold(Some(page), move |page| async move {
+        let mut page = page?;
+        let mut chunk = if page.started { Vec::new() } else { header(page.format, &page.columns) };
+        page.started = true;
+
+        match page.repo.find_page(page.fetch_id, &page.filter, page.cursor.as_ref(), BATCH).await {
+            Ok(rows) => {
+                let done = (rows.len() as i64) < BATCH;
+                page.cursor = rows.last().map(|last| DataCursor::after(sort, order, last));
+                let rows = match &page.filter.contains {
+                    Some(contains) => blob::retain_contains(&page.state, rows, contains).await,
+                    None => rows,
+                };
+                // Spilled bodies exported as stored, not as the empty inline column
+                for mut data in rows {
+                    if let Err(e) = blob::hydrate(&page.state, &mut data).await {
+                        return Some((Err(anyhow::anyhow!("Failed load body of data {}: {}", data.id, e)), None));
+                    }
+                    chunk.extend(row(page.format, &page.columns, &data));
+                }
+                Some((Ok(chunk), if done { None } else { Some(page) }))
+            },
+            Err(e) => Some((Err(e.into()), None)),
+        }
+    })
+}
+
+/// Write yesterday's rows of every fetch with `export` options, once per day
+pub async fn start_daily_export(state: AppState, dir: PathBuf) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+
+    loop {
+        interval.tick().await;
+
+        let fetches = match FetchRepository::new(state.database.clone()).get_all_fetch().await {
+            Ok(fetches) => fetches,
+            Err(e) => {
+                tracing::error!("[EXPORT] Failed to load fetches: {:?}", e);
+                continue;
+            }
+        };
+        let day = Utc::now().date_naive() - Duration::days(1);
+
+        for fetch in fetches {
+            let Some(options) = fetch.parsed_options().export else {
+                continue;
+            };
+            match export_day(&state, &fetch, &options, &dir, day).await {
+                Ok(Some(path)) => tracing::info!("[EXPORT] Fetch {} exported to {}", fetch.id, path.display()),
+                Ok(None) => {},
+                Err(e) => tracing::warn!("[EXPORT] Failed to export fetch {}: {:?}", fetch.id, e),
+            }
+        }
+    }
+}
+
+/// `<dir>/fetch-<id>/<day>.<ext>`, skipped when the file already exists
+async fn export_day(state: &AppState, fetch: &Api, options: &ExportOptions, dir: &Path, day: NaiveDate) -> Result<Option<PathBuf>, anyhow::Error> {
+    let folder = dir.join(format!("fetch-{}", fetch.id));
+    let path = folder.join(format!("{}.{}", day, options.format.extension()));
+    if tokio::fs::try_exists(&path).await? {
+        return Ok(None);
+    }
+    tokio::fs::create_dir_all(&folder).await?;
+
+    let since = day.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    let filter = ApiDataFilter {
+        since,
+        until: since.map(|t| t + Duration::days(1)),
+        sort: Some(DataSort::CreatedAt),
+        order: Some(SortOrder::Asc),
+        ..Default::default()
+    };
+
+    // Written aside then renamed, a crash never leaves a partial day behind
+    let part = path.with_extension("part");
+    let mut file = tokio::fs::File::create(&part).await?;
+    let mut chunks = Box::pin(stream(state.clone(), fetch.id, filter, options.format, options.columns.clone()));
+    while let Some(chunk) = chunks.next().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+    tokio::fs::rename(&part, &path).await?;
+
+    Ok(Some(path))
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use std::time::Instant;
+use crate::{jobs::error::ErrorKind, models::fetch::{Api, CreateApiRun, FetchResult, RunOutcome, RunTiming, RunTrigger}, repository::fetch::FetchRunRepository, state::AppState};
+
+/// Stored error message is cut to this size
+const MAX_ERROR_MESSAGE: usize = 4096;
+
+/// Outcome of one attempt, written by `RunRecorder::finish`
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    pub outcome: RunOutcome,
+    pub status_code: Option<i16>,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+    pub data_id: Option<i32>,
+    pub timing: Option<RunTiming>,
+}
+
+impl RunReport {
+    /// Run without a response of its own, e.g. a matrix fan out
+    pub fn empty() -> Self {
+        Self { outcome: RunOutcome::Success, status_code: None, error_kind: None, error_message: None, data_id: None, timing: None }
+    }
+
+    pub fn from_result(result: &FetchResult, data_id: Option<i32>) -> Self {
+        let (outcome, error_kind) = if result.is_success() {
+            (RunOutcome::Success, None)
+        } else if result.passed() == Some(false) {
+            (RunOutcome::Failure, Some("check".to_string()))
+        } else {
+            (RunOutcome::Failure, Some("status".to_string()))
+        };
+
+        Self { outcome, status_code: Some(result.status_code), error_kind, error_message: result.script_failure.clone(), data_id, timing: result.timing.clone() }
+    }
+
+    pub fn from_error(kind: ErrorKind, message: &str) -> Self {
+        let mut message = message.to_string();
+        if message.len() > MAX_ERROR_MESSAGE {
+            let mut end = MAX_ERROR_MESSAGE;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+        }
+
+        Self { outcome: RunOutcome::Error, status_code: None, error_kind: Some(kind.as_str().to_string()), error_message: Some(message), data_id: None, timing: None }
+    }
+}
+
+pub fn trigger_of(job: &Api) -> RunTrigger {
+    if job.matrix_row.is_some() {
+        RunTrigger::Matrix
+    } else if job.triggered_by.is_some() {
+        RunTrigger::Dependency
+    } else {
+        RunTrigger::Schedule
+    }
+}
+
+/// Tracks one worker attempt from start to the history row
+pub struct RunRecorder {
+    fetch_id: i32,
+    job_id: String,
+    attempt: i32,
+    trigger: RunTrigger,
+    worker_id: String,
+    started_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl RunRecorder {
+    pub fn start(job: &Api, job_id: String, attempt: usize, worker_id: String) -> Self {
+        Self {
+            fetch_id: job.id,
+            job_id,
+            attempt: attempt as i32,
+            trigger: trigger_of(job),
+            worker_id,
+            started_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Write the history row, failures are logged only
+    pub async fn finish(self, state: &AppState, report: RunReport) {
+        let run_repo = FetchRunRepository::new(state.database.clone());
+        let data = CreateApiRun {
+            fetch_id: self.fetch_id,
+            job_id: Some(self.job_id),
+            attempt: self.attempt,
+            trigger: self.trigger,
+            outcome: report.outcome,
+            status_code: report.status_code,
+            error_kind: report.error_kind,
+            error_message: report.error_message,
+            worker_id: Some(self.worker_id),
+            data_id: report.data_id,
+            started_at: self.started_at,
+            finished_at: Utc::now(),
+            duration_ms: self.started.elapsed().as_millis() as i64,
+            timing: report.timing.and_then(|t| serde_json::to_value(t).ok()),
+        };
+
+        if let Err(e) = run_repo.create(data).await {
+            tracing::warn!("[HISTORY] Failed record run of fetch {}: {:?}", self.fetch_id, e);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use apalis::prelude::Storage;
+use serde_json::{Map, Value};
+use crate::{
+    jobs::{blob, change, dependency, error::FetchError, history::RunReport, redact},
+    models::fetch::{Api, CreateApiData, FetchResult, MatrixOptions, MatrixRow, MatrixRowResult, UpstreamResult},
+    repository::fetch::{FetchDataRepository, FetchDatasetRepository, FetchMatrixRepository},
+    state::AppState,
+};
+
+pub const MAX_ROWS: usize = 1000;
+
+/// Row values are available as `{{row.NAME}}`
+pub const ROW_PREFIX: &str = "row.";
+
+/// Create a run summary and enqueue one job per row, upstream result passed to every row.
+/// Keyed by the fan out job, a retry reuses its run and only enqueues rows not enqueued yet
+pub async fn fan_out(state: &AppState, fetch: &Api, options: &MatrixOptions, upstream: Option<UpstreamResult>, job_id: &str) -> Result<i32, String> {
+    let rows = match (&options.rows, options.dataset_id) {
+        (Some(rows), _) => rows.clone(),
+        (None, Some(dataset_id)) => FetchDatasetRepository::new(state.database.clone())
+            .find_by_id(dataset_id)
+            .await.map_err(|e| format!("[MATRIX] Dataset {} not found: {}", dataset_id, e))?
+            .rows_list(),
+        (None, None) => Vec::new(),
+    };
+    if rows.is_empty() {
+        return Err(format!("[MATRIX] Fetch {} has no matrix rows", fetch.id));
+    }
+    let rows: Vec<_> = rows.into_iter().take(MAX_ROWS).collect();
+
+    let matrix_repo = FetchMatrixRepository::new(state.database.clone());
+    let run = matrix_repo.create(fetch.id, rows.len() as i32, job_id)
+        .await.map_err(|e| format!("[MATRIX] Failed create run: {}", e))?;
+
+    let mut queue = state.job_queue.clone();
+    let mut enqueued = 0;
+    for (index, values) in rows.into_iter().enumerate().take(run.total as usize) {
+        let claimed = matrix_repo.claim_row(run.id, index as i32)
+            .await.map_err(|e| format!("[MATRIX] Failed claim row {}: {}", index, e))?;
+        if !claimed {
+            continue;
+        }
+
+        let mut job = fetch.clone();
+        job.triggered_by = upstream.clone();
+        job.matrix_row = Some(MatrixRow { run_id: run.id, index, values });
+        if let Err(e) = queue.push(job).await {
+            if let Err(release) = matrix_repo.release_row(run.id, index as i32).await {
+                tracing::error!("[MATRIX] Failed release row {} of run {}: {}", index, run.id, release);
+            }
+            return Err(format!("[MATRIX] Failed enqueue row {}: {}", index, e));
+        }
+        enqueued += 1;
+    }
+    tracing::info!("[MATRIX] Fetch {} fan out into {} of {} jobs (run {})", fetch.id, enqueued, run.total, run.id);
+
+    Ok(run.id)
+}
+
+/// Store row response and count it in the run summary once per row.
+/// A failed store is logged on the row, only a failed count is returned for a retry
+pub async fn record(state: &AppState, fetch: &Api, row: &MatrixRow, response: Result<FetchResult, FetchError>) -> Result<RunReport, sqlx::Error> {
+    let data_repo = FetchDataRepository::new(state.database.clone());
+    let matrix_repo = FetchMatrixRepository::new(state.database.clone());
+
+    let (row_result, success, report) = match response {
+        Ok(mut result) => {
+            redact::apply(&redact::rules(state, fetch), &mut result);
+            let success = result.is_success();
+            let mut report = RunReport::from_result(&result, None);
+            let passed = result.passed();
+            let assertions = result.assertions.as_ref().and_then(|a| serde_json::to_value(a).ok());
+            let schema_errors = result.schema.as_ref().and_then(|check| serde_json::to_value(&check.errors).ok());
+            let index = Some(row.index as i32);
+            let stored = async {
+                // Change detection against the latest body of the same row
+                let (body_hash, changed, store) = change::detect(&data_repo, fetch, &result, index).await?;
+                if !store {
+                    return Ok(None);
+                }
+                let (response, blob_key, blob_size) = blob::spill(state, fetch, &result).await;
+                data_repo.create(CreateApiData {
+                    fetch_id: fetch.id,
+                    name: format!("{} [{}-run{}] row {}", fetch.name, fetch.id, row.run_id, row.index),
+                    status_code: Some(result.status_code),
+                    response: Some(response),
+                    response_headers: Some(result.headers.clone()),
+                    assertions,
+                    passed,
+                    schema_errors,
+                    body_hash,
+                    changed,
+                    content_type: result.content_type.clone(),
+                    encoding: Some(result.encoding),
+                    truncated: Some(result.truncated),
+                    blob_key,
+                    blob_size,
+                    matrix_row: index,
+                }).await.map(|data| Some(data.id))
+            }.await;
+            let row_result = match stored {
+                Ok(data_id) => {
+                    report.data_id = data_id;
+                    MatrixRowResult { index: row.index, status_code: Some(result.status_code), data_id, error: None }
+                },
+                Err(e) => {
+                    tracing::error!("[MATRIX] Failed store row {} of run {}: {}", row.index, row.run_id, e);
+                    let message = format!("Failed store data: {}", e);
+                    report.error_message = Some(message.clone());
+                    MatrixRowResult { index: row.index, status_code: Some(result.status_code), data_id: None, error: Some(message) }
+                }
+            };
+            (row_result, success, report)
+        },
+        Err(e) => {
+            tracing::warn!("[MATRIX] Row {} of run {} failed: {}", row.index, row.run_id, e);
+            let report = RunReport::from_error(e.kind, &e.message);
+            (MatrixRowResult { index: row.index, status_code: None, data_id: None, error: Some(e.message) }, false, report)
+        }
+    };
+
+    let result = serde_json::to_value(&row_result).unwrap_or(Value::Null);
+    let Some(run) = matrix_repo.record(row.run_id, row.index as i32, success, result).await? else {
+        tracing::warn!("[MATRIX] Row {} of run {} already counted", row.index, row.run_id);
+        return Ok(report);
+    };
+    // Only the row completing the run sees the counts reach the total, dependents fire once
+    if run.finished_at.is_some() && run.succeeded + run.failed == run.total {
+        tracing::info!("[MATRIX] Run {} finished: {} succeeded, {} failed", run.id, run.succeeded, run.failed);
+        dependency::trigger_downstream(state, fetch, dependency::matrix_result(fetch, &run), run.failed == 0).await;
+    }
+
+    Ok(report)
+}
+
+/// Parse CSV text, first line is the header
+pub fn parse_csv(text: &str) -> Result<Vec<Map<String, Value>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()
+        .map_err(|e| format!("Invalid CSV header: {}", e))?
+        .clone();
+
+    let mut rows = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Invalid CSV row {}: {}", line + 1, e))?;
+        let row: Map<String, Value> = headers.iter()
+            .zip(record.iter())
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect();
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Rows must be flat objects and within the limit
+pub fn validate_rows(rows: &[Map<String, Value>]) -> Result<(), String> {
+    if rows.is_empty() || rows.len() > MAX_ROWS {
+        return Err(format!("Matrix must have 1 to {} rows", MAX_ROWS));
+    }
+    for (index, row) in rows.iter().enumerate() {
+        if row.values().any(|v| v.is_array() || v.is_object()) {
+            return Err(format!("Matrix row {} must only have string, number or boolean values", index));
+        }
+    }
+
+    Ok(())
+}
+
+/// Exactly one source of rows
+pub fn validate(options: &MatrixOptions) -> Result<(), String> {
+    match (&options.rows, options.dataset_id) {
+        (Some(rows), None) => validate_rows(rows),
+        (None, Some(_)) => Ok(()),
+        _ => Err("Matrix require either rows or dataset_id".to_string()),
+    }
+}
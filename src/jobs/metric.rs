@@ -0,0 +1,74 @@
+use regex::Regex;
+use serde_json::Value;
+use crate::{models::fetch::{FetchResult, MetricExtractor, MetricKind, MetricSource, MetricValue}, utils::jsonpath};
+
+pub const MAX_METRICS: usize = 20;
+/// Buckets returned by one series query
+pub const MAX_POINTS: i64 = 10_000;
+const MAX_NAME: usize = 64;
+
+/// Run every extractor against the body, extractors without a match are left out
+pub fn extract(extractors: &[MetricExtractor], result: &FetchResult) -> Vec<MetricValue> {
+    let body: Option<Value> = serde_json::from_str(&result.response).ok();
+
+    extractors.iter()
+        .filter_map(|extractor| {
+            let raw = match &extractor.source {
+                MetricSource::Jsonpath { path } => jsonpath::select_first(body.as_ref()?, path).ok()??.clone(),
+                MetricSource::Regex { pattern, group } => {
+                    let captures = Regex::new(pattern).ok()?.captures(&result.response)?;
+                    let index = group.unwrap_or(if captures.len() > 1 { 1 } else { 0 });
+                    Value::String(captures.get(index)?.as_str().to_string())
+                },
+            };
+
+            let (value_num, value_text) = match extractor.kind {
+                MetricKind::Number => (Some(number(&raw)?), None),
+                MetricKind::String => (None, Some(match raw {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                })),
+            };
+            Some(MetricValue { name: extractor.name.trim().to_string(), value_num, value_text })
+        })
+        .collect()
+}
+
+/// JSON number, bool or numeric text
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Names unique, paths and patterns checked on save
+pub fn validate(extractors: &[MetricExtractor]) -> Result<(), String> {
+    if extractors.len() > MAX_METRICS {
+        return Err(format!("Fetch can have at most {} metrics", MAX_METRICS));
+    }
+    for (index, extractor) in extractors.iter().enumerate() {
+        let name = extractor.name.trim();
+        if name.is_empty() || name.len() > MAX_NAME {
+            return Err(format!("Metric {} name must be 1 to {} characters", index, MAX_NAME));
+        }
+        if extractors[..index].iter().any(|e| e.name.trim() == name) {
+            return Err(format!("Metric name '{}' is used more than once", name));
+        }
+        match &extractor.source {
+            MetricSource::Jsonpath { path } => {
+                jsonpath::parse(path).map_err(|e| format!("Metric '{}': {}", name, e))?;
+            },
+            MetricSource::Regex { pattern, group } => {
+                let re = Regex::new(pattern).map_err(|e| format!("Metric '{}': {}", name, e))?;
+                if let Some(group) = group && *group >= re.captures_len() {
+                    return Err(format!("Metric '{}' has no capture group {}", name, group));
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
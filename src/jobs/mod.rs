@@ -1,4 +1,25 @@
 pub mod workers;
 pub mod cleaner;
 pub mod rest;
-pub mod websocket;
\ No newline at end of file
+pub mod websocket;
+pub mod sse;
+pub mod probe;
+pub mod client;
+pub mod auth;
+pub mod secret;
+pub mod template;
+pub mod workflow;
+pub mod dependency;
+pub mod matrix;
+pub mod script;
+pub mod assertion;
+pub mod schema;
+pub mod history;
+pub mod error;
+pub mod timing;
+pub mod change;
+pub mod metric;
+pub mod retention;
+pub mod export;
+pub mod blob;
+pub mod redact;
@@ -0,0 +1,221 @@
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::{sync::{Arc, Mutex}, time::{Duration, Instant}};
+use tokio::{io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt}, net::TcpStream, time::timeout};
+use tokio_rustls::{TlsConnector, rustls::{self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme, client::{WebPkiServerVerifier, danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier}}, crypto::{CryptoProvider, ring}, pki_types::{CertificateDer, ServerName, UnixTime}}};
+use tracing::debug;
+use x509_parser::prelude::{FromDer, X509Certificate};
+use crate::{jobs::error::{ErrorKind, FetchError}, models::fetch::{FetchResult, ProbeCertificate, ProbeOptions, ProbeResult, ProbeTls}};
+
+const DEFAULT_BANNER_BYTES: usize = 1024;
+const DEFAULT_EXPIRY_DAYS: i64 = 14;
+
+#[derive(Clone)]
+pub struct ProbeJobs {
+    timeout_duration: Duration,
+    provider: Arc<CryptoProvider>,
+    roots: Arc<RootCertStore>,
+}
+
+impl ProbeJobs {
+    pub fn new(timeout: u64) -> Self {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+
+        Self {
+            timeout_duration: Duration::from_secs(timeout),
+            provider: Arc::new(ring::default_provider()),
+            roots: Arc::new(roots),
+        }
+    }
+
+    /// Connect to `host:port`, optionally TLS handshake, send payload and read banner
+    pub async fn request_response(&self, target: &str, payload: &Option<String>, options: Option<ProbeOptions>) -> Result<FetchResult, FetchError> {
+        let options = options.unwrap_or_default();
+        let timeout_duration = options.timeout.map(Duration::from_secs).unwrap_or(self.timeout_duration);
+        let (host, port) = split_host_port(target)?;
+
+        // Connect
+        let started = Instant::now();
+        let stream = timeout(timeout_duration, TcpStream::connect((host.as_str(), port)))
+            .await
+            .map_err(|_| FetchError::new(ErrorKind::Timeout, format!("[PROBE] Connect timeout to {}:{}", host, port)))?
+            .map_err(|e| FetchError::from_io(&format!("[PROBE] Failed connect to {}:{}", host, port), &e))?;
+        let connect_ms = started.elapsed().as_millis() as u64;
+        let address = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+        debug!("[PROBE] Connected to {} in {}ms", address, connect_ms);
+
+        let (tls, banner) = if options.tls {
+            let verifier = Arc::new(RecordingVerifier::new(self.roots.clone(), self.provider.clone())?);
+            let config = ClientConfig::builder_with_provider(self.provider.clone())
+                .with_safe_default_protocol_versions()
+                .map_err(|e| format!("[PROBE] Invalid TLS config: {}", e))?
+                .dangerous()
+                .with_custom_certificate_verifier(verifier.clone())
+                .with_no_client_auth();
+
+            let sni = options.server_name.clone().unwrap_or_else(|| host.clone());
+            let server_name = ServerName::try_from(sni)
+                .map_err(|e| format!("[PROBE] Invalid server name: {}", e))?;
+
+            let started = Instant::now();
+            let mut tls_stream = timeout(timeout_duration, TlsConnector::from(Arc::new(config)).connect(server_name, stream))
+                .await
+                .map_err(|_| FetchError::new(ErrorKind::Timeout, format!("[PROBE] TLS handshake timeout to {}:{}", host, port)))?
+                .map_err(|e| FetchError::new(ErrorKind::Connection, format!("[PROBE] TLS handshake failed: {}", e)))?;
+            let handshake_ms = started.elapsed().as_millis() as u64;
+
+            let (_, conn) = tls_stream.get_ref();
+            let version = conn.protocol_version().map(|v| format!("{:?}", v));
+            let cipher = conn.negotiated_cipher_suite().map(|c| format!("{:?}", c.suite()));
+            let certificate = conn.peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(|cert| parse_certificate(cert, options.expiry_days.unwrap_or(DEFAULT_EXPIRY_DAYS)));
+            let verify_error = verifier.error();
+
+            let banner = exchange(&mut tls_stream, payload, &options, timeout_duration).await?;
+            let tls = ProbeTls {
+                handshake_ms,
+                version,
+                cipher,
+                verified: verify_error.is_none(),
+                verify_error,
+                certificate,
+            };
+
+            (Some(tls), banner)
+        } else {
+            let mut stream = stream;
+            (None, exchange(&mut stream, payload, &options, timeout_duration).await?)
+        };
+
+        // 495 (SSL Certificate Error) so an invalid certificate is visible from status code
+        let status_code = match &tls {
+            Some(tls) if !tls.verified => 495,
+            _ => 200,
+        };
+
+        let result = ProbeResult { host, port, address, connect_ms, tls, banner };
+        let response = serde_json::to_string(&result)
+            .map_err(|e| format!("Failed encode probe result: {}", e))?;
+
+        Ok(FetchResult {
+            status_code,
+            headers: json!({}),
+            response,
+            ..Default::default()
+        })
+    }
+}
+
+/// Send payload and read banner when configured
+async fn exchange<S>(stream: &mut S, payload: &Option<String>, options: &ProbeOptions, timeout_duration: Duration) -> Result<Option<String>, FetchError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if let Some(payload) = payload
+        && !payload.is_empty() {
+        debug!("[PROBE] Sending payload...");
+        stream.write_all(payload.as_bytes())
+            .await
+            .map_err(|e| FetchError::from_io("[PROBE] Failed send payload", &e))?;
+        stream.flush()
+            .await
+            .map_err(|e| FetchError::from_io("[PROBE] Failed send payload", &e))?;
+    }
+
+    if !options.read_banner {
+        return Ok(None);
+    }
+
+    let mut buffer = vec![0u8; options.banner_bytes.unwrap_or(DEFAULT_BANNER_BYTES).max(1)];
+    match timeout(timeout_duration, stream.read(&mut buffer)).await {
+        Ok(Ok(size)) => Ok(Some(String::from_utf8_lossy(&buffer[..size]).to_string())),
+        Ok(Err(e)) => Err(FetchError::from_io("[PROBE] Failed read banner", &e)),
+        Err(_) => {
+            debug!("[PROBE] No banner received during the timeout period");
+            Ok(Some(String::new()))
+        }
+    }
+}
+
+/// Split `host:port`, ipv6 must be written as `[::1]:443`
+pub fn split_host_port(target: &str) -> Result<(String, u16), String> {
+    let target = target.trim();
+    let target = target.split_once("://").map(|(_, rest)| rest).unwrap_or(target);
+    let (host, port) = target.rsplit_once(':')
+        .ok_or_else(|| format!("[PROBE] Endpoint must be host:port, got '{}'", target))?;
+    let port = port.trim_end_matches('/').parse::<u16>()
+        .map_err(|_| format!("[PROBE] Invalid port in '{}'", target))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(format!("[PROBE] Missing host in '{}'", target));
+    }
+
+    Ok((host.to_string(), port))
+}
+
+/// Read subject and validity from leaf certificate
+pub fn parse_certificate(cert: &CertificateDer<'_>, expiry_days: i64) -> Option<ProbeCertificate> {
+    let (_, parsed) = X509Certificate::from_der(cert.as_ref()).ok()?;
+    let validity = parsed.validity();
+    let not_before = DateTime::<Utc>::from_timestamp(validity.not_before.timestamp(), 0)?;
+    let not_after = DateTime::<Utc>::from_timestamp(validity.not_after.timestamp(), 0)?;
+    let days_remaining = (not_after - Utc::now()).num_days();
+
+    Some(ProbeCertificate {
+        subject: parsed.subject().to_string(),
+        issuer: parsed.issuer().to_string(),
+        not_before,
+        not_after,
+        days_remaining,
+        expiring_soon: days_remaining < expiry_days,
+    })
+}
+
+/// Accept every certificate so expired/invalid ones can still be inspected,
+/// the webpki verification result is recorded instead.
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+    error: Mutex<Option<String>>,
+}
+
+impl RecordingVerifier {
+    fn new(roots: Arc<RootCertStore>, provider: Arc<CryptoProvider>) -> Result<Self, String> {
+        let inner = WebPkiServerVerifier::builder_with_provider(roots, provider.clone())
+            .build()
+            .map_err(|e| format!("[PROBE] Invalid TLS verifier: {}", e))?;
+
+        Ok(Self { inner, provider, error: Mutex::new(None) })
+    }
+
+    fn error(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|e| e.clone())
+    }
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, intermediates: &[CertificateDer<'_>], server_name: &ServerName<'_>, ocsp_response: &[u8], now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+        if let Err(e) = self.inner.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+            && let Ok(mut error) = self.error.lock() {
+            *error = Some(e.to_string());
+        }
+
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
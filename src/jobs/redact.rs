@@ -0,0 +1,126 @@
+use regex::Regex;
+use serde_json::{Map, Value};
+use crate::{models::fetch::{Api, BodyEncoding, FetchResult, MASKED_VALUE, RedactionOptions, WorkflowStepResult}, state::AppState, utils::jsonpath};
+
+pub const MAX_RULES: usize = 50;
+
+/// Global rules plus the rules of the fetch
+pub fn rules(state: &AppState, fetch: &Api) -> RedactionOptions {
+    fetch.parsed_options().redaction
+        .unwrap_or_default()
+        .with(&state.app_config.redaction)
+}
+
+/// Remove and mask sensitive values of the response before it is stored
+pub fn apply(rules: &RedactionOptions, result: &mut FetchResult) {
+    if rules.is_empty() {
+        return;
+    }
+    let patterns = compile(rules);
+
+    // Binary bodies are base64, nothing to match in them
+    if result.encoding == BodyEncoding::Text {
+        if !rules.paths.is_empty()
+            && let Ok(mut value) = serde_json::from_str::<Value>(&result.response) {
+            let removed: usize = rules.paths.iter()
+                .filter_map(|path| jsonpath::remove(&mut value, path).ok())
+                .sum();
+            if removed > 0 {
+                result.response = value.to_string();
+            }
+        }
+        result.response = mask(&patterns, &result.response);
+    }
+
+    mask_headers(rules, &patterns, &mut result.headers);
+    if let Some(reason) = &mut result.script_failure {
+        *reason = mask(&patterns, reason);
+    }
+}
+
+/// Copy of request headers safe to write in logs
+pub fn headers(rules: &RedactionOptions, headers: &Option<Value>) -> Value {
+    let mut headers = headers.clone().unwrap_or(Value::Null);
+    mask_headers(rules, &compile(rules), &mut headers);
+
+    headers
+}
+
+/// Text masked with the rules patterns, for error messages passed on
+pub fn text(rules: &RedactionOptions, text: &str) -> String {
+    mask(&compile(rules), text)
+}
+
+/// Script variables masked before they are saved in the fetch
+pub fn variables(rules: &RedactionOptions, variables: &mut Map<String, Value>) {
+    if rules.is_empty() {
+        return;
+    }
+    let patterns = compile(rules);
+    for value in variables.values_mut() {
+        mask_value(&patterns, value);
+    }
+}
+
+/// Step of a workflow run, stored in the run body
+pub fn step(rules: &RedactionOptions, step: &mut WorkflowStepResult) {
+    let mut result = FetchResult {
+        response: std::mem::take(&mut step.response),
+        headers: std::mem::take(&mut step.headers),
+        ..Default::default()
+    };
+    apply(rules, &mut result);
+    step.response = result.response;
+    step.headers = result.headers;
+}
+
+fn mask_value(patterns: &[(Regex, String)], value: &mut Value) {
+    match value {
+        Value::String(text) => *text = mask(patterns, text),
+        Value::Array(items) => items.iter_mut().for_each(|item| mask_value(patterns, item)),
+        Value::Object(map) => map.values_mut().for_each(|item| mask_value(patterns, item)),
+        _ => {}
+    }
+}
+
+fn mask_headers(rules: &RedactionOptions, patterns: &[(Regex, String)], headers: &mut Value) {
+    let Value::Object(map) = headers else { return };
+    for (name, value) in map.iter_mut() {
+        if rules.headers.iter().any(|denied| denied.eq_ignore_ascii_case(name)) {
+            *value = Value::String(MASKED_VALUE.to_string());
+        } else if let Value::String(text) = value {
+            *text = mask(patterns, text);
+        }
+    }
+}
+
+fn mask(patterns: &[(Regex, String)], text: &str) -> String {
+    patterns.iter().fold(text.to_string(), |text, (regex, replacement)| {
+        regex.replace_all(&text, replacement.as_str()).into_owned()
+    })
+}
+
+/// Rules are validated on save, an invalid global pattern is skipped
+fn compile(rules: &RedactionOptions) -> Vec<(Regex, String)> {
+    rules.patterns.iter()
+        .filter_map(|p| Regex::new(&p.pattern).ok().map(|regex| (regex, p.replacement.clone().unwrap_or_else(|| MASKED_VALUE.to_string()))))
+        .collect()
+}
+
+/// Paths and patterns must parse
+pub fn validate(rules: &RedactionOptions) -> Result<(), String> {
+    if rules.paths.len() + rules.patterns.len() + rules.headers.len() > MAX_RULES {
+        return Err(format!("Redaction allows at most {} rules", MAX_RULES));
+    }
+    for path in &rules.paths {
+        jsonpath::parse(path).map_err(|e| format!("Redaction: {}", e))?;
+    }
+    for pattern in &rules.patterns {
+        Regex::new(&pattern.pattern).map_err(|e| format!("Redaction pattern '{}': {}", pattern.pattern, e))?;
+    }
+    if rules.headers.iter().any(|name| name.trim().is_empty()) {
+        return Err("Redaction header name is empty".to_string());
+    }
+
+    Ok(())
+}
@@ -1,32 +1,31 @@
-use reqwest::{Client, Method};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::{Client, Method, RequestBuilder, Response, multipart};
 use serde_json::{Map, Value};
-use crate::{models::fetch::{ApiMethod, FetchResult}, utils::reqwest::json_to_headermap};
+use std::time::Instant;
+use crate::{jobs::{error::{ErrorKind, FetchError}, timing}, models::fetch::{ApiBody, ApiMethod, BodyLimit, FetchResult, LimitAction, MultipartPart, RunTiming}, utils::{body, reqwest::{json_to_headermap, json_to_query, value_to_string}}};
 
-pub async fn request_response(http_client: Client, target_url: &str, method: &Option<ApiMethod>, payload: &Option<String>, headers: Option<Value>) -> Result<FetchResult, String> {
+#[allow(clippy::too_many_arguments)]
+pub async fn request_response(http_client: Client, target_url: &str, req_method: Method, payload: &Option<String>, headers: Option<Value>, query: &Option<Value>, body: &Option<ApiBody>, limit: BodyLimit) -> Result<FetchResult, FetchError> {
     let headers_map = json_to_headermap(headers).await; 
 
-    let req_method = match method {
-        Some(ApiMethod::Get) => Method::GET,
-        Some(ApiMethod::Post) => Method::POST,
-        Some(ApiMethod::Put) => Method::PUT,
-        Some(ApiMethod::Delete) => Method::DELETE,
-        Some(ApiMethod::Patch) => Method::PATCH,
-        None => Method::GET, 
-        // _ => Method::GET,
-    };
-
     let mut request_builder = http_client
         .request(req_method, target_url)
         .headers(headers_map);
 
-    if let Some(payload) = payload {
-        if !payload.is_empty() {
-             request_builder = request_builder.body(payload.clone());
-        }
+    let query_pairs = json_to_query(query);
+    if !query_pairs.is_empty() {
+        request_builder = request_builder.query(&query_pairs);
     }
 
-    let response = request_builder.send()
-        .await.map_err(|e| format!("Failed send message: {}", e))?;
+    request_builder = apply_body(request_builder, payload, body)?;
+    let request = request_builder.build()
+        .map_err(|e| FetchError::from_reqwest("Failed build request", &e))?;
+    let request_bytes = request.body().and_then(|b| b.as_bytes()).map(|b| b.len() as u64);
+
+    let started = Instant::now();
+    let (response, net) = timing::capture(http_client.execute(request)).await;
+    let response = response.map_err(|e| FetchError::from_reqwest("Failed send message", &e))?;
+    let ttfb = started.elapsed();
 
     let status_obj = response.status();
     let status_code = status_obj.as_u16() as i16;
@@ -39,14 +38,119 @@ pub async fn request_response(http_client: Client, target_url: &str, method: &Op
     }
     let response_headers_json = Value::Object(response_headers_map);
 
-    let res_text = response.text()
-        .await.map_err(|e| format!("Failed response message: {}", e))?;
+    let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let (res_bytes, truncated) = read_limited(response, limit).await?;
+    let (res_text, encoding) = body::decode(content_type.as_deref(), &res_bytes);
 
+    let timing = RunTiming {
+        connect_ms: net.connect.map(timing::millis),
+        ttfb_ms: Some(timing::millis(ttfb)),
+        total_ms: Some(timing::millis(started.elapsed())),
+        request_bytes,
+        response_bytes: Some(res_bytes.len() as u64),
+        ..Default::default()
+    };
     let result = FetchResult { 
-        status_code: status_code,
+        status_code,
         headers: response_headers_json,
-        response: res_text
+        response: res_text,
+        content_type,
+        encoding,
+        truncated,
+        timing: Some(timing),
+        ..Default::default()
     };
 
     Ok(result)
-}
\ No newline at end of file
+}
+
+/// Read the body chunk by chunk, stop or fail once past `limit`
+pub async fn read_limited(mut response: Response, limit: BodyLimit) -> Result<(Vec<u8>, bool), FetchError> {
+    let max = limit.max_bytes as usize;
+    if limit.action == LimitAction::Fail && response.content_length().is_some_and(|len| len > limit.max_bytes) {
+        return Err(FetchError::new(ErrorKind::Request, format!("Response body exceeds {} bytes", max)));
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk()
+        .await.map_err(|e| FetchError::from_reqwest("Failed response message", &e))? {
+        if bytes.len() + chunk.len() <= max {
+            bytes.extend_from_slice(&chunk);
+            continue;
+        }
+        if limit.action == LimitAction::Fail {
+            return Err(FetchError::new(ErrorKind::Request, format!("Response body exceeds {} bytes", max)));
+        }
+        bytes.extend_from_slice(&chunk[..max - bytes.len()]);
+
+        // Drop a character cut in half so the text still decodes
+        if let Err(e) = std::str::from_utf8(&bytes) && e.error_len().is_none() {
+            bytes.truncate(e.valid_up_to());
+        }
+        return Ok((bytes, true));
+    }
+
+    Ok((bytes, false))
+}
+
+/// Map fetch method to reqwest method, custom method use the stored verb
+pub fn to_method(method: &Option<ApiMethod>, custom_method: &Option<String>) -> Result<Method, String> {
+    let req_method = match method {
+        Some(ApiMethod::Get) => Method::GET,
+        Some(ApiMethod::Post) => Method::POST,
+        Some(ApiMethod::Put) => Method::PUT,
+        Some(ApiMethod::Delete) => Method::DELETE,
+        Some(ApiMethod::Patch) => Method::PATCH,
+        Some(ApiMethod::Head) => Method::HEAD,
+        Some(ApiMethod::Options) => Method::OPTIONS,
+        Some(ApiMethod::Custom) => {
+            let verb = custom_method.as_deref()
+                .ok_or_else(|| "Custom method is empty".to_string())?;
+            Method::from_bytes(verb.as_bytes())
+                .map_err(|_| format!("Invalid custom method: {}", verb))?
+        },
+        None => Method::GET,
+    };
+
+    Ok(req_method)
+}
+
+/// Set request body by mode, raw mode (or no body) send payload as it is
+fn apply_body(request_builder: RequestBuilder, payload: &Option<String>, body: &Option<ApiBody>) -> Result<RequestBuilder, String> {
+    let request_builder = match body {
+        None | Some(ApiBody::Raw) => match payload {
+            Some(payload) if !payload.is_empty() => request_builder.body(payload.clone()),
+            _ => request_builder,
+        },
+        Some(ApiBody::Json { json }) => request_builder.json(json),
+        Some(ApiBody::Form { fields }) => {
+            let pairs: Vec<(String, String)> = fields.iter()
+                .map(|(k, v)| (k.clone(), value_to_string(v)))
+                .collect();
+            request_builder.form(&pairs)
+        },
+        Some(ApiBody::Multipart { parts }) => {
+            let mut form = multipart::Form::new();
+            for part in parts {
+                form = match part {
+                    MultipartPart::Text { name, value } => form.text(name.clone(), value.clone()),
+                    MultipartPart::File { name, filename, content_type, content } => {
+                        let bytes = STANDARD.decode(content)
+                            .map_err(|e| format!("Invalid base64 file part '{}': {}", name, e))?;
+                        let mut file = multipart::Part::bytes(bytes).file_name(filename.clone());
+                        if let Some(mime) = content_type {
+                            file = file.mime_str(mime)
+                                .map_err(|e| format!("Invalid content type of part '{}': {}", name, e))?;
+                        }
+                        form.part(name.clone(), file)
+                    },
+                };
+            }
+            request_builder.multipart(form)
+        },
+    };
+
+    Ok(request_builder)
+}
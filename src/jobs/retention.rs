@@ -0,0 +1,129 @@
+use std::time::Duration;
+use crate::{
+    models::fetch::RetentionOptions,
+    repository::fetch::{FetchDataRepository, FetchMetricRepository, FetchRepository, FetchRunRepository},
+    state::AppState,
+};
+
+// Rows deleted by one pass
+#[derive(Debug, Default, PartialEq)]
+pub struct Pruned {
+    pub data: u64,
+    pub runs: u64,
+    pub metrics: u64,
+}
+
+/// Prune fetch_api_data, fetch_runs and fetch_metrics by the retention of every fetch
+pub async fn start_data_pruner(state: AppState, interval: u64, batch: i64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval));
+
+    loop {
+        interval.tick().await;
+
+        match prune_all(&state, batch).await {
+            Ok(pruned) if pruned == Pruned::default() => tracing::debug!("[RETENTION] Nothing to prune."),
+            Ok(pruned) => tracing::info!("[RETENTION] Deleted {} data rows, {} runs and {} metrics.", pruned.data, pruned.runs, pruned.metrics),
+            Err(e) => tracing::error!("[RETENTION] Failed to prune data: {:?}", e),
+        }
+    }
+}
+
+async fn prune_all(state: &AppState, batch: i64) -> Result<Pruned, sqlx::Error> {
+    let fetch_repo = FetchRepository::new(state.database.clone());
+    let data_repo = FetchDataRepository::new(state.database.clone());
+    let run_repo = FetchRunRepository::new(state.database.clone());
+    let metric_repo = FetchMetricRepository::new(state.database.clone());
+    let mut pruned = Pruned::default();
+
+    for fetch in fetch_repo.get_all_fetch().await? {
+        let policy = fetch.parsed_options().retention
+            .unwrap_or_default()
+            .or(&state.app_config.retention);
+        if policy.is_empty() {
+            continue;
+        }
+
+        match prune(&data_repo, fetch.id, &policy, batch).await {
+            Ok(keys) => {
+                pruned.data += keys.len() as u64;
+                let keys: Vec<String> = keys.into_iter().flatten().collect();
+                if let Some(store) = &state.blob_store {
+                    store.delete_all(&keys).await;
+                }
+            },
+            Err(e) => tracing::warn!("[RETENTION] Failed to prune fetch {}: {:?}", fetch.id, e),
+        }
+        match prune_history(&run_repo, &metric_repo, fetch.id, &policy, batch).await {
+            Ok((runs, metrics)) => {
+                pruned.runs += runs;
+                pruned.metrics += metrics;
+            },
+            Err(e) => tracing::warn!("[RETENTION] Failed to prune history of fetch {}: {:?}", fetch.id, e),
+        }
+    }
+
+    Ok(pruned)
+}
+
+/// Delete in batches so one large fetch never holds a long lock, blob keys of the deleted rows returned
+pub async fn prune(data_repo: &FetchDataRepository, fetch_id: i32, policy: &RetentionOptions, batch: i64) -> Result<Vec<Option<String>>, sqlx::Error> {
+    let mut deleted = Vec::new();
+
+    if let Some(days) = policy.max_age_days {
+        deleted.extend(drain(batch, || data_repo.prune_older_than(fetch_id, days, batch)).await?);
+    }
+    if let Some(keep) = policy.keep_last {
+        deleted.extend(drain(batch, || data_repo.prune_keep_last(fetch_id, keep, batch)).await?);
+    }
+    if let Some(max_bytes) = policy.max_bytes {
+        deleted.extend(drain(batch, || data_repo.prune_max_bytes(fetch_id, max_bytes, batch)).await?);
+    }
+
+    Ok(deleted)
+}
+
+/// Runs and metrics by age and count, `max_bytes` only applies to stored data
+pub async fn prune_history(run_repo: &FetchRunRepository, metric_repo: &FetchMetricRepository, fetch_id: i32, policy: &RetentionOptions, batch: i64) -> Result<(u64, u64), sqlx::Error> {
+    let (mut runs, mut metrics) = (0, 0);
+
+    if let Some(days) = policy.max_age_days {
+        runs += drain(batch, || run_repo.prune_older_than(fetch_id, days, batch)).await?.len() as u64;
+        metrics += drain(batch, || metric_repo.prune_older_than(fetch_id, days, batch)).await?.len() as u64;
+    }
+    if let Some(keep) = policy.keep_last {
+        runs += drain(batch, || run_repo.prune_keep_last(fetch_id, keep, batch)).await?.len() as u64;
+        metrics += drain(batch, || metric_repo.prune_keep_last(fetch_id, keep, batch)).await?.len() as u64;
+    }
+
+    Ok((runs, metrics))
+}
+
+/// Repeat a batch delete until it comes back short
+async fn drain<T, F, Fut>(batch: i64, mut step: F) -> Result<Vec<T>, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, sqlx::Error>>,
+{
+    let mut deleted = Vec::new();
+    loop {
+        let keys = step().await?;
+        let count = keys.len();
+        deleted.extend(keys);
+        if (count as i64) < batch {
+            return Ok(deleted);
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Limits must be positive
+pub fn validate(options: &RetentionOptions) -> Result<(), String> {
+    let fields = [("keep_last", options.keep_last), ("max_age_days", options.max_age_days), ("max_bytes", options.max_bytes)];
+    for (name, value) in fields {
+        if let Some(value) = value && value < 1 {
+            return Err(format!("Retention {} must be at least 1", name));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use serde_json::Value;
+use crate::models::fetch::{SchemaCheck, SchemaError, SchemaOptions};
+
+/// Errors kept per run
+const MAX_ERRORS: usize = 100;
+
+/// Validate body against the schema, non JSON body checked as a string like `ApiDataResponse`
+pub fn check(options: &SchemaOptions, body: &str) -> SchemaCheck {
+    let enforced = options.fail_run.unwrap_or(true);
+    let validator = match jsonschema::validator_for(&options.document) {
+        Ok(validator) => validator,
+        Err(e) => {
+            let error = SchemaError { instance_path: String::new(), schema_path: e.schema_path().to_string(), message: format!("Invalid schema: {}", e) };
+            return SchemaCheck { enforced, errors: vec![error] };
+        }
+    };
+    let instance: Value = serde_json::from_str(body)
+        .unwrap_or_else(|_| Value::String(body.to_string()));
+
+    let errors = validator.iter_errors(&instance)
+        .take(MAX_ERRORS)
+        .map(|e| SchemaError {
+            instance_path: e.instance_path().to_string(),
+            schema_path: e.schema_path().to_string(),
+            message: e.to_string(),
+        })
+        .collect();
+
+    SchemaCheck { enforced, errors }
+}
+
+/// Schema must compile, remote and file references are not resolved
+pub fn validate(options: &SchemaOptions) -> Result<(), String> {
+    jsonschema::validator_for(&options.document)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid JSON Schema: {}", e))
+}
@@ -0,0 +1,218 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, module_resolvers::DummyModuleResolver, serde::{from_dynamic, to_dynamic}};
+use ring::hmac;
+use serde_json::{Map, Value, json};
+use std::time::{Duration, Instant};
+use crate::{jobs::template::validate_variables, models::fetch::{Api, ApiMethod, FetchResult, ScriptOptions}, utils::crypto::{hex, sha256_hex}};
+
+pub const MAX_SCRIPT_SIZE: usize = 64 * 1024;
+pub const DEFAULT_TIMEOUT_MS: u64 = 1000;
+pub const MAX_TIMEOUT_MS: u64 = 10_000;
+const MAX_OPERATIONS: u64 = 5_000_000;
+const MAX_VALUE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Sandboxed engine, no module import, no eval and bounded by time and operations
+fn engine(timeout: Duration) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(MAX_VALUE_SIZE);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+
+    let started = Instant::now();
+    engine.on_progress(move |_| (started.elapsed() > timeout).then(|| Dynamic::from("Script timed out")));
+    engine.on_print(|text| tracing::info!("[SCRIPT] {}", text));
+    engine.on_debug(|text, _, _| tracing::debug!("[SCRIPT] {}", text));
+
+    engine.register_fn("sha256", |text: &str| sha256_hex(text.as_bytes()));
+    engine.register_fn("hmac_sha256", |key: &str, text: &str| hex(hmac_sha256(key, text).as_ref()));
+    engine.register_fn("hmac_sha256_base64", |key: &str, text: &str| STANDARD.encode(hmac_sha256(key, text)));
+    engine.register_fn("base64_encode", |text: &str| STANDARD.encode(text));
+    engine.register_fn("base64_decode", |text: &str| -> Result<String, Box<EvalAltResult>> {
+        let bytes = STANDARD.decode(text).map_err(|e| e.to_string())?;
+        Ok(String::from_utf8(bytes).map_err(|e| e.to_string())?)
+    });
+    engine.register_fn("json_parse", |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        to_dynamic(value)
+    });
+    engine.register_fn("json_stringify", |value: Dynamic| -> Result<String, Box<EvalAltResult>> {
+        let value: Value = from_dynamic(&value)?;
+        Ok(value.to_string())
+    });
+    engine.register_fn("unix_now", || chrono::Utc::now().timestamp());
+    engine.register_fn("unix_now_ms", || chrono::Utc::now().timestamp_millis());
+
+    engine
+}
+
+fn hmac_sha256(key: &str, text: &str) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes()), text.as_bytes())
+}
+
+fn timeout(options: &ScriptOptions) -> Duration {
+    Duration::from_millis(options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS).min(MAX_TIMEOUT_MS))
+}
+
+/// Run script with the given scope values, returns them back after the run
+fn eval(script: &str, timeout: Duration, inputs: Vec<(&'static str, Value)>) -> Result<Map<String, Value>, String> {
+    let engine = engine(timeout);
+    let mut scope = Scope::new();
+    for (name, value) in &inputs {
+        scope.push_dynamic(*name, to_dynamic(value).map_err(|e| e.to_string())?);
+    }
+
+    engine.run_with_scope(&mut scope, script).map_err(|e| match *e {
+        EvalAltResult::ErrorRuntime(reason, _) => reason.to_string(),
+        e => e.to_string(),
+    })?;
+
+    inputs.iter()
+        .map(|(name, _)| {
+            let value = scope.get(name).ok_or_else(|| format!("Script removed '{}'", name))?;
+            let value: Value = from_dynamic(value).map_err(|e| format!("Invalid '{}' after script: {}", name, e))?;
+            Ok((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Pre-request script, may change method, url, headers, query, payload and body
+pub fn pre_request(options: &ScriptOptions, fetch: &mut Api, headers: &mut Option<Value>) -> Result<(), String> {
+    let Some(script) = &options.pre_request else {
+        return Ok(());
+    };
+    let method = match (&fetch.method, &fetch.custom_method) {
+        (Some(ApiMethod::Custom), Some(custom)) => custom.to_uppercase(),
+        (Some(method), _) => serde_json::to_value(method).ok().and_then(|v| v.as_str().map(str::to_uppercase)).unwrap_or_default(),
+        (None, _) => "GET".to_string(),
+    };
+    let request = json!({
+        "method": method,
+        "url": fetch.endpoint,
+        "headers": headers.clone().unwrap_or_else(|| json!({})),
+        "query": fetch.query.clone().unwrap_or_else(|| json!({})),
+        "payload": fetch.payload,
+        "body": fetch.body,
+    });
+
+    let mut output = eval(script, timeout(options), vec![("request", request)])
+        .map_err(|e| format!("Pre-request script failed: {}", e))?;
+    let request = output.remove("request").and_then(|v| match v {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }).ok_or("Pre-request script must keep 'request' a map")?;
+
+    if let Some(Value::String(method)) = request.get("method") {
+        match serde_json::from_value::<ApiMethod>(Value::String(method.to_lowercase())) {
+            Ok(ApiMethod::Custom) | Err(_) => {
+                fetch.method = Some(ApiMethod::Custom);
+                fetch.custom_method = Some(method.clone());
+            },
+            Ok(method) => fetch.method = Some(method),
+        }
+    }
+    if let Some(Value::String(url)) = request.get("url") {
+        fetch.endpoint = url.clone();
+    }
+    *headers = request.get("headers").filter(|v| v.is_object()).cloned();
+    fetch.query = request.get("query").filter(|v| v.is_object()).cloned();
+    fetch.payload = request.get("payload").and_then(|v| match v {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    });
+    fetch.body = request.get("body").filter(|v| !v.is_null()).cloned();
+
+    Ok(())
+}
+
+/// Post-response script, returns variables added or changed by the script.
+/// `response.failed = true` stores the run as failed checks, `throw` is an error and retried.
+pub fn post_response(options: &ScriptOptions, result: &mut FetchResult, variables: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+    let Some(script) = &options.post_response else {
+        return Ok(Map::new());
+    };
+    let response = json!({
+        "status": result.status_code,
+        "headers": result.headers,
+        "body": result.response,
+        "failed": false,
+        "reason": "",
+    });
+
+    let mut output = eval(script, timeout(options), vec![("response", response), ("variables", Value::Object(variables.clone()))])
+        .map_err(|e| format!("Post-response script failed: {}", e))?;
+
+    if let Some(Value::Object(response)) = output.remove("response") {
+        if let Some(status) = response.get("status").and_then(Value::as_i64) {
+            result.status_code = i16::try_from(status).map_err(|_| format!("Invalid status {} from script", status))?;
+        }
+        if let Some(headers) = response.get("headers").filter(|v| v.is_object()) {
+            result.headers = headers.clone();
+        }
+        match response.get("body") {
+            Some(Value::String(body)) => result.response = body.clone(),
+            Some(Value::Null) | None => {},
+            Some(other) => result.response = other.to_string(),
+        }
+        if response.get("failed").and_then(Value::as_bool) == Some(true) {
+            let reason = response.get("reason").and_then(Value::as_str).filter(|r| !r.is_empty());
+            result.script_failure = Some(reason.unwrap_or("Marked failed by post-response script").to_string());
+        }
+    }
+
+    let changed: Map<String, Value> = match output.remove("variables") {
+        Some(Value::Object(after)) => after.into_iter()
+            .filter(|(name, value)| variables.get(name) != Some(value))
+            .collect(),
+        _ => Map::new(),
+    };
+    validate_variables(&changed).map_err(|e| format!("Post-response script: {}", e))?;
+
+    Ok(changed)
+}
+
+/// Size, timeout and compile check
+pub fn validate(options: &ScriptOptions) -> Result<(), String> {
+    if let Some(timeout) = options.timeout_ms
+        && (timeout == 0 || timeout > MAX_TIMEOUT_MS) {
+        return Err(format!("Script timeout must be 1 to {} ms", MAX_TIMEOUT_MS));
+    }
+    for (name, script) in [("pre_request", &options.pre_request), ("post_response", &options.post_response)] {
+        let Some(script) = script else {
+            continue;
+        };
+        if script.len() > MAX_SCRIPT_SIZE {
+            return Err(format!("Script {} exceeds {} bytes", name, MAX_SCRIPT_SIZE));
+        }
+        engine(timeout(options)).compile(script)
+            .map_err(|e| format!("Script {} invalid: {}", name, e))?;
+    }
+
+    Ok(())
+}
+
+/// Pre-request script off the async runtime
+pub async fn run_pre_request(options: &ScriptOptions, mut fetch: Api, mut headers: Option<Value>) -> Result<(Api, Option<Value>), String> {
+    if options.pre_request.is_none() {
+        return Ok((fetch, headers));
+    }
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || pre_request(&options, &mut fetch, &mut headers).map(|_| (fetch, headers)))
+        .await.map_err(|e| format!("Pre-request script aborted: {}", e))?
+}
+
+/// Post-response script off the async runtime
+pub async fn run_post_response(options: &ScriptOptions, mut result: FetchResult, variables: &Map<String, Value>) -> Result<(FetchResult, Map<String, Value>), String> {
+    if options.post_response.is_none() {
+        return Ok((result, Map::new()));
+    }
+    let options = options.clone();
+    let variables = variables.clone();
+    tokio::task::spawn_blocking(move || post_response(&options, &mut result, &variables).map(|changed| (result, changed)))
+        .await.map_err(|e| format!("Post-response script aborted: {}", e))?
+}
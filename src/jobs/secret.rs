@@ -0,0 +1,151 @@
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::{collections::{BTreeSet, HashMap}, sync::LazyLock};
+use crate::{models::{fetch::Api, secret::Secret}, repository::secret::SecretRepository, utils::crypto::SecretCipher};
+
+static SECRET_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*secret\.([A-Za-z0-9_\-]+)\s*\}\}").expect("valid secret pattern")
+});
+
+static MARKER_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x01secret:([0-9a-f]*):([A-Za-z0-9_\-]+)\x01").expect("valid marker pattern")
+});
+
+/// Names of every `{{secret.NAME}}` in endpoint, payload, headers, query and body
+pub fn referenced_names(fetch: &Api, headers: &Option<Value>) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut collect = |text: &str| {
+        for caps in SECRET_PATTERN.captures_iter(text) {
+            names.insert(caps[1].to_string());
+        }
+    };
+
+    collect(&fetch.endpoint);
+    if let Some(payload) = &fetch.payload {
+        collect(payload);
+    }
+    for value in [headers, &fetch.query, &fetch.body].into_iter().flatten() {
+        walk_strings(value, &mut collect);
+    }
+
+    names
+}
+
+/// Secret placeholders of the stored definition, held as markers only this run knows
+#[derive(Debug, Clone)]
+pub struct SecretRefs {
+    nonce: String,
+    names: BTreeSet<String>,
+}
+
+/// Swap `{{secret.NAME}}` of the stored request for run markers before templates render,
+/// placeholders that show up afterwards come from rendered values
+pub fn protect(fetch: &mut Api, headers: &mut Option<Value>) -> SecretRefs {
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let names = referenced_names(fetch, headers);
+    let _ = map_fields(fetch, headers, &mut |text| {
+        Ok(SECRET_PATTERN.replace_all(text, |caps: &Captures| marker(&nonce, &caps[1])).into_owned())
+    });
+
+    SecretRefs { nonce, names }
+}
+
+fn marker(nonce: &str, name: &str) -> String {
+    format!("\u{1}secret:{}:{}\u{1}", nonce, name)
+}
+
+/// Any secret placeholder in endpoint, payload, headers, query, body or options
+pub fn references_secrets(fetch: &Api, headers: &Option<Value>) -> bool {
+    let mut found = !referenced_names(fetch, headers).is_empty();
+    if let Some(options) = &fetch.options {
+        walk_strings(options, &mut |text| found |= SECRET_PATTERN.is_match(text));
+    }
+
+    found
+}
+
+/// Load and decrypt secrets of the stored definition, owned by the creator of the fetch
+pub async fn resolve(cipher: Option<&SecretCipher>, secret_repo: &SecretRepository, refs: &SecretRefs, fetch: &mut Api, headers: &mut Option<Value>) -> Result<(), String> {
+    if let Some(name) = referenced_names(fetch, headers).into_iter().next() {
+        return Err(format!("Secret '{}' comes from a rendered value, only the stored fetch may reference secrets", name));
+    }
+    if refs.names.is_empty() {
+        return Ok(());
+    }
+    let cipher = cipher.ok_or("Fetch references secrets but SECRET_KEY is not configured".to_string())?;
+    let creator = fetch.created_by.ok_or("Fetch has no creator to resolve secrets of".to_string())?;
+
+    let names: Vec<String> = refs.names.iter().cloned().collect();
+    let rows = secret_repo.find_by_names(creator, &names)
+        .await.map_err(|e| format!("Failed load secrets: {}", e))?;
+
+    let mut secrets = HashMap::new();
+    for row in rows {
+        let value = cipher.decrypt(&row.ciphertext, row.key_version, &Secret::aad(row.user_id, &row.name))
+            .map_err(|e| format!("Secret '{}': {}", row.name, e))?;
+        secrets.insert(row.name, value);
+    }
+
+    map_fields(fetch, headers, &mut |text| {
+        let mut error = None;
+        let rendered = MARKER_PATTERN.replace_all(text, |caps: &Captures| {
+            match (caps[1] == *refs.nonce, secrets.get(&caps[2])) {
+                (true, Some(value)) => value.clone(),
+                (true, None) => {
+                    error.get_or_insert_with(|| format!("Secret '{}' not found", &caps[2]));
+                    String::new()
+                },
+                (false, _) => {
+                    error.get_or_insert_with(|| "Secret marker comes from a rendered value".to_string());
+                    String::new()
+                },
+            }
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(rendered.into_owned()),
+        }
+    })
+}
+
+/// Apply `f` to endpoint, payload and every string of headers, query and body
+fn map_fields(fetch: &mut Api, headers: &mut Option<Value>, f: &mut impl FnMut(&str) -> Result<String, String>) -> Result<(), String> {
+    fetch.endpoint = f(&fetch.endpoint)?;
+    if let Some(payload) = &fetch.payload {
+        fetch.payload = Some(f(payload)?);
+    }
+    for value in [headers, &mut fetch.query, &mut fetch.body].into_iter().flatten() {
+        map_strings(value, f)?;
+    }
+
+    Ok(())
+}
+
+fn map_strings(value: &mut Value, f: &mut impl FnMut(&str) -> Result<String, String>) -> Result<(), String> {
+    match value {
+        Value::String(text) => *text = f(text)?,
+        Value::Array(items) => {
+            for item in items {
+                map_strings(item, f)?;
+            }
+        },
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                map_strings(item, f)?;
+            }
+        },
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn walk_strings(value: &Value, f: &mut impl FnMut(&str)) {
+    match value {
+        Value::String(text) => f(text),
+        Value::Array(items) => items.iter().for_each(|item| walk_strings(item, f)),
+        Value::Object(map) => map.values().for_each(|item| walk_strings(item, f)),
+        _ => {}
+    }
+}
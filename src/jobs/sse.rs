@@ -0,0 +1,218 @@
+use futures_util::StreamExt;
+use reqwest::{Client, Method, header::{ACCEPT, CACHE_CONTROL, HeaderValue}};
+use serde_json::{Map, Value};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::debug;
+use crate::{jobs::{error::{ErrorKind, FetchError}, rest}, models::fetch::{ApiMethod, BodyLimit, FetchResult, LimitAction, SseEvent, SseOptions}, utils::{body, reqwest::json_to_headermap}};
+
+#[derive(Clone)]
+pub struct SseJobs {
+    http_client: Client,
+    timeout_duration: Duration,
+    max_events: usize,
+}
+
+impl SseJobs {
+    pub fn new(http_client: Client, timeout: u64, max_events: usize) -> Self {
+        Self {
+            http_client,
+            timeout_duration: Duration::from_secs(timeout),
+            max_events,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn request_response(&self, target_url: &str, method: &Option<ApiMethod>, payload: &Option<String>, headers: Option<Value>, options: Option<SseOptions>, last_event_id: Option<String>, limit: BodyLimit) -> Result<FetchResult, FetchError> {
+        let options = options.unwrap_or_default();
+        let timeout_duration = options.timeout.map(Duration::from_secs).unwrap_or(self.timeout_duration);
+        let max_events = options.max_events.unwrap_or(self.max_events).max(1);
+
+        let mut headers_map = json_to_headermap(headers).await;
+        headers_map.insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+        headers_map.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        if let Some(id) = last_event_id
+            && let Ok(value) = HeaderValue::from_str(&id) {
+            debug!("[SSE] Resume from Last-Event-ID {}", id);
+            headers_map.insert("last-event-id", value);
+        }
+
+        let req_method = match method {
+            Some(ApiMethod::Post) => Method::POST,
+            _ => Method::GET,
+        };
+
+        // Client timeout is shorter than a stream, give the stream its own limit
+        let mut request_builder = self.http_client
+            .request(req_method, target_url)
+            .headers(headers_map)
+            .timeout(timeout_duration + Duration::from_secs(10));
+
+        if let Some(payload) = payload
+            && !payload.is_empty() {
+            request_builder = request_builder.body(payload.clone());
+        }
+
+        let response = request_builder.send()
+            .await.map_err(|e| FetchError::from_reqwest("Failed send message", &e))?;
+
+        let status_obj = response.status();
+        let status_code = status_obj.as_u16() as i16;
+
+        let mut response_headers_map = Map::new();
+        for (k, v) in response.headers() {
+            let val_str = v.to_str().unwrap_or("").to_string();
+            response_headers_map.insert(k.to_string(), Value::String(val_str));
+        }
+        let response_headers_json = Value::Object(response_headers_map);
+
+        // Error response is not a stream, store as it is within the body limit
+        if !status_obj.is_success() {
+            let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let (res_bytes, truncated) = rest::read_limited(response, limit).await?;
+            let (res_text, encoding) = body::decode(content_type.as_deref(), &res_bytes);
+
+            return Ok(FetchResult { status_code, headers: response_headers_json, response: res_text, content_type, encoding, truncated, ..Default::default() });
+        }
+
+        // Collect events (Logic Timeout / Max events)
+        let mut parser = SseParser::default();
+        let mut events: Vec<SseEvent> = Vec::new();
+        let mut received = 0u64;
+        let mut truncated = false;
+        let mut stream = response.bytes_stream();
+        let sleep_timer = sleep(timeout_duration);
+        tokio::pin!(sleep_timer);
+
+        'listen: loop {
+            tokio::select! {
+                chunk = stream.next() => {
+                    match chunk {
+                        Some(Ok(bytes)) => {
+                            // Events completed before the limit are kept, the chunk past it is dropped
+                            received += bytes.len() as u64;
+                            if received > limit.max_bytes {
+                                if limit.action == LimitAction::Fail {
+                                    return Err(FetchError::new(ErrorKind::Request, format!("Response body exceeds {} bytes", limit.max_bytes)));
+                                }
+                                debug!("[SSE] Body limit reached, stopping listener.");
+                                truncated = true;
+                                break 'listen;
+                            }
+                            for event in parser.feed(&bytes) {
+                                debug!("[SSE] Collecting event {}", event.event);
+                                events.push(event);
+                                if events.len() >= max_events {
+                                    debug!("[SSE] Max events reached, stopping listener.");
+                                    break 'listen;
+                                }
+                            }
+                        }
+                        Some(Err(e)) => return Err(FetchError::from_reqwest("[SSE] Error", &e)),
+                        // Incomplete event at the end of stream is discarded
+                        None => break,
+                    }
+                }
+                _ = &mut sleep_timer => {
+                    debug!("[SSE] Timeout reached, stopping listener.");
+                    break;
+                }
+            }
+        }
+
+        if events.is_empty() {
+            debug!("[SSE] No events received during the timeout period");
+        }
+        let response = serde_json::to_string(&events)
+            .map_err(|e| format!("Failed encode events: {}", e))?;
+
+        Ok(FetchResult {
+            status_code,
+            headers: response_headers_json,
+            response,
+            truncated,
+            ..Default::default()
+        })
+    }
+}
+
+/// Get the last event id from a previous stored run (JSON array of events)
+pub fn last_event_id(stored_response: &str) -> Option<String> {
+    let events: Vec<SseEvent> = serde_json::from_str(stored_response).ok()?;
+    events.into_iter().rev().find_map(|e| e.id)
+}
+
+/// Incremental text/event-stream parser
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseParser {
+    /// Feed raw bytes, returns every event completed by this chunk
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n' || *b == b'\r') {
+            // CRLF counts as a single line break
+            let mut end = pos + 1;
+            if self.buffer[pos] == b'\r' {
+                match self.buffer.get(pos + 1) {
+                    Some(b'\n') => end += 1,
+                    None => break,
+                    _ => {}
+                }
+            }
+
+            let line: Vec<u8> = self.buffer.drain(..end).take(pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(SseEvent {
+            event: event.filter(|e| !e.is_empty()).unwrap_or_else(|| "message".to_string()),
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+        })
+    }
+}
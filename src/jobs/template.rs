@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc, format::{Item, StrftimeItems}};
+use rand::Rng;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use std::sync::LazyLock;
+use uuid::Uuid;
+use crate::{jobs::matrix::ROW_PREFIX, models::fetch::{Api, UpstreamResult}, repository::fetch::{FetchEnvironmentRepository, FetchRunRepository}, utils::jsonpath};
+
+static TEMPLATE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([^{}]+?)\s*\}\}").expect("valid template pattern")
+});
+
+/// Built-in variable names, user variables can't shadow them
+pub const BUILTIN_VARIABLES: [&str; 5] = ["now", "last_run_at", "uuid", "random_int", "run_number"];
+
+/// Placeholder prefixes rendered later by another resolver
+const PASSTHROUGH_PREFIXES: [&str; 1] = ["secret."];
+
+/// Upstream values, empty when the run was not triggered by an upstream fetch
+const UPSTREAM_PREFIX: &str = "upstream.";
+
+const DEFAULT_RANDOM_MAX: i64 = 1_000_000;
+
+/// Values available to templates of one run
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub now: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub run_number: i64,
+    pub variables: Map<String, Value>,
+}
+
+impl TemplateContext {
+    /// Build context from the run history of the fetch, `job_id` is the running job so its retries keep the number.
+    /// Fetch variables override variables of its environment, variables saved by scripts override both.
+    pub async fn load(run_repo: &FetchRunRepository, env_repo: &FetchEnvironmentRepository, fetch: &Api, job_id: Option<&str>) -> Result<Self, String> {
+        let (run_count, last_run_at) = run_repo.previous_runs(fetch.id, job_id)
+            .await.map_err(|e| format!("Failed load previous runs: {}", e))?;
+
+        let mut variables = match fetch.environment_id {
+            Some(env_id) => env_repo.find_by_id(env_id)
+                .await.map_err(|e| format!("Failed load environment {}: {}", env_id, e))?
+                .variables_map(),
+            None => Map::new(),
+        };
+        variables.extend(fetch.parsed_options().variables.unwrap_or_default());
+        if let Some(Value::Object(saved)) = run_repo.script_variables(fetch.id)
+            .await.map_err(|e| format!("Failed load script variables: {}", e))? {
+            variables.extend(saved);
+        }
+
+        Ok(Self {
+            now: Utc::now(),
+            last_run_at,
+            run_number: run_count + 1,
+            variables,
+        })
+    }
+
+    /// Expose matrix row values as `{{row.NAME}}`
+    pub fn with_row(&mut self, values: &Map<String, Value>) {
+        for (key, value) in values {
+            self.variables.insert(format!("{}{}", ROW_PREFIX, key), value.clone());
+        }
+    }
+
+    /// Expose upstream run as `{{upstream.id}}`, `{{upstream.status_code}}`, `{{upstream.response}}`...
+    pub fn with_upstream(&mut self, upstream: &UpstreamResult) {
+        let values = [
+            ("id", Value::from(upstream.id)),
+            ("name", Value::from(upstream.name.clone())),
+            ("status_code", upstream.status_code.map(Value::from).unwrap_or(Value::Null)),
+            ("response", Value::from(upstream.response.clone())),
+            ("error", upstream.error.clone().map(Value::from).unwrap_or(Value::Null)),
+        ];
+        for (key, value) in values {
+            self.variables.insert(format!("{}{}", UPSTREAM_PREFIX, key), value);
+        }
+    }
+}
+
+/// Render templates in endpoint, payload, header values, query and body
+pub fn render(fetch: &mut Api, headers: &mut Option<Value>, ctx: &TemplateContext) -> Result<(), String> {
+    fetch.endpoint = render_text(&fetch.endpoint, ctx)?;
+    if let Some(payload) = &fetch.payload {
+        fetch.payload = Some(render_text(payload, ctx)?);
+    }
+    for value in [headers, &mut fetch.query, &mut fetch.body].into_iter().flatten() {
+        render_value(value, ctx)?;
+    }
+
+    Ok(())
+}
+
+/// Render one string, `{{name | filter}}` where filter is optional
+pub fn render_text(text: &str, ctx: &TemplateContext) -> Result<String, String> {
+    let mut error = None;
+    let rendered = TEMPLATE_PATTERN.replace_all(text, |caps: &Captures| {
+        match evaluate(&caps[1], ctx) {
+            Ok(Some(value)) => value,
+            Ok(None) => caps[0].to_string(),
+            Err(e) => {
+                error.get_or_insert(e);
+                String::new()
+            }
+        }
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(rendered.into_owned()),
+    }
+}
+
+/// Variables names must not collide with built-ins and hold scalar values
+pub fn validate_variables(variables: &Map<String, Value>) -> Result<(), String> {
+    for (name, value) in variables {
+        if BUILTIN_VARIABLES.contains(&name.as_str()) || name.contains('.') || name.contains('|') || name.trim().is_empty() {
+            return Err(format!("Invalid variable name '{}'", name));
+        }
+        if value.is_array() || value.is_object() {
+            return Err(format!("Variable '{}' must be a string, number or boolean", name));
+        }
+    }
+
+    Ok(())
+}
+
+fn evaluate(expression: &str, ctx: &TemplateContext) -> Result<Option<String>, String> {
+    let (name, filter) = match expression.split_once('|') {
+        Some((name, filter)) => (name.trim(), Some(filter.trim())),
+        None => (expression.trim(), None),
+    };
+    if PASSTHROUGH_PREFIXES.iter().any(|p| name.starts_with(p)) {
+        return Ok(None);
+    }
+
+    let value = match name {
+        "now" => format_time(&ctx.now, filter)?,
+        "last_run_at" => match &ctx.last_run_at {
+            Some(time) => format_time(time, filter)?,
+            None => String::new(),
+        },
+        "uuid" => match filter {
+            Some("v7") => Uuid::now_v7().to_string(),
+            _ => Uuid::new_v4().to_string(),
+        },
+        "random_int" => random_int(filter)?,
+        "run_number" => ctx.run_number.to_string(),
+        _ => match (ctx.variables.get(name), filter) {
+            (Some(value), Some(path)) if path.starts_with('$') => select_json(value, path)?,
+            (Some(Value::String(value)), _) => value.clone(),
+            (Some(Value::Null), _) => String::new(),
+            (Some(value), _) => value.to_string(),
+            (None, _) if name.starts_with(UPSTREAM_PREFIX) => String::new(),
+            (None, _) => return Err(format!("Unknown template variable '{}'", name)),
+        },
+    };
+
+    Ok(Some(value))
+}
+
+/// JSONPath filter, string variable is parsed as JSON first
+fn select_json(value: &Value, path: &str) -> Result<String, String> {
+    let parsed;
+    let value = match value {
+        Value::String(text) => {
+            parsed = serde_json::from_str::<Value>(text).unwrap_or(Value::Null);
+            &parsed
+        },
+        value => value,
+    };
+
+    Ok(match jsonpath::select_first(value, path)? {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    })
+}
+
+/// `rfc3339` (default), `unix`, `unix_ms`, `date` or a strftime pattern
+fn format_time(time: &DateTime<Utc>, filter: Option<&str>) -> Result<String, String> {
+    let value = match filter.unwrap_or("rfc3339") {
+        "rfc3339" => time.to_rfc3339(),
+        "unix" => time.timestamp().to_string(),
+        "unix_ms" => time.timestamp_millis().to_string(),
+        "date" => time.format("%Y-%m-%d").to_string(),
+        pattern => {
+            if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("Invalid time format '{}'", pattern));
+            }
+            time.format(pattern).to_string()
+        }
+    };
+
+    Ok(value)
+}
+
+/// Optional range filter `min..max` (inclusive)
+fn random_int(filter: Option<&str>) -> Result<String, String> {
+    let (min, max) = match filter {
+        Some(range) => {
+            let (min, max) = range.split_once("..")
+                .ok_or_else(|| format!("Invalid random_int range '{}', use min..max", range))?;
+            let min = min.trim().parse::<i64>().map_err(|_| format!("Invalid random_int range '{}'", range))?;
+            let max = max.trim().parse::<i64>().map_err(|_| format!("Invalid random_int range '{}'", range))?;
+            if min > max {
+                return Err(format!("Invalid random_int range '{}'", range));
+            }
+            (min, max)
+        },
+        None => (0, DEFAULT_RANDOM_MAX),
+    };
+
+    Ok(rand::thread_rng().gen_range(min..=max).to_string())
+}
+
+fn render_value(value: &mut Value, ctx: &TemplateContext) -> Result<(), String> {
+    match value {
+        Value::String(text) => *text = render_text(text, ctx)?,
+        Value::Array(items) => {
+            for item in items {
+                render_value(item, ctx)?;
+            }
+        },
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                render_value(item, ctx)?;
+            }
+        },
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::{future::Future, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}, time::{Duration, Instant}};
+use tower::{Layer, Service};
+
+tokio::task_local! {
+    static CURRENT: Arc<Mutex<NetTiming>>;
+}
+
+/// Phases seen by the http client while a request runs inside `capture`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetTiming {
+    /// DNS, TCP and TLS of a new connection, the connector opens them as one step
+    pub connect: Option<Duration>,
+}
+
+/// Run future with a timing collector, phases of pooled connections stay empty
+pub async fn capture<F: Future>(future: F) -> (F::Output, NetTiming) {
+    let collector = Arc::new(Mutex::new(NetTiming::default()));
+    let output = CURRENT.scope(collector.clone(), future).await;
+    let timing = collector.lock().map(|t| *t).unwrap_or_default();
+
+    (output, timing)
+}
+
+fn record(update: impl FnOnce(&mut NetTiming)) {
+    let _ = CURRENT.try_with(|collector| {
+        if let Ok(mut timing) = collector.lock() {
+            update(&mut timing);
+        }
+    });
+}
+
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
+
+/// Connector layer that records the time to open a new connection
+#[derive(Debug, Clone, Default)]
+pub struct TimingLayer;
+
+impl<S> Layer<S> for TimingLayer {
+    type Service = TimingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimingService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimingService<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimingService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = future.await;
+            record(|t| t.connect = Some(started.elapsed()));
+            result
+        })
+    }
+}
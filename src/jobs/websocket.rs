@@ -1,10 +1,10 @@
 use futures_util::{SinkExt, StreamExt};
 use serde_json::{Value, json};
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, time::{Duration, Instant}};
 use tokio::time::sleep;
-use tokio_tungstenite::{connect_async, tungstenite::{client::IntoClientRequest, protocol::Message}};
+use tokio_tungstenite::{connect_async, tungstenite::{self, client::IntoClientRequest, protocol::Message}};
 use crate::utils::reqwest::json_to_headermap;
-use crate::models::fetch::FetchResult;
+use crate::{jobs::{error::{ErrorKind, FetchError}, timing}, models::fetch::{BodyLimit, FetchResult, LimitAction, RunTiming}};
 use tracing::debug;
 
 
@@ -20,18 +20,20 @@ impl WsJobs {
         }
     }
 
-    pub async fn request_response(&self, target_url: &str, payload: &Option<String>, headers: Option<Value>) -> Result<FetchResult, String> {
+    pub async fn request_response(&self, target_url: &str, payload: &Option<String>, headers: Option<Value>, limit: BodyLimit) -> Result<FetchResult, FetchError> {
         let mut request = target_url
             .into_client_request()
-            .map_err(|e| format!("Invalid URL or Request: {}", e))?;
+            .map_err(|e| FetchError::new(ErrorKind::Request, format!("Invalid URL or Request: {}", e)))?;
 
         let header_map = json_to_headermap(headers).await;
         request.headers_mut().extend(header_map);
 
         // Connect
+        let started = Instant::now();
         let (ws_stream, response) = connect_async(request)
             .await
-            .map_err(|e| format!("Failed connect to websocket: {}", e))?;
+            .map_err(|e| ws_error("Failed connect to websocket", &e))?;
+        let connect = started.elapsed();
 
         let status_obj = response.status();
         let status_code = status_obj.as_u16() as i16;
@@ -50,28 +52,50 @@ impl WsJobs {
             write
                 .send(Message::Text(msg_content.into()))
                 .await
-                .map_err(|e| format!("Failed send message: {}", e))?;
+                .map_err(|e| ws_error("Failed send message", &e))?;
         } else {
             debug!("[WS] No payload provided, directly listening...");
         }
 
         // Collect response (Logic Timeout)
         let mut collected_messages: Vec<String> = Vec::new();
+        let mut first_message = None;
+        let mut response_bytes = 0u64;
+        let mut truncated = false;
         let sleep_timer = sleep(self.timeout_duration);
         tokio::pin!(sleep_timer);
 
         loop {
             tokio::select! {
                 msg = read.next() => {
+                    if matches!(msg, Some(Ok(Message::Text(_) | Message::Binary(_)))) {
+                        first_message.get_or_insert_with(|| started.elapsed() - connect);
+                    }
+                    // Messages before the limit are kept, the frame past it is dropped
+                    let size = match &msg {
+                        Some(Ok(Message::Text(text))) => text.len() as u64,
+                        Some(Ok(Message::Binary(data))) => data.len() as u64,
+                        _ => 0,
+                    };
+                    if response_bytes + size > limit.max_bytes {
+                        if limit.action == LimitAction::Fail {
+                            return Err(FetchError::new(ErrorKind::Request, format!("Response body exceeds {} bytes", limit.max_bytes)));
+                        }
+                        debug!("[WS] Body limit reached, stopping listener.");
+                        truncated = true;
+                        break;
+                    }
                     match msg {
                         Some(Ok(Message::Text(text))) => {
                             debug!("[WS] Collecting websocket response...");
+                            response_bytes += text.len() as u64;
                             collected_messages.push(text.to_string());
                         }
-                        Some(Ok(Message::Binary(_))) => {
+                        Some(Ok(Message::Binary(data))) => {
+                            response_bytes += data.len() as u64;
                             collected_messages.push("[Binary Data]".to_string());
                         }
-                        Some(Err(e)) => return Err(format!("[WS] Error: {}", e)),
+                        Some(Err(e)) => return Err(ws_error("[WS] Error", &e)),
                         None => break,
                         _ => {}
                     }
@@ -88,12 +112,34 @@ impl WsJobs {
         }
         let response = collected_messages.join("\n---\n");
 
+        let timing = RunTiming {
+            connect_ms: Some(timing::millis(connect)),
+            first_message_ms: first_message.map(timing::millis),
+            total_ms: Some(timing::millis(started.elapsed())),
+            request_bytes: payload.as_ref().map(|p| p.len() as u64),
+            response_bytes: Some(response_bytes),
+            ..Default::default()
+        };
         let result = FetchResult{
-            status_code: status_code,
+            status_code,
             headers: json!(server_headers),
-            response: response,
+            response,
+            truncated,
+            timing: Some(timing),
+            ..Default::default()
         };
 
         Ok(result)
     }
-}
\ No newline at end of file
+}
+
+/// Socket and TLS failures are connection errors, the rest comes from the exchange itself
+fn ws_error(context: &str, e: &tungstenite::Error) -> FetchError {
+    match e {
+        tungstenite::Error::Io(io) => FetchError::from_io(context, io),
+        tungstenite::Error::Tls(_) | tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            FetchError::new(ErrorKind::Connection, format!("{}: {}", context, e))
+        },
+        _ => FetchError::new(ErrorKind::Request, format!("{}: {}", context, e)),
+    }
+}
@@ -1,11 +1,13 @@
 use apalis::prelude::*;
 use apalis_sql::context::SqlContext;
-use crate::jobs::rest;
-use crate::models::fetch::{ApiType, FetchResult};
-use crate::{models::fetch::{Api, CreateApiData}, repository::fetch::{FetchDataRepository, FetchExecuteRepository, FetchHeaderRepository, FetchRepository}, services::fetch::FetchService, state::AppState};
+use serde_json::Value;
+use std::time::Instant;
+use crate::jobs::{assertion, auth, blob, change, timing, dependency, error::{self, ErrorKind, FetchError}, history::{RunRecorder, RunReport}, matrix, metric, redact, rest, schema, script, secret, sse, template::{self, TemplateContext}, workflow};
+use crate::models::fetch::{ApiType, FetchResult, RunTiming};
+use crate::{models::fetch::{Api, CreateApiData}, repository::{fetch::{FetchAuthRepository, FetchDataRepository, FetchEnvironmentRepository, FetchExecuteRepository, FetchHeaderRepository, FetchMetricRepository, FetchRepository, FetchRunRepository}, secret::SecretRepository}, services::fetch::FetchService, state::AppState};
 
 pub async fn setup_background_workers(state: AppState,) {
-    let concurrency = state.app_config.concurrency.clone();
+    let concurrency = state.app_config.concurrency;
     tokio::spawn(async move {
         Monitor::new()
             .register(
@@ -21,19 +23,183 @@ pub async fn setup_background_workers(state: AppState,) {
     });
 }
 
-async fn worker_jobs(job: Api, mut ctx: SqlContext, state: Data<AppState>) -> Result<(), anyhow::Error> {
+async fn worker_jobs(job: Api, mut ctx: SqlContext, state: Data<AppState>, attempt: Attempt, task_id: TaskId, worker: Worker<Context>) -> Result<(), anyhow::Error> {
+    let max_attempts = ctx.max_attempts().max(1) as usize;
     ctx.set_max_attempts(10);
 
+    // Every attempt lands in the run history
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string());
+    let recorder = RunRecorder::start(&job, task_id.to_string(), attempt.current(), format!("{}@{}", worker.id(), host));
+    let result = process(&state, &job, &task_id.to_string()).await;
+    let report = match &result {
+        Ok(report) => report.clone(),
+        Err(e) => RunReport::from_error(error::kind_of(e), &e.to_string()),
+    };
+    recorder.finish(&state, report).await;
+
+    // Failed run triggers dependents once, when apalis gives up on it
+    if let Err(e) = &result
+        && job.matrix_row.is_none()
+        && dependency::is_final(true, attempt.current(), max_attempts) {
+        let upstream = dependency::upstream_result(&redact::rules(&state, &job), &job, Err(&e.to_string()));
+        dependency::trigger_downstream(&state, &job, upstream, false).await;
+    }
+
+    result.map(|_| ())
+}
+
+async fn process(state: &AppState, job: &Api, job_id: &str) -> Result<RunReport, anyhow::Error> {
     // Service data
     let execute_repo = FetchExecuteRepository::new(state.database.clone());
     let fetch_repo = FetchRepository::new(state.database.clone());
+    let fetch_service = FetchService::new(state.clone());
+    let fetch_api = fetch_repo.get_by_id(&job.id).await?;
+
+    // Outcome for dependents, decided by the request alone
+    let mut downstream = None;
+
+    // Matrix fetch, scheduled run only fan out into row jobs
+    let report = if let (None, Some(matrix)) = (&job.matrix_row, fetch_api.parsed_options().matrix) {
+        matrix::fan_out(state, &fetch_api, &matrix, job.triggered_by.clone(), job_id)
+            .await.map_err(|e| anyhow::anyhow!(e))?;
+        RunReport::empty()
+    } else {
+        let response = run(state, job, &fetch_api, job_id).await;
+
+        if let Some(row) = &job.matrix_row {
+            return Ok(matrix::record(state, &fetch_api, row, response).await?);
+        }
+
+        // Save data, redacted before anything of it is stored
+        let mut result = match response {
+            Ok(result) => result,
+            Err(e) => return Err(e.into()),
+        };
+        let redaction = redact::rules(state, &fetch_api);
+        redact::apply(&redaction, &mut result);
+
+        match fetch_api.r#type {
+            ApiType::Rest => tracing::info!("[HTTP] Done request to {}. [{}]", &fetch_api.endpoint, result.status_code,),
+            ApiType::Websocket => tracing::info!("[WS] Done request to {}. [{}]", &fetch_api.endpoint, result.status_code,),
+            ApiType::Sse => tracing::info!("[SSE] Done request to {}. [{}]", &fetch_api.endpoint, result.status_code,),
+            ApiType::Probe => tracing::info!("[PROBE] Done probe to {}. [{}]", &fetch_api.endpoint, result.status_code,),
+            ApiType::Workflow => tracing::info!("[WORKFLOW] Done workflow {}. [{}]", &fetch_api.name, result.status_code,),
+        }
+
+        // Failed checks count as a failed run for dependents, the schedule keeps going
+        if result.passed() == Some(false) {
+            let failed = result.assertions.iter().flatten().filter(|a| !a.passed).count();
+            let schema_errors = result.schema.as_ref().map_or(0, |check| check.errors.len());
+            tracing::warn!("[ASSERT] Fetch {} failed {} assertion(s), {} schema error(s)", fetch_api.id, failed, schema_errors);
+        }
+        if let Some(reason) = &result.script_failure {
+            tracing::warn!("[SCRIPT] Fetch {} marked failed: {}", fetch_api.id, reason);
+        }
+        downstream = Some((dependency::upstream_result(&redaction, &fetch_api, Ok(&result)), result.is_success()));
+
+        // Request already went out, a storage error must not retry it
+        let mut report = RunReport::from_result(&result, None);
+        match store(state, &fetch_api, result).await {
+            Ok(data_id) => report.data_id = data_id,
+            Err(e) => {
+                tracing::error!("[FETCH] Failed store data of fetch {}: {:?}", fetch_api.id, e);
+                report.error_message = Some(format!("Failed store data: {}", e));
+            },
+        }
+        report
+    };
+
+    // Create repeatable jobs, triggered run keep the schedule of the fetch untouched
+    let execute = execute_repo.find_by_id(fetch_api.execute_id).await;
+    match execute {
+        Ok(execute) if execute.is_repeat && job.triggered_by.is_none() => {
+            match fetch_service.create_apalis_job(&fetch_api, execute).await {
+                Ok(job_id) => if let Err(e) = fetch_repo.update_job_id(fetch_api.id, job_id).await {
+                    tracing::error!("[FETCH] Failed update job id of fetch {}: {:?}", fetch_api.id, e);
+                },
+                Err(e) => tracing::error!("[FETCH] Failed to create repeatable jobs of fetch {}: {:?}", fetch_api.id, e),
+            }
+        },
+        Ok(_) => {},
+        Err(e) => tracing::error!("[FETCH] Execute {} of fetch {} not found: {:?}", fetch_api.execute_id, fetch_api.id, e),
+    }
+
+    if let Some((upstream, success)) = downstream {
+        dependency::trigger_downstream(state, &fetch_api, upstream, success).await;
+    }
+
+    Ok(report)
+}
+
+/// Store the result unless change detection skips it, metrics recorded either way
+async fn store(state: &AppState, fetch_api: &Api, result: FetchResult) -> Result<Option<i32>, sqlx::Error> {
+    let data_repo = FetchDataRepository::new(state.database.clone());
+    let fetch_job_id = fetch_api.job_id.clone().unwrap_or_else(|| "unknown".to_string());
+    let name_data = format!("{} [{}-{}]", fetch_api.name, fetch_api.id, fetch_job_id);
+
+    let passed = result.passed();
+    let assertions = result.assertions.as_ref().and_then(|a| serde_json::to_value(a).ok());
+    let schema_errors = result.schema.as_ref().and_then(|check| serde_json::to_value(&check.errors).ok());
+    let metrics = fetch_api.parsed_options().metrics
+        .map(|extractors| metric::extract(&extractors, &result))
+        .unwrap_or_default();
+
+    // Change detection against the latest stored body
+    let (body_hash, changed, store) = change::detect(&data_repo, fetch_api, &result, None).await?;
+
+    let mut data_id = None;
+    if store {
+        let (response, blob_key, blob_size) = blob::spill(state, fetch_api, &result).await;
+        let response_data = CreateApiData {
+            fetch_id: fetch_api.id,
+            name: name_data,
+            status_code: Some(result.status_code),
+            content_type: result.content_type,
+            encoding: Some(result.encoding),
+            response: Some(response),
+            response_headers: Some(result.headers),
+            assertions,
+            passed,
+            schema_errors,
+            body_hash,
+            changed,
+            truncated: Some(result.truncated),
+            blob_key,
+            blob_size,
+            matrix_row: None,
+        };
+
+        data_id = Some(data_repo.create(response_data).await?.id);
+    }
+
+    // Metrics recorded every run, also when the body was not stored
+    if !metrics.is_empty() {
+        let metric_repo = FetchMetricRepository::new(state.database.clone());
+        if let Err(e) = metric_repo.create_many(fetch_api.id, data_id, &metrics).await {
+            tracing::warn!("[METRIC] Failed record metrics of fetch {}: {:?}", fetch_api.id, e);
+        }
+    }
+
+    Ok(data_id)
+}
+
+/// Resolve headers, auth, templates and secrets then run the request
+async fn run(state: &AppState, job: &Api, fetch_api: &Api, job_id: &str) -> Result<FetchResult, FetchError> {
     let header_repo = FetchHeaderRepository::new(state.database.clone());
     let data_repo = FetchDataRepository::new(state.database.clone());
-    let fetch_service = FetchService::new((*state).clone());
-    let fetch_api = fetch_repo.get_by_id(&job.id).await?;
+    let auth_repo = FetchAuthRepository::new(state.database.clone());
+    let secret_repo = SecretRepository::new(state.database.clone());
+    let env_repo = FetchEnvironmentRepository::new(state.database.clone());
+    let run_repo = FetchRunRepository::new(state.database.clone());
+
+    // Secrets in headers of another user are never resolved
+    let mut headers_trusted = true;
     let headers_json = if let Some(h_id) = fetch_api.header_id {
         match header_repo.find_by_id(h_id).await {
-            Ok(data) => Some(data.headers),
+            Ok(data) => {
+                headers_trusted = Some(data.user_id) == fetch_api.created_by;
+                Some(data.headers)
+            },
             Err(e) => {
                 tracing::warn!("Header ID {} not found: {:?}. Default.", h_id, e);
                 None
@@ -43,45 +209,114 @@ async fn worker_jobs(job: Api, mut ctx: SqlContext, state: Data<AppState>) -> Re
         None
     };
 
-    let response = match fetch_api.r#type {
-        ApiType::Rest => rest::request_response(state.http_client.clone(), &fetch_api.endpoint, &fetch_api.method, &fetch_api.payload, headers_json).await,
-        ApiType::Websocket => state.ws_client.request_response(&fetch_api.endpoint, &fetch_api.payload, headers_json).await,
-        
+    // Auth profile
+    let auth_profile = if let Some(a_id) = fetch_api.auth_id {
+        match auth_repo.find_by_id(a_id).await {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!("Auth ID {} not found: {:?}. Default.", a_id, e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
-    // Save data
-    let result = match response {
-        Ok(result) => FetchResult { status_code: result.status_code, headers: result.headers, response: result.response },
-        Err(msg) => return Err(anyhow::anyhow!(msg)),
+    // Templates and secrets resolved only here, stored fetch keeps the placeholders
+    let mut template_ctx = TemplateContext::load(&run_repo, &env_repo, fetch_api, Some(job_id))
+        .await.map_err(|e| FetchError::new(ErrorKind::Database, e))?;
+    if let Some(upstream) = &job.triggered_by {
+        template_ctx.with_upstream(upstream);
+    }
+    if let Some(row) = &job.matrix_row {
+        template_ctx.with_row(&row.values);
+    }
+    let mut rendered_request = fetch_api.clone();
+    let mut rendered_headers = headers_json.clone();
+    let secret_refs = match headers_trusted {
+        true => secret::protect(&mut rendered_request, &mut rendered_headers),
+        false => secret::protect(&mut rendered_request, &mut None),
     };
+    template::render(&mut rendered_request, &mut rendered_headers, &template_ctx)
+        .map_err(|e| FetchError::new(ErrorKind::Template, e))?;
+    secret::resolve(state.secret_cipher.as_ref(), &secret_repo, &secret_refs, &mut rendered_request, &mut rendered_headers)
+        .await.map_err(|e| FetchError::new(ErrorKind::Secret, e))?;
 
-    let fetch_id = fetch_api.id.clone();
-    let fetch_job_id = fetch_api.job_id.clone().unwrap_or_else(|| "unknown".to_string());
-    let name_data = format!("{} [{}-{}]", fetch_api.name,fetch_id, fetch_job_id);
+    let scripts = fetch_api.parsed_options().script.unwrap_or_default();
+    let matrix_row = job.matrix_row.as_ref().map(|row| row.index as i32);
+    // OAuth2 token rejected with 401 is refreshed and the request sent once more
+    let (response, latency) = auth::send(&state.http_client, &auth_repo, auth_profile.as_ref(), |auth| {
+        let mut request = rendered_request.clone();
+        let mut request_headers = rendered_headers.clone();
+        auth.apply(&mut request, &mut request_headers);
+        let (scripts, data_repo, template_ctx) = (&scripts, &data_repo, &template_ctx);
+        async move {
+            let (request, request_headers) = script::run_pre_request(scripts, request, request_headers)
+                .await.map_err(|e| FetchError::new(ErrorKind::Script, e))?;
+            tracing::debug!("[FETCH] Request headers of fetch {}: {}", fetch_api.id, redact::headers(&redact::rules(state, fetch_api), &request_headers));
+            let started = Instant::now();
+            let result = execute(state, &request, request_headers, data_repo, template_ctx, matrix_row).await?;
+            Ok((result, started.elapsed()))
+        }
+    }).await?;
 
-    match fetch_api.r#type {
-        ApiType::Rest => tracing::info!("[HTTP] Done request to {}. [{}]", &fetch_api.endpoint, result.status_code,),
-        ApiType::Websocket => tracing::info!("[WS] Done request to {}. [{}]", &fetch_api.endpoint, result.status_code,),
+    // Script variables kept apart from the fetch definition for next runs
+    let (mut result, mut variables) = script::run_post_response(&scripts, response, &template_ctx.variables)
+        .await.map_err(|e| FetchError::new(ErrorKind::Script, e))?;
+    if !variables.is_empty() {
+        redact::variables(&redact::rules(state, fetch_api), &mut variables);
+        if let Err(e) = run_repo.save_script_variables(fetch_api.id, Value::Object(variables)).await {
+            tracing::warn!("[SCRIPT] Failed save variables of fetch {}: {:?}", fetch_api.id, e);
+        }
     }
-    
-    let response_data = CreateApiData {
-        fetch_id: fetch_api.id,
-        name: name_data,
-        status_code: Some(result.status_code),
-        response: Some(result.response),
-        response_headers: Some(result.headers),
-    };
 
-    data_repo.create(response_data).await?;
+    if result.timing.is_none() {
+        result.timing = Some(RunTiming { total_ms: Some(timing::millis(latency)), ..Default::default() });
+    }
 
-    // Create repeatable jobs
-    let execute = execute_repo.find_by_id(fetch_api.execute_id).await?;
-    if execute.is_repeat {
-        let job_id = fetch_service.create_apalis_job(&fetch_api, execute)
-            .await.map_err(|e| anyhow::anyhow!("Failed to create repeatable jobs: {:?}", e))?;
-        let _ = fetch_repo.update_job_id(fetch_api.id, job_id)
-            .await.map_err(|e| anyhow::anyhow!("Failed to update job id: {:?}", e))?;
+    let options = fetch_api.parsed_options();
+    if let Some(assertions) = options.assertions.filter(|a| !a.is_empty()) {
+        result.assertions = Some(assertion::evaluate(&assertions, &result, latency));
+    }
+    if let Some(schema) = &options.schema {
+        result.schema = Some(schema::check(schema, &result.response));
     }
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(result)
+}
+
+/// Run request by fetch type
+async fn execute(state: &AppState, fetch_api: &Api, headers_json: Option<Value>, data_repo: &FetchDataRepository, template_ctx: &TemplateContext, matrix_row: Option<i32>) -> Result<FetchResult, FetchError> {
+    let limit = fetch_api.parsed_options().body_limit.unwrap_or_default().resolve(state.app_config.max_body_bytes);
+    match fetch_api.r#type {
+        ApiType::Rest => {
+            let method = rest::to_method(&fetch_api.method, &fetch_api.custom_method);
+            let client = state.http_clients.get(&fetch_api.parsed_options().client);
+            match (method, client) {
+                (Ok(method), Ok(client)) => rest::request_response(client, &fetch_api.endpoint, method, &fetch_api.payload, headers_json, &fetch_api.query, &fetch_api.parsed_body(), limit).await,
+                (Err(msg), _) | (_, Err(msg)) => Err(msg.into()),
+            }
+        },
+        ApiType::Websocket => state.ws_client.request_response(&fetch_api.endpoint, &fetch_api.payload, headers_json, limit).await,
+        ApiType::Sse => {
+            // Resume stream from previous stored events
+            let last_event_id = match data_repo.find_latest(fetch_api.id, matrix_row).await {
+                Ok(Some(data)) => match blob::body(state, &data).await {
+                    Ok((events, _)) => sse::last_event_id(&events),
+                    Err(e) => {
+                        tracing::warn!("Last events of fetch {} not loaded: {}. Default.", fetch_api.id, e);
+                        None
+                    }
+                },
+                Ok(None) => None,
+                Err(e) => {
+                    tracing::warn!("Last event of fetch {} not found: {:?}. Default.", fetch_api.id, e);
+                    None
+                }
+            };
+            state.sse_client.request_response(&fetch_api.endpoint, &fetch_api.method, &fetch_api.payload, headers_json, fetch_api.parsed_options().sse, last_event_id, limit).await
+        },
+        ApiType::Probe => state.probe_client.request_response(&fetch_api.endpoint, &fetch_api.payload, fetch_api.parsed_options().probe).await,
+        ApiType::Workflow => workflow::request_response(state, fetch_api, headers_json, template_ctx).await,
+    }
+}
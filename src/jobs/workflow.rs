@@ -0,0 +1,214 @@
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::time::Instant;
+use tracing::debug;
+use crate::{
+    jobs::{error::{ErrorKind, FetchError}, redact, rest, secret, template::{self, BUILTIN_VARIABLES, TemplateContext}},
+    models::fetch::{Api, ExtractSource, FetchResult, StepExtract, StepType, WorkflowOptions, WorkflowStep, WorkflowStepResult},
+    repository::secret::SecretRepository,
+    state::AppState,
+    utils::{crypto::SecretCipher, jsonpath},
+};
+
+pub const MAX_STEPS: usize = 20;
+
+/// Status of the run when a step response is fine but a value can't be extracted
+const EXTRACT_FAILED_STATUS: i16 = 422;
+
+/// Run steps in order, each step rendered with variables extracted by the previous ones
+pub async fn request_response(state: &AppState, fetch: &Api, headers: Option<Value>, ctx: &TemplateContext) -> Result<FetchResult, FetchError> {
+    let options = fetch.parsed_options();
+    let steps = options.workflow.unwrap_or_default().steps;
+    if steps.is_empty() {
+        return Err("[WORKFLOW] Workflow has no steps".to_string().into());
+    }
+    let http_client = state.http_clients.get(&options.client)?;
+    let limit = options.body_limit.unwrap_or_default().resolve(state.app_config.max_body_bytes);
+    let secret_repo = SecretRepository::new(state.database.clone());
+    let redaction = redact::rules(state, fetch);
+
+    let mut ctx = ctx.clone();
+    let mut results: Vec<WorkflowStepResult> = Vec::new();
+    let mut status_code = 200;
+    let mut last_headers = Value::Object(Map::new());
+
+    for step in &steps {
+        debug!("[WORKFLOW] Running step '{}'", step.name);
+        let (request, request_headers) = prepare_step(state.secret_cipher.as_ref(), &secret_repo, fetch, &headers, step, &ctx)
+            .await.map_err(|e| e.context(format_args!("[WORKFLOW] Step '{}'", step.name)))?;
+
+        let started = Instant::now();
+        let result = match step.r#type {
+            StepType::Rest => {
+                let method = rest::to_method(&request.method, &request.custom_method)?;
+                rest::request_response(http_client.clone(), &request.endpoint, method, &request.payload, request_headers, &request.query, &request.parsed_body(), limit).await
+            },
+            StepType::Websocket => state.ws_client.request_response(&request.endpoint, &request.payload, request_headers, limit).await,
+        }.map_err(|e| e.context(format_args!("[WORKFLOW] Step '{}'", step.name)))?;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        status_code = result.status_code;
+        last_headers = result.headers.clone();
+        let mut step_result = WorkflowStepResult {
+            name: step.name.clone(),
+            status_code: Some(result.status_code),
+            duration_ms,
+            headers: result.headers.clone(),
+            response: result.response.clone(),
+            extracted: Vec::new(),
+            error: None,
+        };
+
+        let is_success = (200..300).contains(&result.status_code);
+        if !is_success && !step.continue_on_error {
+            step_result.error = Some(format!("Step returned status {}", result.status_code));
+            redact::step(&redaction, &mut step_result);
+            results.push(step_result);
+            break;
+        }
+
+        let mut failed = false;
+        for rule in &step.extract {
+            match extract(rule, &result) {
+                Ok(value) => {
+                    ctx.variables.insert(rule.name.clone(), Value::String(value));
+                    step_result.extracted.push(rule.name.clone());
+                },
+                Err(e) => {
+                    step_result.error = Some(format!("Extract '{}': {}", rule.name, e));
+                    failed = true;
+                    break;
+                }
+            }
+        }
+        redact::step(&redaction, &mut step_result);
+        results.push(step_result);
+        if failed {
+            status_code = EXTRACT_FAILED_STATUS;
+            break;
+        }
+    }
+
+    let response = serde_json::to_string(&results)
+        .map_err(|e| format!("Failed encode workflow result: {}", e))?;
+
+    Ok(FetchResult {
+        status_code,
+        headers: last_headers,
+        response,
+        ..Default::default()
+    })
+}
+
+/// Step request rendered with the variables so far, secrets come from the step definition, never from extracted values
+pub async fn prepare_step(cipher: Option<&SecretCipher>, secret_repo: &SecretRepository, fetch: &Api, headers: &Option<Value>, step: &WorkflowStep, ctx: &TemplateContext) -> Result<(Api, Option<Value>), FetchError> {
+    let (mut request, mut request_headers) = step_request(fetch, headers, step);
+    let secret_refs = secret::protect(&mut request, &mut request_headers);
+    template::render(&mut request, &mut request_headers, ctx)
+        .map_err(|e| FetchError::new(ErrorKind::Template, e))?;
+    secret::resolve(cipher, secret_repo, &secret_refs, &mut request, &mut request_headers)
+        .await.map_err(|e| FetchError::new(ErrorKind::Secret, e))?;
+
+    Ok((request, request_headers))
+}
+
+/// Step as a fetch request, fetch headers and query are the base of every step
+fn step_request(fetch: &Api, headers: &Option<Value>, step: &WorkflowStep) -> (Api, Option<Value>) {
+    let mut request = fetch.clone();
+    request.endpoint = step.endpoint.clone();
+    request.method = step.method.clone();
+    request.custom_method = step.custom_method.clone();
+    request.payload = step.payload.clone();
+    request.body = step.body.clone();
+    request.query = merge_object(&fetch.query, &step.query);
+
+    let step_headers = step.headers.clone().map(Value::Object);
+    (request, merge_object(headers, &step_headers))
+}
+
+fn merge_object(base: &Option<Value>, overlay: &Option<Value>) -> Option<Value> {
+    match (base, overlay) {
+        (Some(Value::Object(base)), Some(Value::Object(overlay))) => {
+            let mut map = base.clone();
+            for (key, value) in overlay {
+                map.retain(|k, _| !k.eq_ignore_ascii_case(key));
+                map.insert(key.clone(), value.clone());
+            }
+            Some(Value::Object(map))
+        },
+        (_, Some(overlay)) => Some(overlay.clone()),
+        (base, None) => base.clone(),
+    }
+}
+
+/// Take one value from a step response
+pub fn extract(rule: &StepExtract, result: &FetchResult) -> Result<String, String> {
+    let text = match rule.source {
+        ExtractSource::Status => result.status_code.to_string(),
+        ExtractSource::Header => {
+            let name = rule.header.as_deref().unwrap_or_default();
+            result.headers.as_object()
+                .and_then(|map| map.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)))
+                .map(|(_, v)| v.as_str().unwrap_or_default().to_string())
+                .ok_or_else(|| format!("Header '{}' not found", name))?
+        },
+        ExtractSource::Body => match &rule.jsonpath {
+            Some(path) => {
+                let body: Value = serde_json::from_str(&result.response)
+                    .map_err(|e| format!("Body is not JSON: {}", e))?;
+                match jsonpath::select_first(&body, path)? {
+                    Some(Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                    None => return Err(format!("Nothing match '{}'", path)),
+                }
+            },
+            None => result.response.clone(),
+        },
+    };
+
+    match &rule.regex {
+        Some(pattern) => {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex: {}", e))?;
+            let caps = regex.captures(&text)
+                .ok_or_else(|| format!("Regex '{}' did not match", pattern))?;
+            Ok(caps.get(1).or_else(|| caps.get(0)).map(|m| m.as_str().to_string()).unwrap_or_default())
+        },
+        None => Ok(text),
+    }
+}
+
+/// Steps must be runnable before saved
+pub fn validate(options: &WorkflowOptions) -> Result<(), String> {
+    if options.steps.is_empty() || options.steps.len() > MAX_STEPS {
+        return Err(format!("Workflow must have 1 to {} steps", MAX_STEPS));
+    }
+
+    for step in &options.steps {
+        if step.name.trim().is_empty() {
+            return Err("Workflow step name is required".to_string());
+        }
+        if step.r#type == StepType::Rest {
+            rest::to_method(&step.method, &step.custom_method)
+                .map_err(|e| format!("Step '{}': {}", step.name, e))?;
+        }
+        for rule in &step.extract {
+            let valid_name = !rule.name.is_empty()
+                && !BUILTIN_VARIABLES.contains(&rule.name.as_str())
+                && rule.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                return Err(format!("Step '{}': invalid extract name '{}'", step.name, rule.name));
+            }
+            if rule.source == ExtractSource::Header && rule.header.is_none() {
+                return Err(format!("Step '{}': extract '{}' require header name", step.name, rule.name));
+            }
+            if let Some(path) = &rule.jsonpath {
+                jsonpath::parse(path).map_err(|e| format!("Step '{}': {}", step.name, e))?;
+            }
+            if let Some(pattern) = &rule.regex {
+                Regex::new(pattern).map_err(|e| format!("Step '{}': invalid regex: {}", step.name, e))?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,13 +1,12 @@
 use axum::serve;
 use dotenvy::dotenv;
 use scheduler::{
-    config::*, create_app, db::postgres::{self, create_root_user, migrate_app}, jobs::{cleaner::start_job_cleaner, websocket::{WsJobs}, workers::setup_background_workers}, models::fetch::Api, state::{AppConfig, AppState}
+    config::*, create_app, db::postgres::{self, create_root_user, migrate_app}, jobs::{blob::{BlobStore, FsStore, S3Store}, cleaner::start_job_cleaner, export::start_daily_export, redact, retention::start_data_pruner, client::{self, HttpClients}, probe::ProbeJobs, sse::SseJobs, websocket::{WsJobs}, workers::setup_background_workers}, models::fetch::{Api, RedactPattern, RedactionOptions, RetentionOptions}, state::{AppConfig, AppState}, utils::crypto::SecretCipher
 };
 use std::sync::Arc;
 use apalis_sql::postgres::PostgresStorage;
 
 use tracing::{info, error};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -28,12 +27,26 @@ async fn main() {
         let _ = create_root_user(&pool, config.root_username, config.root_email, config.root_password).await;
     }
 
+    let redaction = RedactionOptions {
+        paths: config.redact_paths,
+        patterns: config.redact_patterns.into_iter().map(|pattern| RedactPattern { pattern, replacement: None }).collect(),
+        headers: config.redact_headers,
+    };
+    redact::validate(&redaction).unwrap_or_else(|e| panic!("Invalid global redaction: {}", e));
+
     let app_config = AppConfig {
         secret: config.jwt_secret,
         access_ttl: config.access_ttl as i64,
         refresh_ttl: config.refresh_ttl as i64,
         concurrency: config.concurrency,
-
+        retention: RetentionOptions {
+            keep_last: config.retention_keep_last,
+            max_age_days: config.retention_max_age_days,
+            max_bytes: config.retention_max_bytes,
+        },
+        max_body_bytes: config.max_body_bytes,
+        blob_threshold: config.blob_threshold,
+        redaction,
     };
 
     // Apalis config
@@ -44,23 +57,50 @@ async fn main() {
         PostgresStorage::<Api>::new_with_config(pool.clone(), apalis_config);
     
     // Http request
-    let http_client = reqwest::Client::builder()
-        .user_agent("Teknohole/1.0")
-        .timeout(std::time::Duration::from_secs(10)) 
-        .pool_idle_timeout(std::time::Duration::from_secs(90))
-        .pool_max_idle_per_host(10)
+    let http_client = client::base_builder()
         .build()
         .unwrap();
+    let http_clients = HttpClients::new(http_client.clone());
 
     // Websocket request
     let ws_client = WsJobs::new(config.ws_timeout);
+
+    // Server-sent events request
+    let sse_client = SseJobs::new(http_client.clone(), config.sse_timeout, config.sse_max_events);
+
+    // TCP/TLS probe
+    let probe_client = ProbeJobs::new(config.probe_timeout);
+
+    // Secrets store
+    let secret_cipher = match &config.secret_key {
+        Some(key) => Some(SecretCipher::new(key, config.secret_key_version, &config.secret_old_keys)
+            .unwrap_or_else(|e| panic!("Invalid SECRET_KEY: {}", e))),
+        None => {
+            tracing::warn!("SECRET_KEY not set, secrets store disabled");
+            None
+        }
+    };
+
+    // Blob store for large bodies
+    let blob_store = match config.blob_store.as_deref() {
+        Some("fs") => Some(BlobStore::Fs(FsStore::new(&config.blob_dir))),
+        Some("s3") => Some(BlobStore::S3(S3Store::new(http_client.clone(), &config.s3_endpoint, &config.s3_bucket, &config.s3_region, &config.s3_access_key, &config.s3_secret_key)
+            .unwrap_or_else(|e| panic!("Invalid S3 blob store: {}", e)))),
+        Some(other) => panic!("Invalid BLOB_STORE: '{}'", other),
+        None => None,
+    };
     
     //  State
     let state = AppState {
         app_config: Arc::new(app_config),
         database: pool,
-        http_client: http_client,
-        ws_client: ws_client,
+        http_client,
+        http_clients,
+        ws_client,
+        sse_client,
+        probe_client,
+        secret_cipher,
+        blob_store,
         job_queue: scheduler_storage,
     };
 
@@ -70,6 +110,17 @@ async fn main() {
     tokio::spawn(async move {
         start_job_cleaner(pool_for_cleaner).await;
     });
+    let state_for_pruner = state.clone();
+    let (retention_interval, retention_batch) = (config.retention_interval, config.retention_batch);
+    tokio::spawn(async move {
+        start_data_pruner(state_for_pruner, retention_interval, retention_batch).await;
+    });
+    if let Some(dir) = config.export_dir.clone() {
+        let state_for_export = state.clone();
+        tokio::spawn(async move {
+            start_daily_export(state_for_export, dir.into()).await;
+        });
+    }
     
     // Axum
     let addr = format!("0.0.0.0:{}", port);
@@ -51,10 +51,9 @@ async fn fetch_user_from_request(parts: &Parts, state: &AppState) -> Result<User
 
         let today = Utc::now();
 
-        if let Some(expired_time) = record.expires_at {
-            if expired_time < today {
-                return Err(AppError::AuthError("Expired API Key!".to_string()));
-            }
+        if let Some(expired_time) = record.expires_at
+            && expired_time < today {
+            return Err(AppError::AuthError("Expired API Key!".to_string()));
         }
 
         record.user_id
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 use sqlx::FromRow;
 use chrono::{DateTime,Utc};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use crate::utils::body;
 
 // Struct for table fetch_api
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
@@ -10,10 +12,13 @@ use chrono::{DateTime,Utc};
 pub enum ApiType {
     Rest,
     Websocket,
+    Sse,
+    Probe,
+    Workflow,
     // Mqtt,
     // Graphql,
 }
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
 #[sqlx(type_name = "fetch_api_method", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum ApiMethod {
@@ -22,6 +27,9 @@ pub enum ApiMethod {
     Put,
     Patch,
     Delete,
+    Head,
+    Options,
+    Custom,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -31,14 +39,30 @@ pub struct Api {
     pub r#type: ApiType,
     pub endpoint: String,
     pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
     pub topic: Option<Value>,
     pub job_id: Option<String>,
     pub description: String,
     pub payload: Option<String>,
     pub execute_id: i32,
     pub header_id: Option<i32>,
+    pub auth_id: Option<i32>,
+    pub environment_id: Option<i32>,
     pub is_active: bool,
+    pub options: Option<Value>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
+    /// Secrets referenced by the fetch resolve against this user only
+    pub created_by: Option<i32>,
     pub updated_at: DateTime<Utc>,
+    /// Set on jobs enqueued by an upstream fetch, not a column
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggered_by: Option<UpstreamResult>,
+    /// Set on row jobs of a matrix fetch, not a column
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix_row: Option<MatrixRow>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct CreateApi {
@@ -46,12 +70,19 @@ pub struct CreateApi {
     pub r#type: Option<ApiType>,
     pub endpoint: String,
     pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
     pub topic: Option<Value>,
     pub description: String,
     pub payload: Option<String>,
     pub execute_id: i32,
     pub header_id: Option<i32>,
+    pub auth_id: Option<i32>,
+    pub environment_id: Option<i32>,
     pub is_active: Option<bool>,
+    pub options: Option<Value>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
+    pub created_by: Option<i32>,
 }
 #[derive(Deserialize)]
 pub struct ReqCreateApi {
@@ -59,12 +90,18 @@ pub struct ReqCreateApi {
     pub r#type: Option<ApiType>,
     pub endpoint: String,
     pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
     pub topic: Option<Value>,
     pub description: String,
     pub payload: Option<Value>,
     pub execute_id: i32,
     pub header_id: Option<i32>,
+    pub auth_id: Option<i32>,
+    pub environment_id: Option<i32>,
     pub is_active: Option<bool>,
+    pub options: Option<Value>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
 }
 impl ReqCreateApi {
     pub fn into_model(self) -> CreateApi {
@@ -86,12 +123,19 @@ impl ReqCreateApi {
             r#type: self.r#type,
             endpoint: self.endpoint,
             method: self.method,
+            custom_method: self.custom_method,
             topic: self.topic,
             description: self.description,
             payload: payload_string, 
             execute_id: self.execute_id,
             header_id: self.header_id,
-            is_active: self.is_active
+            auth_id: self.auth_id,
+            environment_id: self.environment_id,
+            is_active: self.is_active,
+            options: self.options,
+            query: self.query,
+            body: self.body,
+            created_by: None,
         }
     }
 }
@@ -102,12 +146,450 @@ pub struct UpdateApi {
     pub r#type: Option<ApiType>,
     pub endpoint: Option<String>,
     pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
     pub topic: Option<Value>,
     pub description: Option<String>,
     pub payload: Option<String>,
     pub execute_id: Option<i32>,
     pub header_id: Option<i32>,
+    pub auth_id: Option<i32>,
+    pub environment_id: Option<i32>,
     pub is_active: Option<bool>,
+    pub options: Option<Value>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
+}
+
+impl UpdateApi {
+    /// Patch changes where the request goes or what it sends
+    pub fn changes_request(&self) -> bool {
+        self.r#type.is_some() || self.endpoint.is_some() || self.method.is_some() || self.custom_method.is_some()
+            || self.payload.is_some() || self.query.is_some() || self.body.is_some()
+            || self.header_id.is_some() || self.options.is_some()
+    }
+}
+
+// Options per fetch type (fetch_api.options)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiOptions {
+    #[serde(default)]
+    pub sse: Option<SseOptions>,
+    #[serde(default)]
+    pub probe: Option<ProbeOptions>,
+    #[serde(default)]
+    pub client: Option<ClientOptions>,
+    /// User template variables, `{{name}}`
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub workflow: Option<WorkflowOptions>,
+    #[serde(default)]
+    pub matrix: Option<MatrixOptions>,
+    #[serde(default)]
+    pub script: Option<ScriptOptions>,
+    #[serde(default)]
+    pub assertions: Option<Vec<Assertion>>,
+    #[serde(default)]
+    pub schema: Option<SchemaOptions>,
+    #[serde(default)]
+    pub change_detection: Option<ChangeOptions>,
+    #[serde(default)]
+    pub metrics: Option<Vec<MetricExtractor>>,
+    #[serde(default)]
+    pub retention: Option<RetentionOptions>,
+    #[serde(default)]
+    pub export: Option<ExportOptions>,
+    #[serde(default)]
+    pub body_limit: Option<BodyLimitOptions>,
+    #[serde(default)]
+    pub redaction: Option<RedactionOptions>,
+}
+
+// Response body limits, the global `MAX_BODY_BYTES` stays the ceiling
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BodyLimitOptions {
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub on_limit: LimitAction,
+    /// Bodies over `BLOB_THRESHOLD` go to the blob store, default true
+    #[serde(default)]
+    pub spill: Option<bool>,
+}
+
+impl BodyLimitOptions {
+    pub fn resolve(&self, global_max: u64) -> BodyLimit {
+        BodyLimit {
+            max_bytes: self.max_bytes.unwrap_or(global_max).min(global_max),
+            action: self.on_limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitAction {
+    /// Keep the first `max_bytes` and flag the row
+    #[default]
+    Truncate,
+    Fail,
+}
+
+// Resolved limit of one request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyLimit {
+    pub max_bytes: u64,
+    pub action: LimitAction,
+}
+
+// Sensitive values removed before a response is stored, global rules always apply too
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RedactionOptions {
+    /// JSONPath of body values removed
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Regex matches masked in the body and header values
+    #[serde(default)]
+    pub patterns: Vec<RedactPattern>,
+    /// Header names masked, case insensitive
+    #[serde(default)]
+    pub headers: Vec<String>,
+}
+
+impl RedactionOptions {
+    /// Rules of self added to `global`
+    pub fn with(&self, global: &RedactionOptions) -> RedactionOptions {
+        RedactionOptions {
+            paths: global.paths.iter().chain(&self.paths).cloned().collect(),
+            patterns: global.patterns.iter().chain(&self.patterns).cloned().collect(),
+            headers: global.headers.iter().chain(&self.headers).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.patterns.is_empty() && self.headers.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RedactPattern {
+    pub pattern: String,
+    /// Defaults to `MASKED_VALUE`, may use `$1` groups
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+// Daily file export into `EXPORT_DIR`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub columns: Vec<ExportColumn>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+// Column projected from the response body
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportColumn {
+    pub name: String,
+    pub path: String,
+}
+
+// Stored data limits, unset fields fall back to the global default.
+// Run history and metrics follow `keep_last` and `max_age_days` too
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RetentionOptions {
+    /// Newest rows kept, metrics keep as many values per series
+    #[serde(default)]
+    pub keep_last: Option<i64>,
+    #[serde(default)]
+    pub max_age_days: Option<i64>,
+    /// Body and header bytes kept, oldest data rows pruned first
+    #[serde(default)]
+    pub max_bytes: Option<i64>,
+}
+
+impl RetentionOptions {
+    /// Fields of self win over `defaults`
+    pub fn or(&self, defaults: &RetentionOptions) -> RetentionOptions {
+        RetentionOptions {
+            keep_last: self.keep_last.or(defaults.keep_last),
+            max_age_days: self.max_age_days.or(defaults.max_age_days),
+            max_bytes: self.max_bytes.or(defaults.max_bytes),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.max_age_days.is_none() && self.max_bytes.is_none()
+    }
+}
+
+// Compare body with the previous stored run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeOptions {
+    #[serde(default)]
+    pub mode: ChangeMode,
+    /// JSONPaths left out of the hash, e.g. `$.timestamp`
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeMode {
+    /// Unchanged response is not stored
+    #[default]
+    Skip,
+    /// Every response stored with `changed` set
+    Flag,
+}
+
+// One difference between two stored bodies
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiffChange {
+    pub op: DiffOp,
+    /// JSON pointer, empty for the whole body
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiDataDiff {
+    pub id: i32,
+    pub previous_id: Option<i32>,
+    pub changed: bool,
+    pub changes: Vec<DiffChange>,
+}
+
+// JSON Schema the response body must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaOptions {
+    pub document: Value,
+    /// Invalid body fails the run, default true
+    pub fail_run: Option<bool>,
+}
+
+// Rhai scripts run around the request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptOptions {
+    /// Mutates `request` before it is sent
+    pub pre_request: Option<String>,
+    /// Reads and mutates `response` and `variables`, `throw` fails the run
+    pub post_response: Option<String>,
+    /// Limit per script in milliseconds
+    pub timeout_ms: Option<u64>,
+}
+
+// Fan-out parameters, inline rows or a stored dataset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MatrixOptions {
+    pub rows: Option<Vec<Map<String, Value>>>,
+    pub dataset_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SseOptions {
+    /// Max listening time in seconds
+    pub timeout: Option<u64>,
+    /// Stop after receiving this many events
+    pub max_events: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProbeOptions {
+    /// Do TLS handshake after connected
+    #[serde(default)]
+    pub tls: bool,
+    /// SNI name, default host from endpoint
+    pub server_name: Option<String>,
+    /// Read banner after connected (and payload sent)
+    #[serde(default)]
+    pub read_banner: bool,
+    /// Max banner size in bytes
+    pub banner_bytes: Option<usize>,
+    /// Connect/handshake/read timeout in seconds
+    pub timeout: Option<u64>,
+    /// Flag certificate as expiring within this many days
+    pub expiry_days: Option<i64>,
+}
+
+// Ordered requests of a workflow fetch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowOptions {
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub name: String,
+    #[serde(default)]
+    pub r#type: StepType,
+    pub endpoint: String,
+    pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
+    /// Merged over fetch headers, step wins on conflict
+    pub headers: Option<Map<String, Value>>,
+    pub payload: Option<String>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
+    /// Values taken from this step response, usable as `{{name}}` in later steps
+    #[serde(default)]
+    pub extract: Vec<StepExtract>,
+    /// Keep running next steps when this step get non 2xx status
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepType {
+    #[default]
+    Rest,
+    Websocket,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepExtract {
+    /// Variable name
+    pub name: String,
+    #[serde(default)]
+    pub source: ExtractSource,
+    /// JSONPath on the JSON body
+    pub jsonpath: Option<String>,
+    /// Header name, source header only
+    pub header: Option<String>,
+    /// Regex applied after jsonpath/header, first capture group (or whole match) is taken
+    pub regex: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractSource {
+    #[default]
+    Body,
+    Header,
+    Status,
+}
+
+// Stored response of a workflow run, extracted values are not stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStepResult {
+    pub name: String,
+    pub status_code: Option<i16>,
+    pub duration_ms: u64,
+    pub headers: Value,
+    pub response: String,
+    pub extracted: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Structured request body (fetch_api.body), empty body use raw payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum ApiBody {
+    Raw,
+    Json { json: Value },
+    Form { fields: Map<String, Value> },
+    Multipart { parts: Vec<MultipartPart> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MultipartPart {
+    Text {
+        name: String,
+        value: String,
+    },
+    File {
+        name: String,
+        filename: String,
+        content_type: Option<String>,
+        /// Base64 encoded file content
+        content: String,
+    },
+}
+
+// HTTP client settings per fetch, equal settings share one client
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClientOptions {
+    /// Request timeout in seconds
+    pub timeout: Option<u64>,
+    pub redirect: Option<RedirectMode>,
+    /// Used when redirect mode is limit
+    pub max_redirects: Option<usize>,
+    /// http://, https:// or socks5:// proxy url
+    pub proxy: Option<String>,
+    /// Extra trusted CA certificates (PEM)
+    pub ca_bundle: Option<String>,
+    /// mTLS client certificate and PKCS#8 key (PEM)
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    /// Accept invalid certificates, test environments only
+    #[serde(default)]
+    pub skip_verify: bool,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedirectMode {
+    Follow,
+    Limit,
+    None,
+}
+
+impl Api {
+    /// Parse options column, invalid or empty options fallback to default
+    pub fn parsed_options(&self) -> ApiOptions {
+        self.options
+            .clone()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Parse body column, validated on create/update
+    pub fn parsed_body(&self) -> Option<ApiBody> {
+        self.body
+            .clone()
+            .filter(|v| !v.is_null())
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
 }
 
 // Struct for table fetch_api_members
@@ -237,6 +719,567 @@ pub struct UpdateApiHeader {
     pub headers: Option<Value>,
 }
 
+// Struct for table fetch_api_auth
+pub const MASKED_VALUE: &str = "********";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "fetch_auth_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuthType {
+    ClientCredentials,
+    Password,
+    Bearer,
+    Basic,
+    ApiKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiAuth {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub r#type: AuthType,
+    pub config: Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ApiAuth {
+    /// Hide credentials before returned to client
+    pub fn masked(mut self) -> Self {
+        if let Value::Object(map) = &mut self.config {
+            for key in ["client_secret", "password", "token", "key_value"] {
+                if let Some(value) = map.get_mut(key) {
+                    *value = Value::String(MASKED_VALUE.to_string());
+                }
+            }
+        }
+        self
+    }
+
+    pub fn parsed_config(&self) -> Result<AuthConfig, String> {
+        serde_json::from_value(self.config.clone())
+            .map_err(|e| format!("Invalid auth config: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    // OAuth2 (client_credentials, password)
+    pub token_url: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub scope: Option<String>,
+    pub audience: Option<String>,
+    pub client_auth: Option<ClientAuthMethod>,
+    // password, basic
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // bearer
+    pub token: Option<String>,
+    // api_key
+    pub key_name: Option<String>,
+    pub key_value: Option<String>,
+    pub key_in: Option<KeyLocation>,
+}
+
+/// How client credentials sent to token endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuthMethod {
+    #[default]
+    Basic,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+impl AuthConfig {
+    /// Check required fields of each auth type
+    pub fn validate(&self, auth_type: &AuthType) -> Result<(), String> {
+        let required: &[(&str, &Option<String>)] = match auth_type {
+            AuthType::ClientCredentials => &[("token_url", &self.token_url), ("client_id", &self.client_id), ("client_secret", &self.client_secret)],
+            AuthType::Password => &[("token_url", &self.token_url), ("client_id", &self.client_id), ("username", &self.username), ("password", &self.password)],
+            AuthType::Bearer => &[("token", &self.token)],
+            AuthType::Basic => &[("username", &self.username), ("password", &self.password)],
+            AuthType::ApiKey => &[("key_name", &self.key_name), ("key_value", &self.key_value)],
+        };
+
+        for (field, value) in required {
+            if value.as_deref().unwrap_or("").is_empty() {
+                return Err(format!("Field '{}' is required for this auth type", field));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CreateApiAuth {
+    pub user_id: i32,
+    pub name: String,
+    pub r#type: AuthType,
+    pub config: Value,
+}
+
+// DTO auth
+#[derive(Deserialize)]
+pub struct ReqCreateApiAuth {
+    pub name: String,
+    pub r#type: AuthType,
+    pub config: Value,
+}
+
+impl ReqCreateApiAuth {
+    pub fn into_model(self, user_id: i32) -> CreateApiAuth {
+        CreateApiAuth {
+            user_id,
+            name: self.name,
+            r#type: self.r#type,
+            config: self.config,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UpdateApiAuth {
+    pub name: Option<String>,
+    pub r#type: Option<AuthType>,
+    pub config: Option<Value>,
+}
+
+// Struct for table fetch_api_environment
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiEnvironment {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ApiEnvironment {
+    /// Hide variable values from users the environment is only shared with
+    pub fn masked(mut self) -> Self {
+        if let Value::Object(map) = &mut self.variables {
+            for value in map.values_mut() {
+                *value = Value::String(MASKED_VALUE.to_string());
+            }
+        }
+        self
+    }
+
+    /// Variables column as map, non object fallback to empty
+    pub fn variables_map(&self) -> Map<String, Value> {
+        match &self.variables {
+            Value::Object(map) => map.clone(),
+            _ => Map::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CreateApiEnvironment {
+    pub user_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Value,
+}
+
+#[derive(Deserialize)]
+pub struct ReqCreateApiEnvironment {
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Option<Value>,
+}
+
+impl ReqCreateApiEnvironment {
+    pub fn into_model(self, user_id: i32) -> CreateApiEnvironment {
+        CreateApiEnvironment {
+            user_id,
+            name: self.name,
+            description: self.description,
+            variables: self.variables.unwrap_or_else(|| Value::Object(Map::new())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UpdateApiEnvironment {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub variables: Option<Value>,
+}
+
+// Copy a fetch into another environment
+#[derive(Deserialize)]
+pub struct ReqCloneApi {
+    pub environment_id: Option<i32>,
+    pub name: Option<String>,
+}
+
+// Struct for table fetch_api_dependency
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "fetch_dependency_trigger", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyTrigger {
+    Success,
+    Failure,
+    Always,
+}
+
+impl DependencyTrigger {
+    pub fn matches(&self, success: bool) -> bool {
+        match self {
+            DependencyTrigger::Success => success,
+            DependencyTrigger::Failure => !success,
+            DependencyTrigger::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiDependency {
+    pub id: i32,
+    pub upstream_id: i32,
+    pub downstream_id: i32,
+    pub trigger: DependencyTrigger,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct ReqCreateApiDependency {
+    pub downstream_id: i32,
+    pub trigger: Option<DependencyTrigger>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiDagNode {
+    pub id: i32,
+    pub name: String,
+    pub r#type: ApiType,
+    pub is_active: bool,
+}
+
+// Connected dependency graph of a fetch
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiDag {
+    pub nodes: Vec<ApiDagNode>,
+    pub edges: Vec<ApiDependency>,
+}
+
+// Upstream run passed to downstream templates as `{{upstream.*}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamResult {
+    pub id: i32,
+    pub name: String,
+    pub status_code: Option<i16>,
+    pub response: String,
+    pub error: Option<String>,
+}
+
+// Struct for table fetch_api_dataset
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiDataset {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub rows: Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ApiDataset {
+    /// Rows column as list of objects, other values skipped
+    pub fn rows_list(&self) -> Vec<Map<String, Value>> {
+        match &self.rows {
+            Value::Array(rows) => rows.iter().filter_map(|r| r.as_object().cloned()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Dataset given as CSV text (first line is header) or as rows
+#[derive(Deserialize)]
+pub struct ReqCreateApiDataset {
+    pub name: String,
+    pub csv: Option<String>,
+    pub rows: Option<Vec<Map<String, Value>>>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateApiDataset {
+    pub name: Option<String>,
+    pub csv: Option<String>,
+    pub rows: Option<Vec<Map<String, Value>>>,
+}
+
+// One row job of a matrix run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixRow {
+    pub run_id: i32,
+    pub index: usize,
+    pub values: Map<String, Value>,
+}
+
+// What enqueued a run
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "fetch_run_trigger", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RunTrigger {
+    Schedule,
+    Dependency,
+    Matrix,
+}
+
+// Success: 2xx and checks passed, failure: response but not successful, error: no response
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "fetch_run_outcome", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    Success,
+    Failure,
+    Error,
+}
+
+// Struct for table fetch_runs
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiRun {
+    pub id: i64,
+    pub fetch_id: i32,
+    pub job_id: Option<String>,
+    pub attempt: i32,
+    pub trigger: RunTrigger,
+    pub outcome: RunOutcome,
+    pub status_code: Option<i16>,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+    pub worker_id: Option<String>,
+    pub data_id: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub timing: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateApiRun {
+    pub fetch_id: i32,
+    pub job_id: Option<String>,
+    pub attempt: i32,
+    pub trigger: RunTrigger,
+    pub outcome: RunOutcome,
+    pub status_code: Option<i16>,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+    pub worker_id: Option<String>,
+    pub data_id: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub timing: Option<Value>,
+}
+
+// Query filter of run history
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiRunFilter {
+    pub outcome: Option<RunOutcome>,
+    pub trigger: Option<RunTrigger>,
+    pub error_kind: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Time window of timing aggregates, default last 24 hours
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiTimingFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+// Aggregate of one timing metric over a window
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimingStat {
+    pub metric: String,
+    pub count: i64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+// Named value pulled from every run result into `fetch_metrics`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricExtractor {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: MetricSource,
+    #[serde(default)]
+    pub kind: MetricKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MetricSource {
+    Jsonpath { path: String },
+    /// Capture group, default the first group or the whole match
+    Regex { pattern: String, #[serde(default)] group: Option<usize> },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    #[default]
+    Number,
+    String,
+}
+
+// Value extracted from one run
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricValue {
+    pub name: String,
+    pub value_num: Option<f64>,
+    pub value_text: Option<String>,
+}
+
+// Struct for table fetch_metrics
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiMetric {
+    pub id: i64,
+    pub fetch_id: i32,
+    pub data_id: Option<i32>,
+    pub name: String,
+    pub value_num: Option<f64>,
+    pub value_text: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+// Series stored for a fetch
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MetricSeries {
+    pub name: String,
+    pub count: i64,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricAgg {
+    #[default]
+    Avg,
+    Min,
+    Max,
+    Sum,
+    Last,
+}
+
+impl MetricAgg {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricAgg::Avg => "avg",
+            MetricAgg::Min => "min",
+            MetricAgg::Max => "max",
+            MetricAgg::Sum => "sum",
+            MetricAgg::Last => "last",
+        }
+    }
+}
+
+// Query of a metric series, default last 24 hours in 5 minute buckets
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiMetricFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Bucket size in seconds
+    pub bucket: Option<i64>,
+    pub agg: Option<MetricAgg>,
+}
+
+// One bucket of a metric series
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MetricPoint {
+    pub bucket: DateTime<Utc>,
+    pub count: i64,
+    pub value: Option<f64>,
+    /// Latest text value, set for string metrics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+// Stored data of one fetch
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FetchStorage {
+    pub fetch_id: i32,
+    pub name: String,
+    pub owner_id: Option<i32>,
+    pub rows: i64,
+    pub bytes: i64,
+    pub oldest_at: Option<DateTime<Utc>>,
+    pub newest_at: Option<DateTime<Utc>>,
+}
+
+// Stored data of the fetches a user owns
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserStorage {
+    pub user_id: i32,
+    pub username: String,
+    pub fetches: i64,
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub total_rows: i64,
+    pub total_bytes: i64,
+    pub defaults: RetentionOptions,
+    pub fetches: Vec<FetchStorage>,
+    pub users: Vec<UserStorage>,
+}
+
+// Struct for table fetch_api_matrix_run
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiMatrixRun {
+    pub id: i32,
+    pub fetch_id: i32,
+    pub total: i32,
+    pub succeeded: i32,
+    pub failed: i32,
+    pub results: Value,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixRowResult {
+    pub index: usize,
+    pub status_code: Option<i16>,
+    pub data_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Struct for table fetch_api_auth_token
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ApiAuthToken {
+    pub auth_id: i32,
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
 // Struct for table fetch_api_data
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ApiData {
@@ -246,10 +1289,34 @@ pub struct ApiData {
     pub status_code: i16,
     pub response: String,
     pub response_headers: Value,
+    pub assertions: Option<Value>,
+    pub passed: Option<bool>,
+    pub schema_errors: Option<Value>,
+    pub body_hash: Option<String>,
+    pub changed: Option<bool>,
+    pub content_type: Option<String>,
+    pub encoding: BodyEncoding,
+    pub truncated: bool,
+    /// Body kept in the blob store, `response` is empty
+    pub blob_key: Option<String>,
+    pub blob_size: Option<i64>,
+    /// Matrix row index, None for a regular run
+    pub matrix_row: Option<i32>,
     pub updated_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 
+// How `response` holds the body
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "fetch_body_encoding", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BodyEncoding {
+    #[default]
+    Text,
+    /// Binary body, served decoded by the raw endpoint
+    Base64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ApiDataResponse {
     pub id: i32,
@@ -258,16 +1325,27 @@ pub struct ApiDataResponse {
     pub status_code: i16,
     pub response: Value, 
     pub response_headers: Value,
+    pub assertions: Option<Value>,
+    pub passed: Option<bool>,
+    pub schema_errors: Option<Value>,
+    pub body_hash: Option<String>,
+    pub changed: Option<bool>,
+    pub content_type: Option<String>,
+    pub encoding: BodyEncoding,
+    pub truncated: bool,
+    pub blob_key: Option<String>,
+    pub blob_size: Option<i64>,
     pub updated_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 // Helper Text DB Model to json response 
 impl From<ApiData> for ApiDataResponse {
     fn from(data: ApiData) -> Self {
-        let parsed_response: Value = serde_json::from_str(&data.response)
-            .unwrap_or_else(|_| {
-                Value::String(data.response) 
-            });
+        // Blob bodies only through the raw endpoint
+        let parsed_response = match data.blob_key {
+            Some(_) => Value::Null,
+            None => body::view(data.content_type.as_deref(), &data.response, data.encoding),
+        };
 
         ApiDataResponse {
             id: data.id,
@@ -276,12 +1354,142 @@ impl From<ApiData> for ApiDataResponse {
             status_code: data.status_code,
             response: parsed_response,
             response_headers: data.response_headers,
+            assertions: data.assertions,
+            passed: data.passed,
+            schema_errors: data.schema_errors,
+            body_hash: data.body_hash,
+            changed: data.changed,
+            content_type: data.content_type,
+            encoding: data.encoding,
+            truncated: data.truncated,
+            blob_key: data.blob_key,
+            blob_size: data.blob_size,
             updated_at: data.updated_at,
             created_at: data.created_at,
         }
     }
 }
 
+// Query filter of data listing, paged by `cursor`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiDataFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<i16>,
+    pub status_min: Option<i16>,
+    pub status_max: Option<i16>,
+    /// Plain substring of the stored body
+    pub contains: Option<String>,
+    /// Outcome of the run that stored the row
+    pub outcome: Option<RunOutcome>,
+    pub sort: Option<DataSort>,
+    pub order: Option<SortOrder>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// Query of data export, rows streamed oldest first
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiDataExport {
+    pub format: Option<ExportFormat>,
+    /// `name=$.path` or `$.path`, comma separated
+    pub columns: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<i16>,
+    pub status_min: Option<i16>,
+    pub status_max: Option<i16>,
+    pub contains: Option<String>,
+    pub outcome: Option<RunOutcome>,
+}
+
+impl ApiDataExport {
+    pub fn filter(&self) -> ApiDataFilter {
+        ApiDataFilter {
+            since: self.since,
+            until: self.until,
+            status: self.status,
+            status_min: self.status_min,
+            status_max: self.status_max,
+            contains: self.contains.clone(),
+            outcome: self.outcome.clone(),
+            sort: Some(DataSort::CreatedAt),
+            order: Some(SortOrder::Asc),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    StatusCode,
+}
+
+impl DataSort {
+    pub fn column(&self) -> &'static str {
+        match self {
+            DataSort::CreatedAt => "d.created_at",
+            DataSort::UpdatedAt => "d.updated_at",
+            DataSort::StatusCode => "d.status_code",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Keyset position after the last row of a page, sent as opaque base64
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DataCursor {
+    pub sort: DataSort,
+    pub order: SortOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<i16>,
+    pub id: i32,
+}
+
+impl DataCursor {
+    pub fn after(sort: DataSort, order: SortOrder, data: &ApiData) -> Self {
+        let (at, status) = match sort {
+            DataSort::CreatedAt => (Some(data.created_at), None),
+            DataSort::UpdatedAt => (Some(data.updated_at), None),
+            DataSort::StatusCode => (None, Some(data.status_code)),
+        };
+        DataCursor { sort, order, at, status, id: data.id }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Cursor must come from a listing with the same sort and order
+    pub fn decode(cursor: &str, sort: DataSort, order: SortOrder) -> Result<Self, String> {
+        let cursor: DataCursor = URL_SAFE_NO_PAD.decode(cursor.trim()).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or("Invalid cursor")?;
+        let complete = match sort {
+            DataSort::CreatedAt | DataSort::UpdatedAt => cursor.at.is_some(),
+            DataSort::StatusCode => cursor.status.is_some(),
+        };
+        if cursor.sort != sort || cursor.order != order || !complete {
+            return Err("Cursor does not match the sort or order".to_string());
+        }
+
+        Ok(cursor)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct CreateApiData {
     pub fetch_id: i32,
@@ -289,6 +1497,17 @@ pub struct CreateApiData {
     pub status_code: Option<i16>,
     pub response: Option<String>,
     pub response_headers: Option<Value>,
+    pub assertions: Option<Value>,
+    pub passed: Option<bool>,
+    pub schema_errors: Option<Value>,
+    pub body_hash: Option<String>,
+    pub changed: Option<bool>,
+    pub content_type: Option<String>,
+    pub encoding: Option<BodyEncoding>,
+    pub truncated: Option<bool>,
+    pub blob_key: Option<String>,
+    pub blob_size: Option<i64>,
+    pub matrix_row: Option<i32>,
 }
 // DTO payload data
 #[derive(Deserialize)]
@@ -307,6 +1526,17 @@ impl ReqCreateApiData {
             status_code: self.status_code,
             response: self.response,
             response_headers: self.response_headers,
+            assertions: None,
+            passed: None,
+            schema_errors: None,
+            body_hash: None,
+            changed: None,
+            content_type: None,
+            encoding: None,
+            truncated: None,
+            blob_key: None,
+            blob_size: None,
+            matrix_row: None,
         }
     }
 }
@@ -319,9 +1549,165 @@ pub struct UpdateApiData {
     pub response_headers: Value,
 }
 
+// Single event from text/event-stream
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+// Result of tcp/tls probe, stored as response
 #[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub host: String,
+    pub port: u16,
+    pub address: String,
+    pub connect_ms: u64,
+    pub tls: Option<ProbeTls>,
+    pub banner: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProbeTls {
+    pub handshake_ms: u64,
+    pub version: Option<String>,
+    pub cipher: Option<String>,
+    pub verified: bool,
+    pub verify_error: Option<String>,
+    pub certificate: Option<ProbeCertificate>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProbeCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    pub days_remaining: i64,
+    pub expiring_soon: bool,
+}
+
+// Preview of a fetch request after templates rendered, secrets stay as placeholders
+#[derive(Clone, Debug, Serialize)]
+pub struct RenderedRequest {
+    pub endpoint: String,
+    pub method: Option<ApiMethod>,
+    pub custom_method: Option<String>,
+    pub headers: Option<Value>,
+    pub payload: Option<String>,
+    pub query: Option<Value>,
+    pub body: Option<Value>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct FetchResult {
     pub status_code: i16,
     pub headers: Value,
     pub response: String,
+    /// Content-Type header of the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub encoding: BodyEncoding,
+    /// Body cut at the size limit
+    #[serde(default)]
+    pub truncated: bool,
+    /// Set after the run when the fetch has assertions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assertions: Option<Vec<AssertionResult>>,
+    /// Set after the run when the fetch has a JSON Schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<SchemaCheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<RunTiming>,
+    /// Reason the post-response script set `response.failed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script_failure: Option<String>,
+}
+
+// Network timing of one run in milliseconds, phases missing on reused connections
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RunTiming {
+    /// DNS and TLS set only where timed apart, REST counts them in `connect_ms`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<f64>,
+    /// New connection, DNS lookup and TLS handshake included for REST
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<f64>,
+    /// WebSocket, first message after connect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_message_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_bytes: Option<u64>,
+}
+
+impl FetchResult {
+    /// 2xx, every assertion passed and enforced schema valid
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code) && self.passed().unwrap_or(true)
+    }
+
+    /// None when the fetch has no checks
+    pub fn passed(&self) -> Option<bool> {
+        let assertions = self.assertions.as_ref().map(|list| list.iter().all(|a| a.passed));
+        let schema = self.schema.as_ref().map(|check| !check.enforced || check.errors.is_empty());
+        let script = self.script_failure.as_ref().map(|_| false);
+        match (assertions, schema, script) {
+            (None, None, None) => None,
+            (a, s, c) => Some(a.unwrap_or(true) && s.unwrap_or(true) && c.unwrap_or(true)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaCheck {
+    /// Errors fail the run
+    pub enforced: bool,
+    pub errors: Vec<SchemaError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaError {
+    /// JSON pointer into the body
+    pub instance_path: String,
+    /// JSON pointer into the schema
+    pub schema_path: String,
+    pub message: String,
+}
+
+// Check evaluated on every run result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    Status { codes: Vec<i16> },
+    Latency { max_ms: u64 },
+    Jsonpath { path: String, op: JsonPathOp, #[serde(default)] value: Option<Value> },
+    Regex { pattern: String },
+    Header { name: String, #[serde(default)] value: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonPathOp {
+    Equals,
+    Contains,
+    Exists,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub assertion: Assertion,
+    pub passed: bool,
+    pub actual: Option<Value>,
+    pub message: Option<String>,
 }
\ No newline at end of file
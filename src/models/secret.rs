@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+/// Secret value is never serialized, only metadata leaves the API
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Secret {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(skip)]
+    pub ciphertext: Vec<u8>,
+    pub key_version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateSecret {
+    pub user_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub ciphertext: Vec<u8>,
+    pub key_version: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReqCreateSecret {
+    pub name: String,
+    pub description: Option<String>,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReqUpdateSecret {
+    pub description: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotateSecretResult {
+    pub key_version: i32,
+    pub rotated: usize,
+}
+
+impl ReqCreateSecret {
+    pub fn into_model(self, user_id: i32, ciphertext: Vec<u8>, key_version: i32) -> CreateSecret {
+        CreateSecret {
+            user_id,
+            name: self.name,
+            description: self.description,
+            ciphertext,
+            key_version,
+        }
+    }
+}
+
+impl Secret {
+    /// Additional data bound to ciphertext, a row copied to another owner/name fails decrypt
+    pub fn aad(user_id: i32, name: &str) -> String {
+        format!("{}:{}", user_id, name)
+    }
+}
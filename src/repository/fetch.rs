@@ -1,5 +1,7 @@
-use sqlx::{PgPool};
-use crate::{models::fetch::{Api, ApiData, ApiExecute, ApiHeader, ApiMembers, CreateApi, CreateApiData, CreateApiExecute, CreateApiHeader, CreateApiMembers, UpdateApi, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers}};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use crate::{models::fetch::{Api, ApiAuth, ApiAuthToken, ApiData, ApiExecute, ApiHeader, ApiMembers, CreateApi, CreateApiAuth, CreateApiData, ApiEnvironment, CreateApiEnvironment, UpdateApiEnvironment, ApiDagNode, ApiDependency, DependencyTrigger, ApiDataset, ApiMatrixRun, ApiRun, ApiRunFilter, CreateApiRun, TimingStat, ApiMetric, ApiDataFilter, DataCursor, DataSort, SortOrder, FetchStorage, UserStorage, MetricAgg, MetricPoint, MetricSeries, MetricValue, CreateApiExecute, CreateApiHeader, CreateApiMembers, UpdateApi, UpdateApiAuth, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers}};
 pub struct FetchRepository {
     pool: PgPool,
 }
@@ -15,6 +17,27 @@ pub struct FetchHeaderRepository {
 pub struct FetchDataRepository {
     pool: PgPool
 }
+pub struct FetchAuthRepository {
+    pool: PgPool
+}
+pub struct FetchEnvironmentRepository {
+    pool: PgPool
+}
+pub struct FetchDependencyRepository {
+    pool: PgPool
+}
+pub struct FetchDatasetRepository {
+    pool: PgPool
+}
+pub struct FetchRunRepository {
+    pool: PgPool
+}
+pub struct FetchMatrixRepository {
+    pool: PgPool
+}
+pub struct FetchMetricRepository {
+    pool: PgPool
+}
 
 impl FetchRepository {
     pub fn new(pool: PgPool) -> Self {
@@ -63,8 +86,8 @@ impl FetchRepository {
 
     pub async fn create(&self, data: CreateApi) -> Result<Api, sqlx::Error> {
         sqlx::query_as::<_,Api>(
-            r#"INSERT INTO fetch_api (name, type, endpoint, method, topic, description, payload, execute_id, header_id, is_active)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            r#"INSERT INTO fetch_api (name, type, endpoint, method, topic, description, payload, execute_id, header_id, is_active, options, query, body, custom_method, auth_id, environment_id, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, COALESCE($11, '{}'::jsonb), COALESCE($12, '{}'::jsonb), $13, $14, $15, $16, $17)
             RETURNING *
             "#
         )
@@ -78,6 +101,13 @@ impl FetchRepository {
         .bind(data.execute_id)
         .bind(data.header_id)
         .bind(data.is_active)
+        .bind(data.options)
+        .bind(data.query)
+        .bind(data.body)
+        .bind(data.custom_method)
+        .bind(data.auth_id)
+        .bind(data.environment_id)
+        .bind(data.created_by)
         .fetch_one(&self.pool)
         .await
     }
@@ -106,8 +136,14 @@ impl FetchRepository {
                         payload     = COALESCE($7, payload),
                         execute_id  = COALESCE($8, execute_id),
                         header_id   = COALESCE($9, header_id),
-                        is_active   = COALESCE($10, is_active)
-                    WHERE id = $11
+                        is_active   = COALESCE($10, is_active),
+                        options     = COALESCE($11, options),
+                        query       = COALESCE($12, query),
+                        body        = COALESCE($13, body),
+                        custom_method = COALESCE($14, custom_method),
+                        auth_id     = COALESCE($15, auth_id),
+                        environment_id = COALESCE($16, environment_id)
+                    WHERE id = $17
                     RETURNING *
                 "#
         )
@@ -121,6 +157,12 @@ impl FetchRepository {
         .bind(data.execute_id)
         .bind(data.header_id)
         .bind(data.is_active)
+        .bind(data.options)
+        .bind(data.query)
+        .bind(data.body)
+        .bind(data.custom_method)
+        .bind(data.auth_id)
+        .bind(data.environment_id)
         .bind(id)
         .fetch_one(&self.pool)
         .await
@@ -347,6 +389,116 @@ impl FetchHeaderRepository {
     }
 }
 
+impl FetchAuthRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<ApiAuth, sqlx::Error> {
+        sqlx::query_as::<_, ApiAuth> (
+            r#"SELECT * FROM fetch_api_auth WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn find_all(&self, user_id: i32) -> Result<Vec<ApiAuth>, sqlx::Error> {
+        sqlx::query_as::<_,ApiAuth> (
+            r#"SELECT * FROM fetch_api_auth WHERE user_id = $1"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, data: CreateApiAuth) -> Result<ApiAuth, sqlx::Error>{
+        sqlx::query_as::<_,ApiAuth>(
+            r#" INSERT INTO fetch_api_auth (user_id, name, type, config)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#
+        )
+        .bind(data.user_id)
+        .bind(data.name)
+        .bind(data.r#type)
+        .bind(data.config)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn update(&self, id: i32, data: UpdateApiAuth) -> Result<ApiAuth, sqlx::Error>{
+        sqlx::query_as::<_,ApiAuth>(
+            r#"UPDATE fetch_api_auth
+            SET
+                name   = COALESCE($1, name),
+                type   = COALESCE($2, type),
+                config = COALESCE($3, config)
+            WHERE id=$4
+            RETURNING *
+            "#
+        )
+        .bind(data.name)
+        .bind(data.r#type)
+        .bind(data.config)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, id:i32) -> Result<ApiAuth, sqlx::Error> {
+        sqlx::query_as::<_,ApiAuth> (
+            r#"DELETE FROM fetch_api_auth WHERE id=$1 RETURNING *"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Cached token still valid after `min_valid_seconds`
+    pub async fn find_token(&self, auth_id: i32, min_valid_seconds: i64) -> Result<Option<ApiAuthToken>, sqlx::Error> {
+        sqlx::query_as::<_,ApiAuthToken> (
+            r#"SELECT * FROM fetch_api_auth_token
+            WHERE auth_id = $1 AND expires_at > NOW() + make_interval(secs => $2)"#
+        )
+        .bind(auth_id)
+        .bind(min_valid_seconds as f64)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn save_token(&self, auth_id: i32, access_token: &str, token_type: &str, expires_at: DateTime<Utc>) -> Result<ApiAuthToken, sqlx::Error> {
+        sqlx::query_as::<_,ApiAuthToken> (
+            r#"INSERT INTO fetch_api_auth_token (auth_id, access_token, token_type, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (auth_id) DO UPDATE
+            SET access_token = EXCLUDED.access_token,
+                token_type   = EXCLUDED.token_type,
+                expires_at   = EXCLUDED.expires_at,
+                created_at   = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(auth_id)
+        .bind(access_token)
+        .bind(token_type)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete_token(&self, auth_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"DELETE FROM fetch_api_auth_token WHERE auth_id = $1"#
+        )
+        .bind(auth_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
 impl FetchDataRepository {
     pub fn new(pool: PgPool) -> Self {
         Self {pool}
@@ -361,6 +513,145 @@ impl FetchDataRepository {
         .await
     }
 
+    /// Latest entry of a regular run, or of the given matrix row
+    pub async fn find_latest(&self, fetch_id: i32, matrix_row: Option<i32>) -> Result<Option<ApiData>, sqlx::Error> {
+        sqlx::query_as::<_,ApiData> (
+            r#"SELECT * FROM fetch_api_data
+            WHERE fetch_id = $1 AND matrix_row IS NOT DISTINCT FROM $2
+            ORDER BY created_at DESC, id DESC LIMIT 1"#
+        )
+        .bind(fetch_id)
+        .bind(matrix_row)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Entry stored right before the given one
+    pub async fn find_previous(&self, data: &ApiData) -> Result<Option<ApiData>, sqlx::Error> {
+        sqlx::query_as::<_,ApiData> (
+            r#"SELECT * FROM fetch_api_data
+            WHERE fetch_id = $1 AND (created_at, id) < ($2, $3)
+            ORDER BY created_at DESC, id DESC LIMIT 1"#
+        )
+        .bind(data.fetch_id)
+        .bind(data.created_at)
+        .bind(data.id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Blob keys of every row of the fetch
+    pub async fn blob_keys(&self, fetch_id: i32) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>(
+            r#"SELECT blob_key FROM fetch_api_data WHERE fetch_id = $1 AND blob_key IS NOT NULL"#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn count(&self, fetch_id: i32) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"SELECT COUNT(*) FROM fetch_api_data WHERE fetch_id = $1"#
+        )
+        .bind(fetch_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Delete up to `batch` rows older than `days`, blob keys of the deleted rows returned
+    pub async fn prune_older_than(&self, fetch_id: i32, days: i64, batch: i64) -> Result<Vec<Option<String>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<String>>(
+            r#"DELETE FROM fetch_api_data WHERE id IN (
+                SELECT id FROM fetch_api_data
+                WHERE fetch_id = $1 AND created_at < NOW() - $2 * INTERVAL '1 day'
+                LIMIT $3
+            ) RETURNING blob_key"#
+        )
+        .bind(fetch_id)
+        .bind(days)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Delete up to `batch` rows past the newest `keep`
+    pub async fn prune_keep_last(&self, fetch_id: i32, keep: i64, batch: i64) -> Result<Vec<Option<String>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<String>>(
+            r#"DELETE FROM fetch_api_data WHERE id IN (
+                SELECT id FROM fetch_api_data
+                WHERE fetch_id = $1
+                ORDER BY created_at DESC, id DESC
+                OFFSET $2 LIMIT $3
+            ) RETURNING blob_key"#
+        )
+        .bind(fetch_id)
+        .bind(keep)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Delete up to `batch` of the oldest rows past `max_bytes`
+    pub async fn prune_max_bytes(&self, fetch_id: i32, max_bytes: i64, batch: i64) -> Result<Vec<Option<String>>, sqlx::Error> {
+        sqlx::query_scalar::<_, Option<String>>(
+            r#"DELETE FROM fetch_api_data WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, SUM(COALESCE(octet_length(response), 0) + COALESCE(octet_length(response_headers::text), 0) + COALESCE(blob_size, 0))
+                        OVER (ORDER BY created_at DESC, id DESC) AS total
+                    FROM fetch_api_data
+                    WHERE fetch_id = $1
+                ) t
+                WHERE total > $2
+                LIMIT $3
+            ) RETURNING blob_key"#
+        )
+        .bind(fetch_id)
+        .bind(max_bytes)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Rows and body bytes of every fetch, largest first
+    pub async fn storage_by_fetch(&self) -> Result<Vec<FetchStorage>, sqlx::Error> {
+        sqlx::query_as::<_, FetchStorage>(
+            r#"SELECT f.id AS fetch_id, f.name, o.user_id AS owner_id,
+                COUNT(d.id) AS rows,
+                COALESCE(SUM(COALESCE(octet_length(d.response), 0) + COALESCE(octet_length(d.response_headers::text), 0) + COALESCE(d.blob_size, 0)), 0)::bigint AS bytes,
+                MIN(d.created_at) AS oldest_at,
+                MAX(d.created_at) AS newest_at
+            FROM fetch_api f
+            LEFT JOIN LATERAL (
+                SELECT user_id FROM fetch_api_members
+                WHERE fetch_id = f.id AND role = 'owner'
+                ORDER BY user_id LIMIT 1
+            ) o ON TRUE
+            LEFT JOIN fetch_api_data d ON d.fetch_id = f.id
+            GROUP BY f.id, f.name, o.user_id
+            ORDER BY bytes DESC, f.id ASC"#
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Rows and body bytes of the fetches each user owns
+    pub async fn storage_by_user(&self) -> Result<Vec<UserStorage>, sqlx::Error> {
+        sqlx::query_as::<_, UserStorage>(
+            r#"SELECT u.id AS user_id, u.username,
+                COUNT(DISTINCT m.fetch_id) AS fetches,
+                COUNT(d.id) AS rows,
+                COALESCE(SUM(COALESCE(octet_length(d.response), 0) + COALESCE(octet_length(d.response_headers::text), 0) + COALESCE(d.blob_size, 0)), 0)::bigint AS bytes
+            FROM users u
+            INNER JOIN fetch_api_members m ON m.user_id = u.id AND m.role = 'owner'
+            LEFT JOIN fetch_api_data d ON d.fetch_id = m.fetch_id
+            GROUP BY u.id, u.username
+            ORDER BY bytes DESC, u.id ASC"#
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
     pub async fn find_all(&self, fetch_id: i32) -> Result<Vec<ApiData>, sqlx::Error> {
         sqlx::query_as::<_,ApiData> (
             r#"SELECT * FROM fetch_api_data WHERE fetch_id = $1 ORDER BY updated_at DESC"#
@@ -370,10 +661,59 @@ impl FetchDataRepository {
         .await
     }
 
+    /// One page of data, keyset on the sort column then id
+    pub async fn find_page(&self, fetch_id: i32, filter: &ApiDataFilter, cursor: Option<&DataCursor>, limit: i64) -> Result<Vec<ApiData>, sqlx::Error> {
+        let sort = filter.sort.unwrap_or_default();
+        let (cmp, dir) = match filter.order.unwrap_or_default() {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+
+        let mut q = QueryBuilder::<Postgres>::new("SELECT d.* FROM fetch_api_data d WHERE d.fetch_id = ");
+        q.push_bind(fetch_id);
+        if let Some(since) = filter.since {
+            q.push(" AND d.created_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            q.push(" AND d.created_at < ").push_bind(until);
+        }
+        if let Some(status) = filter.status {
+            q.push(" AND d.status_code = ").push_bind(status);
+        }
+        if let Some(status_min) = filter.status_min {
+            q.push(" AND d.status_code >= ").push_bind(status_min);
+        }
+        if let Some(status_max) = filter.status_max {
+            q.push(" AND d.status_code <= ").push_bind(status_max);
+        }
+        if let Some(contains) = &filter.contains {
+            // Spilled bodies are not in the table, callers match them with `blob::retain_contains`
+            q.push(" AND (d.blob_key IS NOT NULL OR strpos(d.response, ").push_bind(contains.clone()).push(") > 0)");
+        }
+        if let Some(outcome) = &filter.outcome {
+            q.push(" AND EXISTS (SELECT 1 FROM fetch_runs r WHERE r.data_id = d.id AND r.outcome = ")
+                .push_bind(outcome.clone())
+                .push(")");
+        }
+        if let Some(cursor) = cursor {
+            q.push(format!(" AND ({}, d.id) {} (", sort.column(), cmp));
+            match sort {
+                DataSort::CreatedAt | DataSort::UpdatedAt => q.push_bind(cursor.at),
+                DataSort::StatusCode => q.push_bind(cursor.status),
+            };
+            q.push(", ").push_bind(cursor.id).push(")");
+        }
+        q.push(format!(" ORDER BY {} {}, d.id {} LIMIT ", sort.column(), dir, dir)).push_bind(limit);
+
+        q.build_query_as::<ApiData>()
+            .fetch_all(&self.pool)
+            .await
+    }
+
     pub async fn create(&self, data: CreateApiData) -> Result<ApiData, sqlx::Error>{
         sqlx::query_as::<_,ApiData> (
-            r#"INSERT INTO fetch_api_data (fetch_id, name, status_code, response, response_headers)
-            VALUES ($1, $2, $3, $4, $5)
+            r#"INSERT INTO fetch_api_data (fetch_id, name, status_code, response, response_headers, assertions, passed, schema_errors, body_hash, changed, content_type, encoding, truncated, blob_key, blob_size, matrix_row)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, COALESCE($12, 'text'), COALESCE($13, FALSE), $14, $15, $16)
             RETURNING *
             "#
         )
@@ -382,6 +722,17 @@ impl FetchDataRepository {
         .bind(data.status_code)
         .bind(data.response)
         .bind(data.response_headers)
+        .bind(data.assertions)
+        .bind(data.passed)
+        .bind(data.schema_errors)
+        .bind(data.body_hash)
+        .bind(data.changed)
+        .bind(data.content_type)
+        .bind(data.encoding)
+        .bind(data.truncated)
+        .bind(data.blob_key)
+        .bind(data.blob_size)
+        .bind(data.matrix_row)
         .fetch_one(&self.pool)
         .await
     }
@@ -411,3 +762,625 @@ impl FetchDataRepository {
         .await
     }
 }
+
+impl FetchEnvironmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<ApiEnvironment, sqlx::Error> {
+        sqlx::query_as::<_, ApiEnvironment> (
+            r#"SELECT * FROM fetch_api_environment WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Owned environments and environments used by fetches the user is member of
+    pub async fn find_all(&self, user_id: i32) -> Result<Vec<ApiEnvironment>, sqlx::Error> {
+        sqlx::query_as::<_, ApiEnvironment> (
+            r#"SELECT e.* FROM fetch_api_environment e
+            WHERE e.user_id = $1
+               OR e.id IN (
+                    SELECT f.environment_id FROM fetch_api f
+                    INNER JOIN fetch_api_members m ON f.id = m.fetch_id
+                    WHERE m.user_id = $1
+               )
+            ORDER BY e.name ASC
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Environment used by a fetch the user is member of
+    pub async fn is_shared(&self, id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar::<_, bool> (
+            r#"SELECT EXISTS (
+                SELECT 1 FROM fetch_api f
+                INNER JOIN fetch_api_members m ON f.id = m.fetch_id
+                WHERE f.environment_id = $1 AND m.user_id = $2
+            )"#
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, data: CreateApiEnvironment) -> Result<ApiEnvironment, sqlx::Error>{
+        sqlx::query_as::<_, ApiEnvironment>(
+            r#"INSERT INTO fetch_api_environment (user_id, name, description, variables)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#
+        )
+        .bind(data.user_id)
+        .bind(data.name)
+        .bind(data.description)
+        .bind(data.variables)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn update(&self, id: i32, data: UpdateApiEnvironment) -> Result<ApiEnvironment, sqlx::Error>{
+        sqlx::query_as::<_, ApiEnvironment>(
+            r#"UPDATE fetch_api_environment
+            SET
+                name        = COALESCE($1, name),
+                description = COALESCE($2, description),
+                variables   = COALESCE($3, variables)
+            WHERE id=$4
+            RETURNING *
+            "#
+        )
+        .bind(data.name)
+        .bind(data.description)
+        .bind(data.variables)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<ApiEnvironment, sqlx::Error> {
+        sqlx::query_as::<_, ApiEnvironment> (
+            r#"DELETE FROM fetch_api_environment WHERE id=$1 RETURNING *"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl FetchDependencyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<ApiDependency, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"SELECT * FROM fetch_api_dependency WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Edges where the fetch is upstream or downstream
+    pub async fn find_related(&self, fetch_id: i32) -> Result<Vec<ApiDependency>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"SELECT * FROM fetch_api_dependency WHERE upstream_id = $1 OR downstream_id = $1 ORDER BY id"#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_downstream(&self, upstream_id: i32) -> Result<Vec<ApiDependency>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"SELECT * FROM fetch_api_dependency WHERE upstream_id = $1 ORDER BY id"#
+        )
+        .bind(upstream_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Every edge reachable downstream from the fetch
+    pub async fn find_reachable(&self, fetch_id: i32) -> Result<Vec<ApiDependency>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"WITH RECURSIVE reach(id) AS (
+                SELECT $1::int
+                UNION
+                SELECT d.downstream_id FROM fetch_api_dependency d
+                INNER JOIN reach r ON d.upstream_id = r.id
+            )
+            SELECT d.* FROM fetch_api_dependency d
+            WHERE d.upstream_id IN (SELECT id FROM reach)
+            ORDER BY d.id
+            "#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Every edge connected to the fetch, both directions
+    pub async fn find_graph(&self, fetch_id: i32) -> Result<Vec<ApiDependency>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"WITH RECURSIVE component(id) AS (
+                SELECT $1::int
+                UNION
+                SELECT CASE WHEN d.upstream_id = c.id THEN d.downstream_id ELSE d.upstream_id END
+                FROM fetch_api_dependency d
+                INNER JOIN component c ON d.upstream_id = c.id OR d.downstream_id = c.id
+            )
+            SELECT d.* FROM fetch_api_dependency d
+            WHERE d.upstream_id IN (SELECT id FROM component)
+            ORDER BY d.id
+            "#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_nodes(&self, fetch_ids: &[i32]) -> Result<Vec<ApiDagNode>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDagNode> (
+            r#"SELECT id, name, type, is_active FROM fetch_api WHERE id = ANY($1) ORDER BY id"#
+        )
+        .bind(fetch_ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, upstream_id: i32, downstream_id: i32, trigger: DependencyTrigger) -> Result<ApiDependency, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"INSERT INTO fetch_api_dependency (upstream_id, downstream_id, trigger)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#
+        )
+        .bind(upstream_id)
+        .bind(downstream_id)
+        .bind(trigger)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<ApiDependency, sqlx::Error> {
+        sqlx::query_as::<_, ApiDependency> (
+            r#"DELETE FROM fetch_api_dependency WHERE id = $1 RETURNING *"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl FetchDatasetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<ApiDataset, sqlx::Error> {
+        sqlx::query_as::<_, ApiDataset> (
+            r#"SELECT * FROM fetch_api_dataset WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn find_all(&self, user_id: i32) -> Result<Vec<ApiDataset>, sqlx::Error> {
+        sqlx::query_as::<_, ApiDataset> (
+            r#"SELECT * FROM fetch_api_dataset WHERE user_id = $1 ORDER BY name ASC"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, user_id: i32, name: String, rows: Value) -> Result<ApiDataset, sqlx::Error> {
+        sqlx::query_as::<_, ApiDataset> (
+            r#"INSERT INTO fetch_api_dataset (user_id, name, rows)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(rows)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn update(&self, id: i32, name: Option<String>, rows: Option<Value>) -> Result<ApiDataset, sqlx::Error> {
+        sqlx::query_as::<_, ApiDataset> (
+            r#"UPDATE fetch_api_dataset
+            SET
+                name = COALESCE($1, name),
+                rows = COALESCE($2, rows)
+            WHERE id = $3
+            RETURNING *
+            "#
+        )
+        .bind(name)
+        .bind(rows)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<ApiDataset, sqlx::Error> {
+        sqlx::query_as::<_, ApiDataset> (
+            r#"DELETE FROM fetch_api_dataset WHERE id = $1 RETURNING *"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl FetchMatrixRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<ApiMatrixRun, sqlx::Error> {
+        sqlx::query_as::<_, ApiMatrixRun> (
+            r#"SELECT * FROM fetch_api_matrix_run WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn find_all(&self, fetch_id: i32) -> Result<Vec<ApiMatrixRun>, sqlx::Error> {
+        sqlx::query_as::<_, ApiMatrixRun> (
+            r#"SELECT * FROM fetch_api_matrix_run WHERE fetch_id = $1 ORDER BY started_at DESC, id DESC LIMIT 100"#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Run of the fan out job, the run created by an earlier attempt of the job when there is one
+    pub async fn create(&self, fetch_id: i32, total: i32, job_id: &str) -> Result<ApiMatrixRun, sqlx::Error> {
+        sqlx::query_as::<_, ApiMatrixRun> (
+            r#"INSERT INTO fetch_api_matrix_run (fetch_id, total, job_id) VALUES ($1, $2, $3)
+            ON CONFLICT (job_id) DO UPDATE SET job_id = EXCLUDED.job_id
+            RETURNING *"#
+        )
+        .bind(fetch_id)
+        .bind(total)
+        .bind(job_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Claim a row of the run, false when the row job was already enqueued
+    pub async fn claim_row(&self, run_id: i32, index: i32) -> Result<bool, sqlx::Error> {
+        let claimed = sqlx::query(
+            r#"INSERT INTO fetch_api_matrix_row (run_id, row_index) VALUES ($1, $2) ON CONFLICT DO NOTHING"#
+        )
+        .bind(run_id)
+        .bind(index)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(claimed.rows_affected() == 1)
+    }
+
+    /// Give a row back when its job could not be enqueued
+    pub async fn release_row(&self, run_id: i32, index: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"DELETE FROM fetch_api_matrix_row WHERE run_id = $1 AND row_index = $2"#)
+            .bind(run_id)
+            .bind(index)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Count one row result, run finished when every row reported.
+    /// None when the row was already counted by an earlier attempt of its job
+    pub async fn record(&self, id: i32, index: i32, success: bool, result: Value) -> Result<Option<ApiMatrixRun>, sqlx::Error> {
+        sqlx::query_as::<_, ApiMatrixRun> (
+            r#"WITH marked AS (
+                UPDATE fetch_api_matrix_row SET recorded_at = NOW()
+                WHERE run_id = $1 AND row_index = $2 AND recorded_at IS NULL
+                RETURNING run_id
+            )
+            UPDATE fetch_api_matrix_run
+            SET
+                succeeded   = succeeded + CASE WHEN $3 THEN 1 ELSE 0 END,
+                failed      = failed + CASE WHEN $3 THEN 0 ELSE 1 END,
+                results     = results || jsonb_build_array($4::jsonb),
+                finished_at = CASE WHEN succeeded + failed + 1 >= total THEN NOW() ELSE finished_at END
+            WHERE id = $1 AND EXISTS (SELECT 1 FROM marked)
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(index)
+        .bind(success)
+        .bind(result)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
+
+impl FetchRunRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    /// Delete up to `batch` runs started more than `days` ago
+    pub async fn prune_older_than(&self, fetch_id: i32, days: i64, batch: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"DELETE FROM fetch_runs WHERE id IN (
+                SELECT id FROM fetch_runs
+                WHERE fetch_id = $1 AND started_at < NOW() - $2 * INTERVAL '1 day'
+                LIMIT $3
+            ) RETURNING id"#
+        )
+        .bind(fetch_id)
+        .bind(days)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Delete up to `batch` runs past the newest `keep`
+    pub async fn prune_keep_last(&self, fetch_id: i32, keep: i64, batch: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"DELETE FROM fetch_runs WHERE id IN (
+                SELECT id FROM fetch_runs
+                WHERE fetch_id = $1
+                ORDER BY started_at DESC, id DESC
+                OFFSET $2 LIMIT $3
+            ) RETURNING id"#
+        )
+        .bind(fetch_id)
+        .bind(keep)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_all(&self, fetch_id: i32, filter: &ApiRunFilter, limit: i64, offset: i64) -> Result<Vec<ApiRun>, sqlx::Error> {
+        sqlx::query_as::<_, ApiRun> (
+            r#"SELECT * FROM fetch_runs
+            WHERE fetch_id = $1
+                AND ($2::fetch_run_outcome IS NULL OR outcome = $2)
+                AND ($3::fetch_run_trigger IS NULL OR trigger = $3)
+                AND ($4::varchar IS NULL OR error_kind = $4)
+                AND ($5::timestamptz IS NULL OR started_at >= $5)
+                AND ($6::timestamptz IS NULL OR started_at < $6)
+            ORDER BY started_at DESC, id DESC
+            LIMIT $7 OFFSET $8"#
+        )
+        .bind(fetch_id)
+        .bind(&filter.outcome)
+        .bind(&filter.trigger)
+        .bind(&filter.error_kind)
+        .bind(filter.since)
+        .bind(filter.until)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, data: CreateApiRun) -> Result<ApiRun, sqlx::Error> {
+        sqlx::query_as::<_, ApiRun> (
+            r#"INSERT INTO fetch_runs
+            (fetch_id, job_id, attempt, trigger, outcome, status_code, error_kind, error_message, worker_id, data_id, started_at, finished_at, duration_ms, timing)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING *"#
+        )
+        .bind(data.fetch_id)
+        .bind(data.job_id)
+        .bind(data.attempt)
+        .bind(data.trigger)
+        .bind(data.outcome)
+        .bind(data.status_code)
+        .bind(data.error_kind)
+        .bind(data.error_message)
+        .bind(data.worker_id)
+        .bind(data.data_id)
+        .bind(data.started_at)
+        .bind(data.finished_at)
+        .bind(data.duration_ms)
+        .bind(data.timing)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Jobs run before `job_id` and when the last of them started, retries of a job count once
+    pub async fn previous_runs(&self, fetch_id: i32, job_id: Option<&str>) -> Result<(i64, Option<DateTime<Utc>>), sqlx::Error> {
+        sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)> (
+            r#"SELECT COUNT(DISTINCT job_id)::bigint, MAX(started_at)
+            FROM fetch_runs
+            WHERE fetch_id = $1
+                AND ($2::varchar IS NULL OR job_id IS DISTINCT FROM $2)"#
+        )
+        .bind(fetch_id)
+        .bind(job_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Variables saved by post-response scripts of the fetch
+    pub async fn script_variables(&self, fetch_id: i32) -> Result<Option<Value>, sqlx::Error> {
+        sqlx::query_scalar::<_, Value> (
+            r#"SELECT variables FROM fetch_api_script_state WHERE fetch_id = $1"#
+        )
+        .bind(fetch_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Merge script variables into the saved ones
+    pub async fn save_script_variables(&self, fetch_id: i32, variables: Value) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO fetch_api_script_state (fetch_id, variables)
+            VALUES ($1, $2)
+            ON CONFLICT (fetch_id) DO UPDATE
+            SET variables  = fetch_api_script_state.variables || EXCLUDED.variables,
+                updated_at = NOW()"#
+        )
+        .bind(fetch_id)
+        .bind(variables)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop saved script variables, the fetch variables apply again
+    pub async fn clear_script_variables(&self, fetch_id: i32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"DELETE FROM fetch_api_script_state WHERE fetch_id = $1"#
+        )
+        .bind(fetch_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Percentiles of every numeric timing metric in the window
+    pub async fn timing_stats(&self, fetch_id: i32, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<TimingStat>, sqlx::Error> {
+        sqlx::query_as::<_, TimingStat> (
+            r#"SELECT m.metric,
+                COUNT(*) AS count,
+                MIN(m.value) AS min,
+                MAX(m.value) AS max,
+                AVG(m.value) AS avg,
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY m.value) AS p50,
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY m.value) AS p95,
+                percentile_cont(0.99) WITHIN GROUP (ORDER BY m.value) AS p99
+            FROM fetch_runs r
+            CROSS JOIN LATERAL (
+                SELECT key AS metric, value::text::float8 AS value
+                FROM jsonb_each(r.timing)
+                WHERE jsonb_typeof(value) = 'number'
+            ) m
+            WHERE r.fetch_id = $1 AND r.timing IS NOT NULL AND r.started_at >= $2 AND r.started_at < $3
+            GROUP BY m.metric
+            ORDER BY m.metric"#
+        )
+        .bind(fetch_id)
+        .bind(since)
+        .bind(until)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+impl FetchMetricRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {pool}
+    }
+
+    /// Delete up to `batch` values recorded more than `days` ago
+    pub async fn prune_older_than(&self, fetch_id: i32, days: i64, batch: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"DELETE FROM fetch_metrics WHERE id IN (
+                SELECT id FROM fetch_metrics
+                WHERE fetch_id = $1 AND recorded_at < NOW() - $2 * INTERVAL '1 day'
+                LIMIT $3
+            ) RETURNING id"#
+        )
+        .bind(fetch_id)
+        .bind(days)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Delete up to `batch` values past the newest `keep` of each series
+    pub async fn prune_keep_last(&self, fetch_id: i32, keep: i64, batch: i64) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            r#"DELETE FROM fetch_metrics WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (PARTITION BY name ORDER BY recorded_at DESC, id DESC) AS position
+                    FROM fetch_metrics
+                    WHERE fetch_id = $1
+                ) t
+                WHERE position > $2
+                LIMIT $3
+            ) RETURNING id"#
+        )
+        .bind(fetch_id)
+        .bind(keep)
+        .bind(batch)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Insert every value of one run
+    pub async fn create_many(&self, fetch_id: i32, data_id: Option<i32>, values: &[MetricValue]) -> Result<Vec<ApiMetric>, sqlx::Error> {
+        let names: Vec<&str> = values.iter().map(|v| v.name.as_str()).collect();
+        let nums: Vec<Option<f64>> = values.iter().map(|v| v.value_num).collect();
+        let texts: Vec<Option<&str>> = values.iter().map(|v| v.value_text.as_deref()).collect();
+
+        sqlx::query_as::<_, ApiMetric> (
+            r#"INSERT INTO fetch_metrics (fetch_id, data_id, name, value_num, value_text)
+            SELECT $1, $2, m.name, m.value_num, m.value_text
+            FROM UNNEST($3::varchar[], $4::float8[], $5::text[]) AS m(name, value_num, value_text)
+            RETURNING *"#
+        )
+        .bind(fetch_id)
+        .bind(data_id)
+        .bind(names)
+        .bind(nums)
+        .bind(texts)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_series(&self, fetch_id: i32) -> Result<Vec<MetricSeries>, sqlx::Error> {
+        sqlx::query_as::<_, MetricSeries> (
+            r#"SELECT name, COUNT(*) AS count, MIN(recorded_at) AS first_at, MAX(recorded_at) AS last_at
+            FROM fetch_metrics
+            WHERE fetch_id = $1
+            GROUP BY name
+            ORDER BY name"#
+        )
+        .bind(fetch_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Values of one series grouped into buckets of `bucket` seconds
+    pub async fn find_points(&self, fetch_id: i32, name: &str, since: DateTime<Utc>, until: DateTime<Utc>, bucket: i64, agg: MetricAgg) -> Result<Vec<MetricPoint>, sqlx::Error> {
+        sqlx::query_as::<_, MetricPoint> (
+            r#"SELECT to_timestamp(floor(extract(epoch FROM recorded_at) / $5) * $5) AS bucket,
+                COUNT(*) AS count,
+                CASE $6::text
+                    WHEN 'min' THEN MIN(value_num)
+                    WHEN 'max' THEN MAX(value_num)
+                    WHEN 'sum' THEN SUM(value_num)
+                    WHEN 'last' THEN (array_agg(value_num ORDER BY recorded_at DESC, id DESC))[1]
+                    ELSE AVG(value_num)
+                END AS value,
+                (array_agg(value_text ORDER BY recorded_at DESC, id DESC))[1] AS text
+            FROM fetch_metrics
+            WHERE fetch_id = $1 AND name = $2 AND recorded_at >= $3 AND recorded_at < $4
+            GROUP BY 1
+            ORDER BY 1"#
+        )
+        .bind(fetch_id)
+        .bind(name)
+        .bind(since)
+        .bind(until)
+        .bind(bucket as f64)
+        .bind(agg.as_str())
+        .fetch_all(&self.pool)
+        .await
+    }
+}
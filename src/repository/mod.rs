@@ -1,4 +1,5 @@
 pub mod user;
 pub mod token;
 pub mod fetch;
-pub mod apikey;
\ No newline at end of file
+pub mod apikey;
+pub mod secret;
\ No newline at end of file
@@ -0,0 +1,94 @@
+use sqlx::PgPool;
+use crate::models::secret::{CreateSecret, Secret};
+
+pub struct SecretRepository {
+    pool: PgPool,
+}
+
+impl SecretRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Secret, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"SELECT * FROM user_secrets WHERE id = $1"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn find_all_user(&self, user_id: i32) -> Result<Vec<Secret>, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"SELECT * FROM user_secrets WHERE user_id = $1 ORDER BY name"#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Secrets of the user by name
+    pub async fn find_by_names(&self, user_id: i32, names: &[String]) -> Result<Vec<Secret>, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"SELECT * FROM user_secrets WHERE user_id = $1 AND name = ANY($2)"#
+        )
+        .bind(user_id)
+        .bind(names)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn find_by_key_version(&self, key_version: i32) -> Result<Vec<Secret>, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"SELECT * FROM user_secrets WHERE key_version <> $1"#
+        )
+        .bind(key_version)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create(&self, data: CreateSecret) -> Result<Secret, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"INSERT INTO user_secrets (user_id, name, description, ciphertext, key_version)
+                    VALUES ($1, $2, $3, $4, $5)
+                    RETURNING *
+                "#
+        )
+        .bind(data.user_id)
+        .bind(data.name)
+        .bind(data.description)
+        .bind(data.ciphertext)
+        .bind(data.key_version)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn update(&self, id: i32, description: Option<String>, ciphertext: Option<Vec<u8>>, key_version: Option<i32>) -> Result<Secret, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"UPDATE user_secrets
+                    SET
+                    description = COALESCE($1, description),
+                    ciphertext = COALESCE($2, ciphertext),
+                    key_version = COALESCE($3, key_version)
+                    WHERE id = $4
+                    RETURNING *
+                "#
+        )
+        .bind(description)
+        .bind(ciphertext)
+        .bind(key_version)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<Secret, sqlx::Error> {
+        sqlx::query_as::<_, Secret>(
+            r#"DELETE FROM user_secrets WHERE id = $1 RETURNING *"#
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
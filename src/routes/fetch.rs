@@ -7,9 +7,12 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/fetch", get(get_all))
         .route("/fetch", post(create_fetch_api))
+        .route("/fetch/storage", get(get_fetch_storage))
         .route("/fetch/{id}", get(get_fetch_api))
         .route("/fetch/{id}", patch(update_fetch_api))
         .route("/fetch/{id}", delete(delete_fetch_api))
+        .route("/fetch/{id}/render", post(render_fetch_api))
+        .route("/fetch/{id}/clone", post(clone_fetch_api))
 
         .route("/fetch/{job_id}/job", get(get_fetch_job))
 
@@ -19,11 +22,27 @@ pub fn routes() -> Router<AppState> {
         .route("/fetch/{fetch_id}/member/{id}", patch(update_fetch_member))
         .route("/fetch/{fetch_id}/member/{id}", delete(delete_fetch_member))
         
+        .route("/fetch/{fetch_id}/dependency", get(get_all_dependency))
+        .route("/fetch/{fetch_id}/dependency", post(create_fetch_dependency))
+        .route("/fetch/{fetch_id}/dependency/{id}", delete(delete_fetch_dependency))
+        .route("/fetch/{fetch_id}/dag", get(get_fetch_dag))
+
+        .route("/fetch/{fetch_id}/runs", get(get_all_fetch_run))
+        .route("/fetch/{fetch_id}/runs/stats", get(get_fetch_run_stats))
+        .route("/fetch/{fetch_id}/metrics", get(get_all_fetch_metric))
+        .route("/fetch/{fetch_id}/metrics/{name}", get(get_fetch_metric))
+
+        .route("/fetch/{fetch_id}/matrix", get(get_all_matrix_run))
+        .route("/fetch/{fetch_id}/matrix/{id}", get(get_fetch_matrix_run))
+
         .route("/fetch/{fetch_id}/data", post(create_fetch_data))
         .route("/fetch/{fetch_id}/data", get(get_all_data))
+        .route("/fetch/{fetch_id}/data/export", get(export_fetch_data))
         .route("/fetch/{fetch_id}/data/{id}", get(get_fetch_data))
         .route("/fetch/{fetch_id}/data/{id}", patch(update_fetch_data))
         .route("/fetch/{fetch_id}/data/{id}", delete(delete_fetch_data))
+        .route("/fetch/{fetch_id}/data/{id}/diff", get(get_fetch_data_diff))
+        .route("/fetch/{fetch_id}/data/{id}/raw", get(get_fetch_data_raw))
 
         .route("/fetch/execute", get(get_all_execute))
         .route("/fetch/execute", post(create_fetch_execute))
@@ -37,4 +56,22 @@ pub fn routes() -> Router<AppState> {
         .route("/fetch/header/{id}", patch(update_fetch_header))
         .route("/fetch/header/{id}", delete(delete_fetch_header))
 
+        .route("/fetch/auth", get(get_all_auth))
+        .route("/fetch/auth", post(create_fetch_auth))
+        .route("/fetch/auth/{id}", get(get_fetch_auth))
+        .route("/fetch/auth/{id}", patch(update_fetch_auth))
+        .route("/fetch/auth/{id}", delete(delete_fetch_auth))
+
+        .route("/fetch/environment", get(get_all_environment))
+        .route("/fetch/environment", post(create_fetch_environment))
+        .route("/fetch/environment/{id}", get(get_fetch_environment))
+        .route("/fetch/environment/{id}", patch(update_fetch_environment))
+        .route("/fetch/environment/{id}", delete(delete_fetch_environment))
+
+        .route("/fetch/dataset", get(get_all_dataset))
+        .route("/fetch/dataset", post(create_fetch_dataset))
+        .route("/fetch/dataset/{id}", get(get_fetch_dataset))
+        .route("/fetch/dataset/{id}", patch(update_fetch_dataset))
+        .route("/fetch/dataset/{id}", delete(delete_fetch_dataset))
+
 }
\ No newline at end of file
@@ -18,4 +18,11 @@ pub fn routes() -> Router<AppState> {
         .route("/user/apikey/{id}", patch(update_api_key))
         .route("/user/apikey/{id}", delete(delete_api_key))
         .route("/user/apikey/{id}", post(rotate_api_key))
+
+        .route("/user/secret", get(get_all_secret))
+        .route("/user/secret", post(create_secret))
+        .route("/user/secret/rotate", post(rotate_secrets))
+        .route("/user/secret/{id}", get(get_secret))
+        .route("/user/secret/{id}", patch(update_secret))
+        .route("/user/secret/{id}", delete(delete_secret))
 }
\ No newline at end of file
@@ -95,7 +95,7 @@ impl AuthService {
             .await.map_err(|e| {AppError::NotFound(format!("Database: {}", e))})?;
 
         if q.user_id != user.id {
-            return Err(AppError::Forbidden(format!("You don't have permission to access this data")))?;
+            return Err(AppError::Forbidden("You don't have permission to access this data".to_string()))?;
         }
         
         Ok(q)
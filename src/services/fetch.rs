@@ -1,8 +1,11 @@
 use apalis::prelude::Storage;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures_util::Stream;
+use serde_json::Value;
 use axum::{extract::{FromRef, FromRequestParts}, http::request::Parts};
 use chrono::{Utc, Duration};
 use tracing::{warn,info};
-use crate::{models::{fetch::{Api, ApiData, ApiDataResponse, ApiExecute, ApiHeader, ApiMembers, CreateApiData, CreateApiExecute, CreateApiHeader, CreateApiMembers, ExecuteType, ReqCreateApi, ReqCreateApiData, ReqCreateApiExecute, ReqCreateApiHeader, Role, UpdateApi, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers}, user::User}, repository::fetch::{FetchDataRepository, FetchExecuteRepository, FetchHeaderRepository, FetchMemberRepository, FetchRepository}, state::AppState, utils::response::AppError};
+use crate::{jobs::{assertion, blob, change, dependency, export, matrix, metric, redact, retention, schema, script, secret, workflow, client::build_client, template::{self, TemplateContext, validate_variables}}, models::{fetch::{MASKED_VALUE, Api, ApiType, ApiDataDiff, ApiDataExport, BodyEncoding, ApiDataFilter, DataCursor, ApiDataset, ApiMatrixRun, ApiRun, ApiRunFilter, ApiTimingFilter, TimingStat, ApiMetricFilter, MetricPoint, MetricSeries, StorageReport, ReqCreateApiDataset, UpdateApiDataset, ApiDag, ApiDependency, DependencyTrigger, ReqCreateApiDependency, RenderedRequest, ApiAuth, ApiBody, ApiData, ApiMethod, ApiOptions, AuthConfig, AuthType, CreateApiAuth, ReqCreateApiAuth, UpdateApiAuth, ApiEnvironment, ReqCloneApi, ReqCreateApiEnvironment, UpdateApiEnvironment, ApiDataResponse, ApiExecute, ApiHeader, ApiMembers, CreateApiData, CreateApiExecute, CreateApiHeader, CreateApiMembers, ExecuteType, MultipartPart, ReqCreateApi, ReqCreateApiData, ReqCreateApiExecute, ReqCreateApiHeader, Role, UpdateApi, UpdateApiData, UpdateApiExecute, UpdateApiHeader, UpdateApiMembers}, user::User}, repository::fetch::{FetchAuthRepository, FetchDataRepository, FetchDatasetRepository, FetchDependencyRepository, FetchMatrixRepository, FetchMetricRepository, FetchRunRepository, FetchEnvironmentRepository, FetchExecuteRepository, FetchHeaderRepository, FetchMemberRepository, FetchRepository}, state::AppState, utils::{body, response::{AppError, Pagination}}};
 
 #[allow(dead_code)]
 pub struct FetchService {
@@ -11,6 +14,13 @@ pub struct FetchService {
     execute_repo: FetchExecuteRepository,
     header_repo: FetchHeaderRepository,
     data_repo: FetchDataRepository,
+    auth_repo: FetchAuthRepository,
+    env_repo: FetchEnvironmentRepository,
+    dependency_repo: FetchDependencyRepository,
+    dataset_repo: FetchDatasetRepository,
+    matrix_repo: FetchMatrixRepository,
+    run_repo: FetchRunRepository,
+    metric_repo: FetchMetricRepository,
     state: AppState,
 }
 
@@ -21,7 +31,14 @@ impl FetchService {
         let execute_repo = FetchExecuteRepository::new(state.database.clone());
         let header_repo = FetchHeaderRepository::new(state.database.clone());
         let data_repo = FetchDataRepository::new(state.database.clone());
-        Self {fetch_repo, member_repo, execute_repo, header_repo, data_repo, state}
+        let auth_repo = FetchAuthRepository::new(state.database.clone());
+        let env_repo = FetchEnvironmentRepository::new(state.database.clone());
+        let dependency_repo = FetchDependencyRepository::new(state.database.clone());
+        let dataset_repo = FetchDatasetRepository::new(state.database.clone());
+        let matrix_repo = FetchMatrixRepository::new(state.database.clone());
+        let run_repo = FetchRunRepository::new(state.database.clone());
+        let metric_repo = FetchMetricRepository::new(state.database.clone());
+        Self {fetch_repo, member_repo, execute_repo, header_repo, data_repo, auth_repo, env_repo, dependency_repo, dataset_repo, matrix_repo, run_repo, metric_repo, state}
     }
 
     // Create apalis job
@@ -45,7 +62,7 @@ impl FetchService {
         Ok(apalis.task_id.to_string())
     }
 
-    /// #API AREA
+    // #API AREA
 
     /// get all fetch user
     pub async fn get_all_fetch_user(&self, user: User) -> Result<Vec<Api>, AppError> {
@@ -99,22 +116,39 @@ impl FetchService {
     }
     
     pub async fn create_fetch(&self, data: ReqCreateApi, user: User) -> Result<Api, AppError> {
-        let model = data.into_model();
+        validate_request_body(&data.query, &data.body)?;
+        validate_method(&data.method, &data.custom_method)?;
+        validate_options(&data.options)?;
+        validate_workflow(&data.r#type, &data.options)?;
+        if let Some(auth_id) = data.auth_id {
+            self.check_auth_owner(&user, auth_id).await?;
+        }
+        if let Some(env_id) = data.environment_id {
+            self.check_environment_owner(&user, env_id).await?;
+        }
+        self.check_dataset_owner(&user, &data.options).await?;
+        let mut model = data.into_model();
+        model.created_by = Some(user.id);
         let fetch = self.fetch_repo.create(model)
             .await
             .map_err(|e|{
-                if let Some(db_error) = e.as_database_error() {
-                    if db_error.is_foreign_key_violation() {
-                        match db_error.constraint() {
-                            Some("fk_fetch_execute") => {
-                                return AppError::BadRequest("Execute ID not found. Please create execute first.".to_string());
-                            },
-                            Some("fk_fetch_header") => {
-                                return AppError::BadRequest("Header ID not found. Please create header first.".to_string());
-                            },
-                            _ => {
-                                return AppError::BadRequest("Reference not found.".to_string());
-                            }
+                if let Some(db_error) = e.as_database_error()
+                    && db_error.is_foreign_key_violation() {
+                    match db_error.constraint() {
+                        Some("fk_fetch_execute") => {
+                            return AppError::BadRequest("Execute ID not found. Please create execute first.".to_string());
+                        },
+                        Some("fk_fetch_header") => {
+                            return AppError::BadRequest("Header ID not found. Please create header first.".to_string());
+                        },
+                        Some("fk_fetch_auth") => {
+                            return AppError::BadRequest("Auth ID not found. Please create auth profile first.".to_string());
+                        },
+                        Some("fk_fetch_environment") => {
+                            return AppError::BadRequest("Environment ID not found. Please create environment first.".to_string());
+                        },
+                        _ => {
+                            return AppError::BadRequest("Reference not found.".to_string());
                         }
                     }
                 }
@@ -137,6 +171,20 @@ impl FetchService {
 
     /// Update fetch viewer not allowed
     pub async fn update_fetch(&self, id: &i32, data: UpdateApi, user: User) -> Result<Api, AppError> {
+        validate_request_body(&data.query, &data.body)?;
+        validate_options(&data.options)?;
+        if data.r#type.is_some() || data.options.is_some() {
+            let fetch = self.fetch_repo.get_by_id(id).await?;
+            let fetch_type = data.r#type.clone().unwrap_or(fetch.r#type);
+            let options = data.options.clone().or(fetch.options);
+            validate_workflow(&Some(fetch_type), &options)?;
+        }
+        if data.method == Some(ApiMethod::Custom) && data.custom_method.is_none() {
+            let fetch = self.fetch_repo.get_by_id(id).await?;
+            validate_method(&data.method, &fetch.custom_method)?;
+        } else {
+            validate_method(&data.method, &data.custom_method)?;
+        }
         if !user.is_superuser {
             let member = self.member_repo.find_member_id(*id, user.id)
                 .await
@@ -145,7 +193,31 @@ impl FetchService {
             if member.role == Some(Role::Viewer) {
                 return Err(AppError::Forbidden("Viewer not allowed to update fetch api.".to_string()));
             }
+            if member.role != Some(Role::Owner) && self.references_secrets(*id, &data).await? {
+                return Err(AppError::Forbidden("Only owner allowed to save a fetch that references secrets.".to_string()));
+            }
+        }
+
+        if let Some(auth_id) = data.auth_id {
+            self.check_auth_owner(&user, auth_id).await?;
+        }
+        if let Some(env_id) = data.environment_id {
+            self.check_environment_owner(&user, env_id).await?;
+        }
+
+        // Request of a fetch using someone else's environment or auth profile can't be pointed elsewhere
+        if data.changes_request() {
+            let fetch = self.fetch_repo.get_by_id(id).await?;
+            if data.environment_id.is_none()
+                && let Some(env_id) = fetch.environment_id {
+                self.check_environment_owner(&user, env_id).await?;
+            }
+            if data.auth_id.is_none()
+                && let Some(auth_id) = fetch.auth_id {
+                self.check_auth_owner(&user, auth_id).await?;
+            }
         }
+        self.check_dataset_owner(&user, &data.options).await?;
 
         if let Some(exe_id) = data.execute_id {
             let execute = self.execute_repo.find_by_id(exe_id).await?;
@@ -154,39 +226,66 @@ impl FetchService {
             }
             
             let fetch = self.fetch_repo.get_by_id(id).await?;
-            if let Some(j_id) = &fetch.job_id {
-                if let Err(e) = self.fetch_repo.delete_apalis_job(j_id).await {
-                    warn!("Failed delete apalis job {}, continue to next step\n error: {}", j_id, e);
-                }
+            if let Some(j_id) = &fetch.job_id
+                && let Err(e) = self.fetch_repo.delete_apalis_job(j_id).await {
+                warn!("Failed delete apalis job {}, continue to next step\n error: {}", j_id, e);
             }
             let job_id = self.create_apalis_job(&fetch, execute).await?;
             self.fetch_repo.update_job_id(*id, job_id).await?;
         }
 
+        // Hand-set variables replace what scripts saved before
+        let resets_variables = data.options.as_ref().is_some_and(|options| options.get("variables").is_some());
         let query = self.fetch_repo.update(id, data)
             .await
             .map_err(|e| {
-                if let Some(db_error) = e.as_database_error() {
-                    if db_error.is_foreign_key_violation() {
-                        match db_error.constraint() {
-                            Some("fk_fetch_execute") => {
-                                return AppError::BadRequest("Execute ID not found. Please create execute first.".to_string());
-                            },
-                            Some("fk_fetch_header") => {
-                                return AppError::BadRequest("Header ID not found. Please create header first.".to_string());
-                            },
-                            _ => {
-                                return AppError::BadRequest("Reference not found.".to_string());
-                            }
+                if let Some(db_error) = e.as_database_error()
+                    && db_error.is_foreign_key_violation() {
+                    match db_error.constraint() {
+                        Some("fk_fetch_execute") => {
+                            return AppError::BadRequest("Execute ID not found. Please create execute first.".to_string());
+                        },
+                        Some("fk_fetch_header") => {
+                            return AppError::BadRequest("Header ID not found. Please create header first.".to_string());
+                        },
+                        Some("fk_fetch_auth") => {
+                            return AppError::BadRequest("Auth ID not found. Please create auth profile first.".to_string());
+                        },
+                        Some("fk_fetch_environment") => {
+                            return AppError::BadRequest("Environment ID not found. Please create environment first.".to_string());
+                        },
+                        _ => {
+                            return AppError::BadRequest("Reference not found.".to_string());
                         }
                     }
                 }
                 AppError::BadRequest(format!("Database: {}", e))
             })?;
+        if resets_variables {
+            self.run_repo.clear_script_variables(*id).await?;
+        }
 
         Ok(query)
     }
     
+    /// Fetch as it would be saved, with its headers, references any secret
+    async fn references_secrets(&self, id: i32, data: &UpdateApi) -> Result<bool, AppError> {
+        let mut fetch = self.fetch_repo.get_by_id(&id).await?;
+        if let Some(endpoint) = &data.endpoint {
+            fetch.endpoint = endpoint.clone();
+        }
+        fetch.payload = data.payload.clone().or(fetch.payload);
+        fetch.query = data.query.clone().or(fetch.query);
+        fetch.body = data.body.clone().or(fetch.body);
+        fetch.options = data.options.clone().or(fetch.options);
+        let headers = match data.header_id.or(fetch.header_id) {
+            Some(h_id) => self.header_repo.find_by_id(h_id).await.ok().map(|h| h.headers),
+            None => None,
+        };
+
+        Ok(secret::references_secrets(&fetch, &headers))
+    }
+
     /// delete fetch api
     pub async fn delete_fetch(&self, id: i32, user: User) -> Result<Api, AppError> {
         let fetch = self.fetch_repo.get_by_id(&id).await?;
@@ -209,19 +308,89 @@ impl FetchService {
             }
         }
 
+        let blob_keys = self.data_repo.blob_keys(id).await?;
         let query = self.fetch_repo.delete(id).await?;
+        if let Some(store) = &self.state.blob_store {
+            store.delete_all(&blob_keys).await;
+        }
         
         Ok(query)
     }
-    /// # MEMBER AREA
+
+    /// Preview fetch request with templates rendered for the next run
+    pub async fn render_fetch(&self, user: User, id: i32) -> Result<RenderedRequest, AppError> {
+        let mut fetch = self.get_fetch_by_id(user.clone(), id).await?;
+        let mut headers = match fetch.header_id {
+            Some(h_id) => Some(self.header_repo.find_by_id(h_id).await?.headers),
+            None => None,
+        };
+
+        let mut ctx = TemplateContext::load(&self.run_repo, &self.env_repo, &fetch, None)
+            .await.map_err(AppError::InternalError)?;
+
+        // Environment values stay hidden from users the environment is only shared with
+        if let Some(env_id) = fetch.environment_id {
+            let env = self.env_repo.find_by_id(env_id).await?;
+            if env.user_id != user.id && !user.is_superuser {
+                let own = fetch.parsed_options().variables.unwrap_or_default();
+                for name in env.variables_map().keys().filter(|name| !own.contains_key(*name)) {
+                    ctx.variables.insert(name.clone(), Value::String(MASKED_VALUE.to_string()));
+                }
+            }
+        }
+        template::render(&mut fetch, &mut headers, &ctx).map_err(AppError::BadRequest)?;
+
+        Ok(RenderedRequest {
+            endpoint: fetch.endpoint,
+            method: fetch.method,
+            custom_method: fetch.custom_method,
+            headers,
+            payload: fetch.payload,
+            query: fetch.query,
+            body: fetch.body,
+        })
+    }
+
+    /// Copy fetch as a new fetch, optionally into another environment
+    pub async fn clone_fetch(&self, user: User, id: i32, data: ReqCloneApi) -> Result<Api, AppError> {
+        let fetch = self.get_fetch_by_id(user.clone(), id).await?;
+        let environment_id = data.environment_id.or(fetch.environment_id);
+        let name = match (data.name, data.environment_id) {
+            (Some(name), _) => name,
+            (None, Some(env_id)) => {
+                let env = self.get_environment(user.clone(), env_id).await?;
+                format!("{} [{}]", fetch.name, env.name)
+            },
+            (None, None) => format!("{} (copy)", fetch.name),
+        };
+
+        let req = ReqCreateApi {
+            name,
+            r#type: Some(fetch.r#type),
+            endpoint: fetch.endpoint,
+            method: fetch.method,
+            custom_method: fetch.custom_method,
+            topic: fetch.topic,
+            description: fetch.description,
+            payload: fetch.payload.map(Value::String),
+            execute_id: fetch.execute_id,
+            header_id: fetch.header_id,
+            auth_id: fetch.auth_id,
+            environment_id,
+            is_active: Some(fetch.is_active),
+            options: fetch.options,
+            query: fetch.query,
+            body: fetch.body,
+        };
+
+        self.create_fetch(req, user).await
+    }
+    // # MEMBER AREA
     
     /// Find member by id
     pub async fn find_member(&self, user: User, fetch_id: i32, id: i32) -> Result<ApiMembers, AppError>{
         if !user.is_superuser {
-            let is_allowed = match self.member_repo.find_member_id(fetch_id, user.id).await {
-                Ok(_) => true,
-                Err(_) => false, 
-            };
+            let is_allowed = self.member_repo.find_member_id(fetch_id, user.id).await.is_ok();
 
             if !is_allowed {
                 return Err(AppError::Forbidden("You don't have permission to view members!".to_string()));
@@ -237,10 +406,7 @@ impl FetchService {
     /// Find all related fetch members
     pub async fn find_members(&self, user:User, fetch_id: i32) -> Result<Vec<ApiMembers>, AppError> {
         if !user.is_superuser {
-            let is_allowed = match self.member_repo.find_member_id(fetch_id, user.id).await {
-                Ok(_) => true,
-                Err(_) => false, 
-            };
+            let is_allowed = self.member_repo.find_member_id(fetch_id, user.id).await.is_ok();
 
             if !is_allowed {
                 return Err(AppError::Forbidden("You don't have permission to view members!".to_string()));
@@ -367,7 +533,7 @@ impl FetchService {
         Ok(self.execute_repo.delete(id).await?)
     }
 
-    /// #Fetch Header Area
+    // #Fetch Header Area
     
     /// get one
     pub async fn get_header(&self, user: User, id: i32) -> Result<ApiHeader, AppError> {
@@ -420,13 +586,389 @@ impl FetchService {
         Ok(q)
     }
 
-    /// #Fetch Data Area
+    // #Fetch Auth Area
+
+    /// Auth profile only usable by the owner
+    async fn check_auth_owner(&self, user: &User, auth_id: i32) -> Result<(), AppError> {
+        let auth = self.auth_repo.find_by_id(auth_id)
+            .await.map_err(|_| AppError::BadRequest("Auth ID not found. Please create auth profile first.".to_string()))?;
+        if auth.user_id != user.id && !user.is_superuser {
+            return Err(AppError::Forbidden("You do not have permission to use this auth profile.".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// get one
+    pub async fn get_auth(&self, user: User, id: i32) -> Result<ApiAuth, AppError> {
+        let q = self.auth_repo.find_by_id(id).await?;
+        if !user.is_superuser && q.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to access this data".to_string()));
+        }
+
+        Ok(q.masked())
+    }
+
+    /// get all auth profiles related with user
+    pub async fn get_all_auth(&self, user: User) -> Result<Vec<ApiAuth>, AppError> {
+        let q = self.auth_repo.find_all(user.id).await?;
+
+        Ok(q.into_iter().map(ApiAuth::masked).collect())
+    }
+
+    /// Create auth profile user
+    pub async fn create_auth(&self, user: User, data: ReqCreateApiAuth) -> Result<ApiAuth, AppError> {
+        validate_auth_config(&data.r#type, &data.config)?;
+        let model: CreateApiAuth = data.into_model(user.id);
+        let q = self.auth_repo.create(model).await?;
+
+        Ok(q.masked())
+    }
+
+    /// Update auth profile user, cached token dropped
+    pub async fn update_auth(&self, user: User, id: i32, mut data: UpdateApiAuth) -> Result<ApiAuth, AppError> {
+        let auth = self.auth_repo.find_by_id(id).await?;
+
+        if !user.is_superuser && auth.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to update this data".to_string()));
+        }
+
+        // Masked value sent back keep the stored credential
+        if let (Some(Value::Object(new_config)), Value::Object(old_config)) = (&mut data.config, &auth.config) {
+            for (key, value) in new_config.iter_mut() {
+                if value.as_str() == Some(MASKED_VALUE)
+                    && let Some(old_value) = old_config.get(key) {
+                    *value = old_value.clone();
+                }
+            }
+        }
+
+        let auth_type = data.r#type.clone().unwrap_or(auth.r#type);
+        let config = data.config.clone().unwrap_or(auth.config);
+        validate_auth_config(&auth_type, &config)?;
+
+        let q = self.auth_repo.update(id, data).await?;
+        self.auth_repo.delete_token(id).await?;
+
+        Ok(q.masked())
+    }
+
+    /// Delete auth profile user
+    pub async fn delete_auth(&self, user: User, id: i32) -> Result<ApiAuth, AppError> {
+        let auth = self.auth_repo.find_by_id(id).await?;
+
+        if !user.is_superuser && auth.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to delete this data".to_string()));
+        }
+
+        let q = self.auth_repo.delete(id).await?;
+
+        Ok(q.masked())
+    }
+
+    // #Fetch Dependency Area
+
+    /// Owner or editor of the fetch
+    async fn check_editor(&self, user: &User, fetch_id: i32) -> Result<(), AppError> {
+        if !user.is_superuser {
+            let member = self.member_repo.find_member_id(fetch_id, user.id)
+                .await
+                .map_err(|_| AppError::Forbidden("You are not a member of this project!".to_string()))?;
+
+            if member.role == Some(Role::Viewer) {
+                return Err(AppError::Forbidden("Viewer not allowed to change dependencies.".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Dependencies where fetch is upstream or downstream
+    pub async fn get_dependencies(&self, user: User, fetch_id: i32) -> Result<Vec<ApiDependency>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let q = self.dependency_repo.find_related(fetch_id).await?;
+
+        Ok(q)
+    }
+
+    /// Add edge fetch -> downstream, edge closing a cycle rejected
+    pub async fn add_dependency(&self, user: User, fetch_id: i32, data: ReqCreateApiDependency) -> Result<ApiDependency, AppError> {
+        self.check_editor(&user, fetch_id).await?;
+        self.check_editor(&user, data.downstream_id).await?;
+        self.fetch_repo.get_by_id(&data.downstream_id)
+            .await.map_err(|_| AppError::BadRequest("Downstream fetch not found.".to_string()))?;
+
+        let edges: Vec<(i32, i32)> = self.dependency_repo.find_reachable(data.downstream_id).await?
+            .into_iter()
+            .map(|e| (e.upstream_id, e.downstream_id))
+            .collect();
+        if dependency::creates_cycle(&edges, fetch_id, data.downstream_id) {
+            return Err(AppError::BadRequest("Dependency would create a cycle.".to_string()));
+        }
+
+        let trigger = data.trigger.unwrap_or(DependencyTrigger::Success);
+        let q = self.dependency_repo.create(fetch_id, data.downstream_id, trigger)
+            .await
+            .map_err(|e| match e.as_database_error().and_then(|db| db.constraint()) {
+                Some("uq_fetch_dependency") => AppError::BadRequest("Dependency already exists.".to_string()),
+                _ => AppError::from(e),
+            })?;
+
+        Ok(q)
+    }
+
+    pub async fn delete_dependency(&self, user: User, fetch_id: i32, id: i32) -> Result<ApiDependency, AppError> {
+        self.check_editor(&user, fetch_id).await?;
+        let edge = self.dependency_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if edge.upstream_id != fetch_id && edge.downstream_id != fetch_id {
+            return Err(AppError::NotFound("Dependency not found in this fetch.".to_string()));
+        }
+
+        let q = self.dependency_repo.delete(id).await?;
+
+        Ok(q)
+    }
+
+    /// Whole graph connected to the fetch
+    pub async fn get_dag(&self, user: User, fetch_id: i32) -> Result<ApiDag, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let edges = self.dependency_repo.find_graph(fetch_id).await?;
+
+        let mut ids: Vec<i32> = edges.iter()
+            .flat_map(|e| [e.upstream_id, e.downstream_id])
+            .chain([fetch_id])
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let nodes = self.dependency_repo.find_nodes(&ids).await?;
+
+        Ok(ApiDag { nodes, edges })
+    }
+
+    // #Fetch Run Area
+
+    /// Attempt history of a fetch, latest first
+    pub async fn get_runs(&self, user: User, fetch_id: i32, filter: ApiRunFilter) -> Result<Vec<ApiRun>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+        let offset = filter.offset.unwrap_or(0).max(0);
+        let q = self.run_repo.find_all(fetch_id, &filter, limit, offset).await?;
+
+        Ok(q)
+    }
+
+    /// p50/p95/p99, min and max of run timings in a window
+    pub async fn get_timing_stats(&self, user: User, fetch_id: i32, filter: ApiTimingFilter) -> Result<Vec<TimingStat>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let until = filter.until.unwrap_or_else(Utc::now);
+        let since = filter.since.unwrap_or(until - Duration::hours(24));
+        if since >= until {
+            return Err(AppError::BadRequest("since must be before until".to_string()));
+        }
+        let q = self.run_repo.timing_stats(fetch_id, since, until).await?;
+
+        Ok(q)
+    }
+
+    // #Fetch Metric Area
+
+    pub async fn get_metric_series(&self, user: User, fetch_id: i32) -> Result<Vec<MetricSeries>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let q = self.metric_repo.find_series(fetch_id).await?;
+
+        Ok(q)
+    }
+
+    /// Bucketed series for charting, bucket size in seconds
+    pub async fn get_metric_points(&self, user: User, fetch_id: i32, name: String, filter: ApiMetricFilter) -> Result<Vec<MetricPoint>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let until = filter.until.unwrap_or_else(Utc::now);
+        let since = filter.since.unwrap_or(until - Duration::hours(24));
+        if since >= until {
+            return Err(AppError::BadRequest("since must be before until".to_string()));
+        }
+        let bucket = filter.bucket.unwrap_or(300);
+        if bucket < 1 {
+            return Err(AppError::BadRequest("bucket must be at least 1 second".to_string()));
+        }
+        if (until - since).num_seconds() / bucket > metric::MAX_POINTS {
+            return Err(AppError::BadRequest(format!("Window has more than {} buckets, use a larger bucket", metric::MAX_POINTS)));
+        }
+        let q = self.metric_repo.find_points(fetch_id, &name, since, until, bucket, filter.agg.unwrap_or_default()).await?;
+
+        Ok(q)
+    }
+
+    // #Fetch Storage Area
+
+    /// Stored data per fetch and per owner, admin only
+    pub async fn get_storage_report(&self) -> Result<StorageReport, AppError> {
+        let fetches = self.data_repo.storage_by_fetch().await?;
+        let users = self.data_repo.storage_by_user().await?;
+
+        Ok(StorageReport {
+            total_rows: fetches.iter().map(|f| f.rows).sum(),
+            total_bytes: fetches.iter().map(|f| f.bytes).sum(),
+            defaults: self.state.app_config.retention.clone(),
+            fetches,
+            users,
+        })
+    }
+
+    // #Fetch Matrix Area
+
+    /// Dataset referenced by matrix options only usable by the owner
+    async fn check_dataset_owner(&self, user: &User, options: &Option<Value>) -> Result<(), AppError> {
+        let dataset_id = options.clone()
+            .and_then(|v| serde_json::from_value::<ApiOptions>(v).ok())
+            .and_then(|o| o.matrix)
+            .and_then(|m| m.dataset_id);
+        if let Some(dataset_id) = dataset_id {
+            self.get_dataset(user.clone(), dataset_id).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_dataset(&self, user: User, id: i32) -> Result<ApiDataset, AppError> {
+        let q = self.dataset_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if !user.is_superuser && q.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to access this dataset".to_string()));
+        }
+
+        Ok(q)
+    }
+
+    pub async fn get_all_dataset(&self, user: User) -> Result<Vec<ApiDataset>, AppError> {
+        let q = self.dataset_repo.find_all(user.id).await?;
+
+        Ok(q)
+    }
+
+    pub async fn create_dataset(&self, user: User, data: ReqCreateApiDataset) -> Result<ApiDataset, AppError> {
+        let rows = dataset_rows(data.csv, data.rows)?
+            .ok_or(AppError::BadRequest("Dataset require csv or rows.".to_string()))?;
+        let q = self.dataset_repo.create(user.id, data.name, rows).await?;
+
+        Ok(q)
+    }
+
+    pub async fn update_dataset(&self, user: User, id: i32, data: UpdateApiDataset) -> Result<ApiDataset, AppError> {
+        self.get_dataset(user, id).await?;
+        let rows = dataset_rows(data.csv, data.rows)?;
+        let q = self.dataset_repo.update(id, data.name, rows).await?;
+
+        Ok(q)
+    }
+
+    pub async fn delete_dataset(&self, user: User, id: i32) -> Result<ApiDataset, AppError> {
+        self.get_dataset(user, id).await?;
+        let q = self.dataset_repo.delete(id).await?;
+
+        Ok(q)
+    }
+
+    /// Run summaries of a matrix fetch, latest first
+    pub async fn get_matrix_runs(&self, user: User, fetch_id: i32) -> Result<Vec<ApiMatrixRun>, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let q = self.matrix_repo.find_all(fetch_id).await?;
+
+        Ok(q)
+    }
+
+    pub async fn get_matrix_run(&self, user: User, fetch_id: i32, id: i32) -> Result<ApiMatrixRun, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let q = self.matrix_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if q.fetch_id != fetch_id {
+            return Err(AppError::NotFound("Matrix run not found in this fetch.".to_string()));
+        }
+
+        Ok(q)
+    }
+
+    // #Fetch Environment Area
+
+    /// Owner, superuser or member of a fetch using this environment, variables masked unless owner
+    pub async fn get_environment(&self, user: User, id: i32) -> Result<ApiEnvironment, AppError> {
+        let q = self.env_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if user.is_superuser || q.user_id == user.id {
+            return Ok(q);
+        }
+        if !self.env_repo.is_shared(id, user.id).await? {
+            return Err(AppError::Forbidden("You don't have permission to access this environment".to_string()));
+        }
+
+        Ok(q.masked())
+    }
+
+    /// get all environments owned or shared to user, variables of shared ones masked
+    pub async fn get_all_environment(&self, user: User) -> Result<Vec<ApiEnvironment>, AppError> {
+        let q = self.env_repo.find_all(user.id).await?
+            .into_iter()
+            .map(|env| if env.user_id == user.id || user.is_superuser { env } else { env.masked() })
+            .collect();
+
+        Ok(q)
+    }
+
+    /// Environment only attachable to a fetch by its owner
+    async fn check_environment_owner(&self, user: &User, env_id: i32) -> Result<(), AppError> {
+        let env = self.env_repo.find_by_id(env_id)
+            .await.map_err(|_| AppError::BadRequest("Environment ID not found. Please create environment first.".to_string()))?;
+        if env.user_id != user.id && !user.is_superuser {
+            return Err(AppError::Forbidden("You do not have permission to use this environment.".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_environment(&self, user: User, data: ReqCreateApiEnvironment) -> Result<ApiEnvironment, AppError> {
+        if let Some(variables) = &data.variables {
+            validate_environment_variables(variables)?;
+        }
+        let model = data.into_model(user.id);
+        let q = self.env_repo.create(model).await?;
+
+        Ok(q)
+    }
+
+    /// Shared members can use the environment, only owner can change it
+    pub async fn update_environment(&self, user: User, id: i32, data: UpdateApiEnvironment) -> Result<ApiEnvironment, AppError> {
+        let env = self.env_repo.find_by_id(id).await?;
+        if !user.is_superuser && env.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to update this environment".to_string()));
+        }
+        if let Some(variables) = &data.variables {
+            validate_environment_variables(variables)?;
+        }
+
+        let q = self.env_repo.update(id, data).await?;
+
+        Ok(q)
+    }
+
+    pub async fn delete_environment(&self, user: User, id: i32) -> Result<ApiEnvironment, AppError> {
+        let env = self.env_repo.find_by_id(id).await?;
+        if !user.is_superuser && env.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to delete this environment".to_string()));
+        }
+
+        let q = self.env_repo.delete(id).await?;
+
+        Ok(q)
+    }
+
+    // #Fetch Data Area
     
     /// get one
     pub async fn get_data(&self, user: User, fetch_id: i32, id: i32) -> Result<ApiDataResponse, AppError> {
         if !user.is_superuser {
             self.member_repo.find_member_id(fetch_id, user.id)
-            .await.map_err(|_|{AppError::Forbidden(format!("You don't have permission to access this data"))})?;
+            .await.map_err(|_|{AppError::Forbidden("You don't have permission to access this data".to_string())})?;
         }
 
         let data = self.data_repo.find_by_id(id).await?;
@@ -434,19 +976,109 @@ impl FetchService {
         Ok(ApiDataResponse::from(data))
     }
 
+    /// Stored body as the original bytes with its content type
+    pub async fn get_data_raw(&self, user: User, fetch_id: i32, id: i32) -> Result<(String, Vec<u8>), AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let data = self.data_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if data.fetch_id != fetch_id {
+            return Err(AppError::NotFound("Data not found in this fetch.".to_string()));
+        }
+
+        let bytes = blob::load(&self.state, &data).await.map_err(AppError::InternalError)?;
+        let content_type = data.content_type.unwrap_or_else(|| match data.encoding {
+            BodyEncoding::Text => "text/plain; charset=utf-8".to_string(),
+            BodyEncoding::Base64 => "application/octet-stream".to_string(),
+        });
+
+        // Upstream decides the bytes, never let it serve markup or script from this origin
+        Ok((body::served_type(&content_type), bytes))
+    }
+
+    /// Diff of a data entry against the entry stored before it
+    pub async fn get_data_diff(&self, user: User, fetch_id: i32, id: i32) -> Result<ApiDataDiff, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let data = self.data_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+        if data.fetch_id != fetch_id {
+            return Err(AppError::NotFound("Data not found in this fetch.".to_string()));
+        }
+
+        let previous = self.data_repo.find_previous(&data).await?;
+        let changes = match &previous {
+            Some(previous) => change::diff(&change::body_value(&self.body_text(previous).await?), &change::body_value(&self.body_text(&data).await?)),
+            None => Vec::new(),
+        };
+
+        Ok(ApiDataDiff { id: data.id, previous_id: previous.map(|p| p.id), changed: !changes.is_empty(), changes })
+    }
+
+    /// Stored body as text, loaded from the blob store when it was spilled
+    async fn body_text(&self, data: &ApiData) -> Result<String, AppError> {
+        let (text, _) = blob::body(&self.state, data).await.map_err(AppError::InternalError)?;
+
+        Ok(text)
+    }
+
     /// get all fetch data related with fetch
-    pub async fn get_all_data(&self, user: User, fetch_id: i32) -> Result<Vec<ApiDataResponse>, AppError> {
+    pub async fn get_all_data(&self, user: User, fetch_id: i32, filter: ApiDataFilter) -> Result<(Vec<ApiDataResponse>, Pagination), AppError> {
         if !user.is_superuser {
              self.member_repo.find_member_id(fetch_id, user.id)
-            .await.map_err(|_|{AppError::Forbidden(format!("You don't have permission to access this data"))})?;
+            .await.map_err(|_|{AppError::Forbidden("You don't have permission to access this data".to_string())})?;
         }
 
-        let data_list = self.data_repo.find_all(fetch_id).await?;
+        let sort = filter.sort.unwrap_or_default();
+        let order = filter.order.unwrap_or_default();
+        let cursor = filter.cursor.as_deref()
+            .map(|c| DataCursor::decode(c, sort, order))
+            .transpose().map_err(AppError::BadRequest)?;
+        let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+
+        // One extra row tells if another page exists. Spilled rows are matched after the query,
+        // so scanning goes on until the page is full or the rows run out
+        let mut data_list: Vec<ApiData> = Vec::new();
+        let mut after = cursor;
+        let (has_more, next_cursor) = loop {
+            let mut scanned = self.data_repo.find_page(fetch_id, &filter, after.as_ref(), limit + 1).await?;
+            let exhausted = scanned.len() as i64 <= limit;
+            scanned.truncate(limit as usize);
+            let last_scanned = scanned.last().map(|last| DataCursor::after(sort, order, last));
+            let matched = match &filter.contains {
+                Some(contains) => blob::retain_contains(&self.state, scanned, contains).await,
+                None => scanned,
+            };
+
+            let room = limit as usize - data_list.len();
+            if matched.len() > room {
+                data_list.extend(matched.into_iter().take(room));
+                break (true, data_list.last().map(|last| DataCursor::after(sort, order, last).encode()));
+            }
+            data_list.extend(matched);
+            if exhausted {
+                break (false, None);
+            }
+            if data_list.len() == limit as usize {
+                break (true, last_scanned.map(|c| c.encode()));
+            }
+            after = last_scanned;
+        };
+
         let response_list: Vec<ApiDataResponse> = data_list.into_iter()
             .map(ApiDataResponse::from)
             .collect();
 
-        Ok(response_list)
+        Ok((response_list, Pagination { limit, has_more, next_cursor }))
+    }
+
+    /// Stream of encoded rows, membership checked before the first byte
+    pub async fn export_data(&self, user: User, fetch_id: i32, query: ApiDataExport) -> Result<impl Stream<Item = Result<Vec<u8>, anyhow::Error>> + Send + 'static, AppError> {
+        self.get_fetch_by_id(user, fetch_id).await?;
+        let columns = match &query.columns {
+            Some(spec) => export::parse_columns(spec).map_err(AppError::BadRequest)?,
+            None => Vec::new(),
+        };
+
+        Ok(export::stream(self.state.clone(), fetch_id, query.filter(), query.format.unwrap_or_default(), columns))
     }
 
     /// Create fetch data user
@@ -507,12 +1139,152 @@ impl FetchService {
         }
 
         let q = self.data_repo.delete(id).await?;
+        if let (Some(store), Some(key)) = (&self.state.blob_store, &q.blob_key) {
+            store.delete_all(std::slice::from_ref(key)).await;
+        }
 
         Ok(q)
     }
 }
 
 
+/// Options must match each fetch type options, client options must build a client
+fn validate_options(options: &Option<Value>) -> Result<(), AppError> {
+    if let Some(options) = options
+        && !options.is_null() {
+        let options: ApiOptions = serde_json::from_value(options.clone())
+            .map_err(|e| AppError::BadRequest(format!("Invalid options: {}", e)))?;
+
+        if let Some(client) = &options.client {
+            build_client(client).map_err(AppError::BadRequest)?;
+        }
+        if let Some(variables) = &options.variables {
+            validate_variables(variables).map_err(AppError::BadRequest)?;
+        }
+        if let Some(workflow) = &options.workflow {
+            workflow::validate(workflow).map_err(AppError::BadRequest)?;
+        }
+        if let Some(matrix) = &options.matrix {
+            matrix::validate(matrix).map_err(AppError::BadRequest)?;
+        }
+        if let Some(script) = &options.script {
+            script::validate(script).map_err(AppError::BadRequest)?;
+        }
+        if let Some(assertions) = &options.assertions {
+            assertion::validate(assertions).map_err(AppError::BadRequest)?;
+        }
+        if let Some(schema) = &options.schema {
+            schema::validate(schema).map_err(AppError::BadRequest)?;
+        }
+        if let Some(change) = &options.change_detection {
+            change::validate(change).map_err(AppError::BadRequest)?;
+        }
+        if let Some(metrics) = &options.metrics {
+            metric::validate(metrics).map_err(AppError::BadRequest)?;
+        }
+        if let Some(policy) = &options.retention {
+            retention::validate(policy).map_err(AppError::BadRequest)?;
+        }
+        if let Some(options) = &options.export {
+            export::validate(&options.columns).map_err(AppError::BadRequest)?;
+        }
+        if let Some(limit) = &options.body_limit {
+            blob::validate(limit).map_err(AppError::BadRequest)?;
+        }
+        if let Some(rules) = &options.redaction {
+            redact::validate(rules).map_err(AppError::BadRequest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Workflow fetch must have steps in options
+fn validate_workflow(fetch_type: &Option<ApiType>, options: &Option<Value>) -> Result<(), AppError> {
+    if matches!(fetch_type, Some(ApiType::Workflow)) {
+        let has_steps = options.clone()
+            .and_then(|v| serde_json::from_value::<ApiOptions>(v).ok())
+            .and_then(|o| o.workflow)
+            .is_some();
+        if !has_steps {
+            return Err(AppError::BadRequest("Workflow fetch require steps in options.workflow.".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Dataset rows from CSV text or rows, CSV wins when both given
+fn dataset_rows(csv: Option<String>, rows: Option<Vec<serde_json::Map<String, Value>>>) -> Result<Option<Value>, AppError> {
+    let rows = match (csv, rows) {
+        (Some(csv), _) => matrix::parse_csv(&csv).map_err(AppError::BadRequest)?,
+        (None, Some(rows)) => rows,
+        (None, None) => return Ok(None),
+    };
+    matrix::validate_rows(&rows).map_err(AppError::BadRequest)?;
+
+    Ok(Some(Value::Array(rows.into_iter().map(Value::Object).collect())))
+}
+
+/// Environment variables must be an object of template variables
+fn validate_environment_variables(variables: &Value) -> Result<(), AppError> {
+    match variables {
+        Value::Object(map) => validate_variables(map).map_err(AppError::BadRequest),
+        _ => Err(AppError::BadRequest("Variables must be an object of name and value.".to_string())),
+    }
+}
+
+/// Auth config must have required fields of its type
+fn validate_auth_config(auth_type: &AuthType, config: &Value) -> Result<(), AppError> {
+    let config: AuthConfig = serde_json::from_value(config.clone())
+        .map_err(|e| AppError::BadRequest(format!("Invalid auth config: {}", e)))?;
+
+    config.validate(auth_type).map_err(AppError::BadRequest)
+}
+
+/// Custom method require a valid HTTP token as verb
+fn validate_method(method: &Option<ApiMethod>, custom_method: &Option<String>) -> Result<(), AppError> {
+    if let Some(verb) = custom_method {
+        let is_token = !verb.is_empty()
+            && verb.len() <= 32
+            && verb.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+        if !is_token {
+            return Err(AppError::BadRequest(format!("Invalid custom method: '{}'.", verb)));
+        }
+    }
+
+    if method == &Some(ApiMethod::Custom) && custom_method.is_none() {
+        return Err(AppError::BadRequest("Custom method required when method is custom.".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Query must be an object and body must match one of body mode
+fn validate_request_body(query: &Option<Value>, body: &Option<Value>) -> Result<(), AppError> {
+    if let Some(query) = query
+        && !query.is_object() && !query.is_null() {
+        return Err(AppError::BadRequest("Query must be an object of key and value.".to_string()));
+    }
+
+    if let Some(body) = body
+        && !body.is_null() {
+        let body: ApiBody = serde_json::from_value(body.clone())
+            .map_err(|e| AppError::BadRequest(format!("Invalid body: {}", e)))?;
+
+        if let ApiBody::Multipart { parts } = body {
+            for part in parts {
+                if let MultipartPart::File { name, content, .. } = part
+                    && STANDARD.decode(content).is_err() {
+                    return Err(AppError::BadRequest(format!("File part '{}' must be base64 encoded.", name)));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl<S> FromRequestParts<S> for FetchService
 where
     AppState: FromRef<S>,
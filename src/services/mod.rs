@@ -1,3 +1,4 @@
 pub mod auth;
 pub mod user;
-pub mod fetch;
\ No newline at end of file
+pub mod fetch;
+pub mod secret;
\ No newline at end of file
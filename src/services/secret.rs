@@ -0,0 +1,130 @@
+use axum::{extract::{FromRef, FromRequestParts}, http::request::Parts};
+use tracing::info;
+use crate::{models::{secret::{ReqCreateSecret, ReqUpdateSecret, RotateSecretResult, Secret}, user::User}, repository::secret::SecretRepository, state::AppState, utils::{crypto::SecretCipher, response::AppError}};
+
+const MAX_NAME_LENGTH: usize = 64;
+
+#[allow(dead_code)]
+pub struct SecretService {
+    secret_repo: SecretRepository,
+    state: AppState,
+}
+
+impl SecretService {
+    pub fn new(state: AppState) -> Self {
+        let secret_repo = SecretRepository::new(state.database.clone());
+        Self { secret_repo, state }
+    }
+
+    fn cipher(&self) -> Result<&SecretCipher, AppError> {
+        self.state.secret_cipher.as_ref()
+            .ok_or(AppError::BadRequest("Secret store is not configured, set SECRET_KEY".to_string()))
+    }
+
+    pub async fn get_secret(&self, user: User, id: i32) -> Result<Secret, AppError> {
+        let q = self.secret_repo.find_by_id(id)
+            .await.map_err(|e| AppError::NotFound(format!("Database: {}", e)))?;
+
+        if !user.is_superuser && q.user_id != user.id {
+            return Err(AppError::Forbidden("You don't have permission to access this data".to_string()));
+        }
+
+        Ok(q)
+    }
+
+    pub async fn get_all_secrets(&self, user: User) -> Result<Vec<Secret>, AppError> {
+        let q = self.secret_repo.find_all_user(user.id).await?;
+
+        Ok(q)
+    }
+
+    pub async fn create_secret(&self, user: User, data: ReqCreateSecret) -> Result<Secret, AppError> {
+        validate_secret_name(&data.name)?;
+        let (ciphertext, key_version) = self.cipher()?
+            .encrypt(&data.value, &Secret::aad(user.id, &data.name))
+            .map_err(AppError::InternalError)?;
+        let model = data.into_model(user.id, ciphertext, key_version);
+
+        let q = self.secret_repo.create(model).await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.constraint() == Some("uq_user_secrets_name") => {
+                    AppError::BadRequest("Secret with this name already exists".to_string())
+                },
+                _ => AppError::from(e),
+            })?;
+
+        Ok(q)
+    }
+
+    pub async fn update_secret(&self, user: User, id: i32, data: ReqUpdateSecret) -> Result<Secret, AppError> {
+        let secret = self.get_secret(user, id).await?;
+
+        let (ciphertext, key_version) = match &data.value {
+            Some(value) => {
+                let (ciphertext, key_version) = self.cipher()?
+                    .encrypt(value, &Secret::aad(secret.user_id, &secret.name))
+                    .map_err(AppError::InternalError)?;
+                (Some(ciphertext), Some(key_version))
+            },
+            None => (None, None),
+        };
+
+        let q = self.secret_repo.update(id, data.description, ciphertext, key_version).await?;
+
+        Ok(q)
+    }
+
+    pub async fn delete_secret(&self, user: User, id: i32) -> Result<Secret, AppError> {
+        self.get_secret(user, id).await?;
+        let q = self.secret_repo.delete(id).await?;
+
+        Ok(q)
+    }
+
+    /// Re-encrypt every secret not on the current key version
+    pub async fn rotate_secrets(&self) -> Result<RotateSecretResult, AppError> {
+        let cipher = self.cipher()?;
+        let key_version = cipher.version();
+        let secrets = self.secret_repo.find_by_key_version(key_version).await?;
+
+        let mut rotated = 0;
+        for secret in secrets {
+            let aad = Secret::aad(secret.user_id, &secret.name);
+            let value = cipher.decrypt(&secret.ciphertext, secret.key_version, &aad)
+                .map_err(|e| AppError::InternalError(format!("Secret {}: {}", secret.id, e)))?;
+            let (ciphertext, version) = cipher.encrypt(&value, &aad)
+                .map_err(AppError::InternalError)?;
+            self.secret_repo.update(secret.id, None, Some(ciphertext), Some(version)).await?;
+            rotated += 1;
+        }
+        info!("[SECRET] Rotated {} secrets to key version {}", rotated, key_version);
+
+        Ok(RotateSecretResult { key_version, rotated })
+    }
+}
+
+/// Name must be usable inside `{{secret.NAME}}`
+pub fn validate_secret_name(name: &str) -> Result<(), AppError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(AppError::BadRequest("Secret name must be 1-64 characters of A-Z, a-z, 0-9, '_' or '-'".to_string()));
+    }
+
+    Ok(())
+}
+
+impl<S> FromRequestParts<S> for SecretService
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = AppState::from_ref(state);
+
+        Ok(SecretService::new(state))
+    }
+}
@@ -76,7 +76,7 @@ impl UserService {
     }
 
     pub async fn update_user(&self, user_id: &i32, data: UserReq) -> Result<User, AppError> {
-        let current_user = self.user_repo.find_by_id(&user_id).await?;
+        let current_user = self.user_repo.find_by_id(user_id).await?;
 
         let new_password_hash = if let Some(raw_pass) = data.password {
             let pass_to_hash = raw_pass.clone(); 
@@ -1,7 +1,7 @@
 use apalis_sql::postgres::PostgresStorage;
 use sqlx::PgPool;
 use std::sync::Arc;
-use crate::{models::fetch::Api, jobs::websocket::WsJobs};
+use crate::{models::fetch::{Api, RedactionOptions, RetentionOptions}, utils::crypto::SecretCipher, jobs::{blob::BlobStore, client::HttpClients, probe::ProbeJobs, sse::SseJobs, websocket::WsJobs}};
 
 #[derive(Clone)]
 pub struct AppConfig {
@@ -9,6 +9,14 @@ pub struct AppConfig {
     pub access_ttl: i64,
     pub refresh_ttl: i64,
     pub concurrency: u32,
+    /// Global data retention, per fetch options override it
+    pub retention: RetentionOptions,
+    /// Global response body ceiling
+    pub max_body_bytes: u64,
+    /// Bodies larger than this go to the blob store when one is set
+    pub blob_threshold: u64,
+    /// Global redaction, per fetch rules are added to it
+    pub redaction: RedactionOptions,
 }
 
 #[derive(Clone)]
@@ -16,6 +24,11 @@ pub struct AppState {
     pub app_config: Arc<AppConfig>,
     pub database: PgPool,
     pub http_client: reqwest::Client,
+    pub http_clients: HttpClients,
     pub ws_client: WsJobs,
+    pub sse_client: SseJobs,
+    pub probe_client: ProbeJobs,
+    pub secret_cipher: Option<SecretCipher>,
+    pub blob_store: Option<BlobStore>,
     pub job_queue: PostgresStorage<Api>,
 }
\ No newline at end of file
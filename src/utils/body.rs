@@ -0,0 +1,173 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use encoding_rs::{Encoding, UTF_8};
+use serde_json::{Map, Value};
+use crate::models::fetch::BodyEncoding;
+
+/// Media type without parameters, lowercase
+pub fn mime(content_type: Option<&str>) -> String {
+    content_type.unwrap_or("")
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+}
+
+fn charset(content_type: Option<&str>) -> Option<&'static Encoding> {
+    content_type?.split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("charset"))
+        .and_then(|(_, value)| Encoding::for_label(value.trim().trim_matches('"').as_bytes()))
+}
+
+fn is_binary(mime: &str) -> bool {
+    if mime.ends_with("+xml") || mime.ends_with("+json") {
+        return false;
+    }
+    ["image/", "audio/", "video/", "font/"].iter().any(|prefix| mime.starts_with(prefix))
+        || matches!(mime,
+            "application/octet-stream" | "application/pdf" | "application/zip" | "application/gzip"
+            | "application/x-protobuf" | "application/protobuf" | "application/msgpack" | "application/wasm")
+}
+
+/// Content type safe to serve back, types a browser would render or run become `application/octet-stream`
+pub fn served_type(content_type: &str) -> String {
+    let mime = mime(Some(content_type));
+    let active = mime.is_empty()
+        || mime.ends_with("+xml")
+        || mime.contains("html")
+        || mime.contains("javascript")
+        || mime.contains("ecmascript")
+        || matches!(mime.as_str(), "text/xml" | "application/xml" | "text/xsl" | "multipart/x-mixed-replace");
+
+    match active {
+        true => "application/octet-stream".to_string(),
+        false => content_type.to_string(),
+    }
+}
+
+/// Text in its charset, binary types and undecodable bytes as base64
+pub fn decode(content_type: Option<&str>, bytes: &[u8]) -> (String, BodyEncoding) {
+    if !is_binary(&mime(content_type)) {
+        match charset(content_type) {
+            Some(encoding) if encoding != UTF_8 => {
+                let (text, _, had_errors) = encoding.decode(bytes);
+                if !had_errors {
+                    return (text.into_owned(), BodyEncoding::Text);
+                }
+            },
+            _ => if let Ok(text) = std::str::from_utf8(bytes) {
+                return (text.to_string(), BodyEncoding::Text);
+            },
+        }
+    }
+
+    (STANDARD.encode(bytes), BodyEncoding::Base64)
+}
+
+/// Original bytes of a stored body, text encoded back to its charset
+pub fn encode(content_type: Option<&str>, body: &str, encoding: BodyEncoding) -> Vec<u8> {
+    match encoding {
+        BodyEncoding::Base64 => STANDARD.decode(body).unwrap_or_default(),
+        BodyEncoding::Text => match charset(content_type) {
+            Some(charset) if charset != UTF_8 => charset.encode(body).0.into_owned(),
+            _ => body.as_bytes().to_vec(),
+        },
+    }
+}
+
+/// Structured view of the body by content type, then JSON, then the plain string
+pub fn view(content_type: Option<&str>, body: &str, encoding: BodyEncoding) -> Value {
+    if encoding == BodyEncoding::Base64 {
+        return Value::String(body.to_string());
+    }
+
+    let mime = mime(content_type);
+    let parsed = if mime.ends_with("/xml") || mime.ends_with("+xml") {
+        xml(body)
+    } else if mime == "text/csv" {
+        csv(body)
+    } else if mime == "application/x-www-form-urlencoded" {
+        Some(form(body))
+    } else {
+        None
+    };
+
+    parsed
+        .or_else(|| serde_json::from_str(body).ok())
+        .unwrap_or_else(|| Value::String(body.to_string()))
+}
+
+/// `{root: ...}`, attributes as `@name`, mixed text as `#text`, repeated tags as arrays
+pub fn xml(body: &str) -> Option<Value> {
+    let document = roxmltree::Document::parse(body).ok()?;
+    let root = document.root_element();
+    let mut object = Map::new();
+    object.insert(root.tag_name().name().to_string(), xml_element(root));
+
+    Some(Value::Object(object))
+}
+
+fn xml_element(node: roxmltree::Node) -> Value {
+    let mut object = Map::new();
+    for attribute in node.attributes() {
+        object.insert(format!("@{}", attribute.name()), Value::String(attribute.value().to_string()));
+    }
+
+    let mut text = String::new();
+    for child in node.children() {
+        if child.is_element() {
+            let value = xml_element(child);
+            match object.get_mut(child.tag_name().name()) {
+                Some(Value::Array(items)) => items.push(value),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+                None => { object.insert(child.tag_name().name().to_string(), value); },
+            }
+        } else if let Some(part) = child.text() {
+            text.push_str(part);
+        }
+    }
+
+    let text = text.trim();
+    if object.is_empty() {
+        return Value::String(text.to_string());
+    }
+    if !text.is_empty() {
+        object.insert("#text".to_string(), Value::String(text.to_string()));
+    }
+
+    Value::Object(object)
+}
+
+/// Rows as objects keyed by the header line
+pub fn csv(body: &str) -> Option<Value> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(body.as_bytes());
+    let headers = reader.headers().ok()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.ok()?;
+        let row: Map<String, Value> = headers.iter()
+            .zip(record.iter())
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+
+    Some(Value::Array(rows))
+}
+
+/// Repeated keys become arrays
+pub fn form(body: &str) -> Value {
+    let mut object = Map::new();
+    for (key, value) in form_urlencoded::parse(body.as_bytes()) {
+        let value = Value::String(value.into_owned());
+        match object.get_mut(key.as_ref()) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => { object.insert(key.into_owned(), value); },
+        }
+    }
+
+    Value::Object(object)
+}
@@ -0,0 +1,93 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use rand::{RngCore, rngs::OsRng};
+use ring::{aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey}, digest};
+use std::collections::HashMap;
+
+/// AES-256-GCM cipher for user secrets, old keys kept for decrypt until rotated
+#[derive(Clone)]
+pub struct SecretCipher {
+    version: i32,
+    keys: HashMap<i32, [u8; 32]>,
+}
+
+impl SecretCipher {
+    /// `old_keys` format: `version:base64key,version:base64key`
+    pub fn new(current_key: &str, version: i32, old_keys: &str) -> Result<Self, String> {
+        let mut keys = HashMap::new();
+        keys.insert(version, decode_key(current_key)?);
+
+        for entry in old_keys.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (old_version, key) = entry.split_once(':')
+                .ok_or_else(|| format!("Invalid old secret key entry: '{}'", entry))?;
+            let old_version = old_version.trim().parse::<i32>()
+                .map_err(|_| format!("Invalid old secret key version: '{}'", old_version))?;
+            if old_version != version {
+                keys.insert(old_version, decode_key(key)?);
+            }
+        }
+
+        Ok(Self { version, keys })
+    }
+
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Encrypt with current key, returns nonce + ciphertext and key version
+    pub fn encrypt(&self, plaintext: &str, aad: &str) -> Result<(Vec<u8>, i32), String> {
+        let key = self.key(self.version)?;
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+
+        let mut in_out = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), Aad::from(aad.as_bytes()), &mut in_out)
+            .map_err(|_| "Failed encrypt secret".to_string())?;
+
+        let mut data = nonce_bytes.to_vec();
+        data.extend_from_slice(&in_out);
+
+        Ok((data, self.version))
+    }
+
+    pub fn decrypt(&self, data: &[u8], version: i32, aad: &str) -> Result<String, String> {
+        if data.len() < NONCE_LEN {
+            return Err("Invalid secret ciphertext".to_string());
+        }
+        let key = self.key(version)?;
+        let (nonce_bytes, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce_bytes)
+            .map_err(|_| "Invalid secret nonce".to_string())?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = key.open_in_place(nonce, Aad::from(aad.as_bytes()), &mut in_out)
+            .map_err(|_| "Failed decrypt secret".to_string())?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| "Secret is not valid UTF-8".to_string())
+    }
+
+    fn key(&self, version: i32) -> Result<LessSafeKey, String> {
+        let bytes = self.keys.get(&version)
+            .ok_or_else(|| format!("Secret key version {} not configured", version))?;
+        let key = UnboundKey::new(&AES_256_GCM, bytes)
+            .map_err(|_| "Invalid secret key".to_string())?;
+
+        Ok(LessSafeKey::new(key))
+    }
+}
+
+fn decode_key(key: &str) -> Result<[u8; 32], String> {
+    let bytes = STANDARD.decode(key.trim())
+        .map_err(|_| "Secret key must be base64 encoded".to_string())?;
+
+    bytes.try_into().map_err(|_| "Secret key must be 32 bytes".to_string())
+}
+
+/// Lowercase hex of bytes
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// SHA-256 as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
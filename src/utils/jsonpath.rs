@@ -0,0 +1,141 @@
+use serde_json::Value;
+
+/// One step of a path, `$.items[0].name` = [Key(items), Index(0), Key(name)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+}
+
+/// Parse subset of JSONPath: `$`, `.key`, `['key']`, `[0]`, `[-1]`, `.*` and `[*]`
+pub fn parse(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let rest = path.strip_prefix('$')
+        .ok_or_else(|| format!("JSONPath must start with '$': '{}'", path))?;
+    let chars: Vec<char> = rest.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+                    end += 1;
+                }
+                let key: String = chars[start..end].iter().collect();
+                match key.as_str() {
+                    "" => return Err(format!("Empty key in JSONPath '{}'", path)),
+                    "*" => segments.push(Segment::Wildcard),
+                    _ => segments.push(Segment::Key(key)),
+                }
+                i = end;
+            },
+            '[' => {
+                let close = chars[i..].iter().position(|c| *c == ']')
+                    .map(|p| p + i)
+                    .ok_or_else(|| format!("Unclosed '[' in JSONPath '{}'", path))?;
+                let inner: String = chars[i + 1..close].iter().collect();
+                let inner = inner.trim();
+                let quoted = inner.len() >= 2
+                    && ((inner.starts_with('\'') && inner.ends_with('\'')) || (inner.starts_with('"') && inner.ends_with('"')));
+                if inner == "*" {
+                    segments.push(Segment::Wildcard);
+                } else if quoted {
+                    segments.push(Segment::Key(inner[1..inner.len() - 1].to_string()));
+                } else {
+                    let index = inner.parse::<i64>()
+                        .map_err(|_| format!("Invalid index '{}' in JSONPath '{}'", inner, path))?;
+                    segments.push(Segment::Index(index));
+                }
+                i = close + 1;
+            },
+            c => return Err(format!("Unexpected '{}' in JSONPath '{}'", c, path)),
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Every value matching the path
+pub fn select<'a>(value: &'a Value, path: &str) -> Result<Vec<&'a Value>, String> {
+    let segments = parse(path)?;
+    let mut current = vec![value];
+
+    for segment in &segments {
+        let mut next = Vec::new();
+        for item in current {
+            match (segment, item) {
+                (Segment::Key(key), Value::Object(map)) => next.extend(map.get(key)),
+                (Segment::Index(index), Value::Array(items)) => {
+                    let position = if *index < 0 { items.len() as i64 + index } else { *index };
+                    if position >= 0 {
+                        next.extend(items.get(position as usize));
+                    }
+                },
+                (Segment::Wildcard, Value::Array(items)) => next.extend(items.iter()),
+                (Segment::Wildcard, Value::Object(map)) => next.extend(map.values()),
+                _ => {}
+            }
+        }
+        current = next;
+    }
+
+    Ok(current)
+}
+
+/// First value matching the path
+pub fn select_first<'a>(value: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    Ok(select(value, path)?.into_iter().next())
+}
+
+/// Remove every value matching the path, returns how many were removed
+pub fn remove(value: &mut Value, path: &str) -> Result<usize, String> {
+    let segments = parse(path)?;
+
+    Ok(remove_segments(value, &segments))
+}
+
+fn remove_segments(value: &mut Value, segments: &[Segment]) -> usize {
+    let Some((segment, rest)) = segments.split_first() else {
+        return 0;
+    };
+
+    if rest.is_empty() {
+        return match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.remove(key).is_some() as usize,
+            (Segment::Index(index), Value::Array(items)) => match position(*index, items.len()) {
+                Some(position) => {
+                    items.remove(position);
+                    1
+                },
+                None => 0,
+            },
+            (Segment::Wildcard, Value::Array(items)) => items.drain(..).count(),
+            (Segment::Wildcard, Value::Object(map)) => {
+                let count = map.len();
+                map.clear();
+                count
+            },
+            _ => 0,
+        };
+    }
+
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => map.get_mut(key).map_or(0, |v| remove_segments(v, rest)),
+        (Segment::Index(index), Value::Array(items)) => match position(*index, items.len()) {
+            Some(position) => remove_segments(&mut items[position], rest),
+            None => 0,
+        },
+        (Segment::Wildcard, Value::Array(items)) => items.iter_mut().map(|v| remove_segments(v, rest)).sum(),
+        (Segment::Wildcard, Value::Object(map)) => map.values_mut().map(|v| remove_segments(v, rest)).sum(),
+        _ => 0,
+    }
+}
+
+fn position(index: i64, len: usize) -> Option<usize> {
+    let position = if index < 0 { len as i64 + index } else { index };
+    (position >= 0 && (position as usize) < len).then_some(position as usize)
+}
@@ -2,4 +2,6 @@ pub mod auth;
 pub mod requests;
 pub mod response;
 pub mod hash;
-pub mod reqwest;
\ No newline at end of file
+pub mod reqwest;
+pub mod crypto;pub mod jsonpath;
+pub mod body;
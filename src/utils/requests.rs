@@ -1,5 +1,5 @@
 use axum::{
-    extract::{FromRequest, Request, FromRequestParts, Path},
+    extract::{FromRequest, Request, FromRequestParts, Path, Query},
     extract::rejection::JsonRejection,
     http::request::Parts,
     Json,
@@ -10,6 +10,7 @@ use crate::utils::response::*;
 // Digunakan untuk format response jika request tidak sesuai
 pub struct ValidatedJson<T>(pub T);
 pub struct ValidatedPath<T>(pub T);
+pub struct ValidatedQuery<T>(pub T);
 
 impl<T, S> FromRequest<S> for ValidatedJson<T>
 where
@@ -54,4 +55,24 @@ where
             }
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let uri = parts.uri.clone();
+
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(value) => Ok(Self(value.0)),
+            Err(rejection) => Err(
+                AppError::BadRequest(format!("Query Error: {}", rejection))
+                .with_path(&uri)
+            ),
+        }
+    }
+}
@@ -24,4 +24,32 @@ pub async fn json_to_headermap(json_input: Option<Value>) -> HeaderMap {
     }
     
     headers
-}
\ No newline at end of file
+}
+
+/// Query map to pairs, array value repeat the key (`?tag=a&tag=b`)
+pub fn json_to_query(json_input: &Option<Value>) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+
+    if let Some(Value::Object(map)) = json_input {
+        for (k, v) in map {
+            match v {
+                Value::Null => continue,
+                Value::Array(items) => {
+                    for item in items {
+                        pairs.push((k.clone(), value_to_string(item)));
+                    }
+                }
+                _ => pairs.push((k.clone(), value_to_string(v))),
+            }
+        }
+    }
+
+    pairs
+}
+
+pub fn value_to_string(value: &Value) -> String {
+    match value.as_str() {
+        Some(s) => s.to_string(),
+        None => value.to_string(),
+    }
+}
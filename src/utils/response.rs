@@ -15,6 +15,16 @@ pub struct WebResponse<T> {
     pub timestamp: String,
     // #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+// Cursor page metadata, pass `next_cursor` back as `cursor`
+#[derive(Debug, Clone, Serialize)]
+pub struct Pagination {
+    pub limit: i64,
+    pub has_more: bool,
+    pub next_cursor: Option<String>,
 }
 
 impl<T: Serialize> WebResponse<T> {
@@ -30,6 +40,24 @@ impl<T: Serialize> WebResponse<T> {
                 path: uri.path().to_string(),
                 timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(), // Format Z
                 data: Some(data),
+                pagination: None,
+            }),
+        )
+    }
+
+    // Helper SUCCESS with page metadata
+    pub fn paginated(uri: &Uri, message: &str, data: T, pagination: Pagination) -> (StatusCode, Json<Self>) {
+        let status = StatusCode::OK;
+        (
+            status,
+            Json(Self {
+                success: true,
+                status: status.as_u16(),
+                message: message.to_string(),
+                path: uri.path().to_string(),
+                timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                data: Some(data),
+                pagination: Some(pagination),
             }),
         )
     }
@@ -46,6 +74,7 @@ impl<T: Serialize> WebResponse<T> {
                 path: uri.path().to_string(),
                 timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
                 data: Some(data),
+                pagination: None,
             }),
         )
     }
@@ -62,7 +91,8 @@ impl WebResponse<()> {
                 message: message.to_string(),
                 path: uri.path().to_string(),
                 timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
-                data: None, 
+                data: None,
+                pagination: None,
             }),
         )
     }
@@ -109,6 +139,7 @@ impl IntoResponse for ApiError {
             path: self.path,
             timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
             data: None::<()>,
+            pagination: None,
         });
 
         (status, body).into_response()
@@ -133,6 +164,7 @@ impl IntoResponse for AppError {
             path: "".to_string(),
             timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
             data: None::<()>,
+            pagination: None,
         });
 
         (status, body).into_response()
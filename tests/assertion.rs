@@ -0,0 +1,48 @@
+use scheduler::jobs::assertion::{evaluate, validate};
+use scheduler::models::fetch::{Assertion, FetchResult};
+use serde_json::json;
+use std::time::Duration;
+
+fn assertions(value: serde_json::Value) -> Vec<Assertion> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_evaluate_assertions() {
+    let mut result = FetchResult {
+        status_code: 200,
+        headers: json!({ "content-type": "application/json" }),
+        response: r#"{"status":"ok","items":[1,2],"name":"scheduler"}"#.to_string(),
+        ..Default::default()
+    };
+    let checks = assertions(json!([
+        { "type": "status", "codes": [200, 204] },
+        { "type": "latency", "max_ms": 500 },
+        { "type": "jsonpath", "path": "$.status", "op": "equals", "value": "ok" },
+        { "type": "jsonpath", "path": "$.items", "op": "contains", "value": 2 },
+        { "type": "jsonpath", "path": "$.name", "op": "contains", "value": "sched" },
+        { "type": "regex", "pattern": "\"items\":\\[1" },
+        { "type": "header", "name": "Content-Type", "value": "application/json" }
+    ]));
+
+    result.assertions = Some(evaluate(&checks, &result, Duration::from_millis(120)));
+    assert_eq!(result.passed(), Some(true));
+    assert!(result.is_success());
+
+    let failing = assertions(json!([
+        { "type": "latency", "max_ms": 100 },
+        { "type": "jsonpath", "path": "$.missing", "op": "exists" }
+    ]));
+    result.assertions = Some(evaluate(&failing, &result, Duration::from_millis(120)));
+    assert_eq!(result.passed(), Some(false));
+    assert!(!result.is_success());
+    assert_eq!(result.assertions.as_ref().unwrap()[0].actual, Some(json!(120)));
+}
+
+#[test]
+fn test_validate_assertions() {
+    assert!(validate(&assertions(json!([{ "type": "status", "codes": [] }]))).is_err());
+    assert!(validate(&assertions(json!([{ "type": "regex", "pattern": "(" }]))).is_err());
+    assert!(validate(&assertions(json!([{ "type": "jsonpath", "path": "$.a", "op": "equals" }]))).is_err());
+    assert!(validate(&assertions(json!([{ "type": "header", "name": "etag" }]))).is_ok());
+}
@@ -0,0 +1,69 @@
+use reqwest::Method;
+use scheduler::jobs::{blob::{BlobStore, FsStore, signing_key}, client::base_builder, rest::request_response};
+use scheduler::models::fetch::{BodyLimitOptions, LimitAction};
+use scheduler::utils::crypto::hex;
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+/// Serve one response with the body in two chunks and no content-length
+async fn serve(body: &'static [u8]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        let (first, second) = body.split_at(body.len() / 2);
+        socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\nconnection: close\r\n\r\n").await.unwrap();
+        socket.write_all(first).await.unwrap();
+        socket.flush().await.unwrap();
+        socket.write_all(second).await.unwrap();
+    });
+
+    format!("http://localhost:{}/", port)
+}
+
+#[tokio::test]
+async fn test_body_limit() {
+    let client = base_builder().build().unwrap();
+
+    // Cut inside "é" drops the half character
+    let url = serve("abcdé-and-more".as_bytes()).await;
+    let limit = BodyLimitOptions { max_bytes: Some(5), ..Default::default() }.resolve(1024);
+    let result = request_response(client.clone(), &url, Method::GET, &None, None, &None, &None, limit).await.unwrap();
+    assert_eq!((result.response.as_str(), result.truncated), ("abcd", true));
+
+    let url = serve(b"0123456789").await;
+    let limit = BodyLimitOptions { max_bytes: Some(4), on_limit: LimitAction::Fail, spill: None }.resolve(1024);
+    let error = request_response(client.clone(), &url, Method::GET, &None, None, &None, &None, limit).await.unwrap_err();
+    assert_eq!(error.message, "Response body exceeds 4 bytes");
+
+    let url = serve(b"0123456789").await;
+    let result = request_response(client, &url, Method::GET, &None, None, &None, &None, BodyLimitOptions::default().resolve(10)).await.unwrap();
+    assert_eq!((result.response.as_str(), result.truncated), ("0123456789", false));
+
+    // Global maximum caps the fetch limit
+    assert_eq!(BodyLimitOptions { max_bytes: Some(500), ..Default::default() }.resolve(100).max_bytes, 100);
+}
+
+#[tokio::test]
+async fn test_fs_store() {
+    let root = std::env::temp_dir().join(format!("scheduler-blob-{}", std::process::id()));
+    let store = BlobStore::Fs(FsStore::new(&root));
+
+    store.put("fetch-1/a", b"body".to_vec()).await.unwrap();
+    assert_eq!(store.get("fetch-1/a").await.unwrap(), b"body");
+    store.delete("fetch-1/a").await.unwrap();
+    assert!(store.get("fetch-1/a").await.is_err());
+    assert!(store.delete("fetch-1/a").await.is_ok());
+
+    assert!(store.put("../x", Vec::new()).await.is_err());
+    assert!(store.put("/etc/x", Vec::new()).await.is_err());
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn test_signing_key() {
+    // Example from the AWS SigV4 documentation
+    let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+    assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+}
@@ -0,0 +1,56 @@
+use scheduler::models::fetch::BodyEncoding;
+use scheduler::utils::body::{decode, encode, served_type, view};
+use serde_json::json;
+
+#[test]
+fn test_body_decode() {
+    let (text, encoding) = decode(Some("application/json"), br#"{"a":1}"#);
+    assert_eq!((text.as_str(), encoding), (r#"{"a":1}"#, BodyEncoding::Text));
+
+    // Latin-1 text decoded then encoded back to the same bytes
+    let latin = b"caf\xe9";
+    let content_type = Some("text/plain; charset=ISO-8859-1");
+    let (text, encoding) = decode(content_type, latin);
+    assert_eq!((text.as_str(), encoding), ("café", BodyEncoding::Text));
+    assert_eq!(encode(content_type, &text, encoding), latin.to_vec());
+
+    let png = [0x89, b'P', b'N', b'G', 0x00, 0xff];
+    let (text, encoding) = decode(Some("image/png"), &png);
+    assert_eq!(encoding, BodyEncoding::Base64);
+    assert_eq!(encode(Some("image/png"), &text, encoding), png.to_vec());
+
+    let (_, encoding) = decode(None, &[0xff, 0xfe, 0x00]);
+    assert_eq!(encoding, BodyEncoding::Base64);
+    assert_eq!(decode(Some("image/svg+xml"), b"<svg/>").1, BodyEncoding::Text);
+}
+
+#[test]
+fn test_body_view() {
+    let xml = r#"<order id="7"><item>a</item><item>b</item><note>hi</note></order>"#;
+    assert_eq!(view(Some("application/xml"), xml, BodyEncoding::Text), json!({
+        "order": { "@id": "7", "item": ["a", "b"], "note": "hi" }
+    }));
+
+    assert_eq!(view(Some("text/csv"), "name,qty\na,1\nb,2\n", BodyEncoding::Text), json!([
+        { "name": "a", "qty": "1" },
+        { "name": "b", "qty": "2" },
+    ]));
+
+    assert_eq!(view(Some("application/x-www-form-urlencoded"), "a=1&b=x+y&a=2", BodyEncoding::Text), json!({
+        "a": ["1", "2"], "b": "x y"
+    }));
+
+    assert_eq!(view(None, r#"{"a":1}"#, BodyEncoding::Text), json!({ "a": 1 }));
+    assert_eq!(view(Some("application/xml"), "not xml", BodyEncoding::Text), json!("not xml"));
+    assert_eq!(view(Some("image/png"), "iVBO", BodyEncoding::Base64), json!("iVBO"));
+}
+
+#[test]
+fn test_served_type() {
+    assert_eq!(served_type("application/json; charset=utf-8"), "application/json; charset=utf-8");
+    assert_eq!(served_type("image/png"), "image/png");
+    assert_eq!(served_type("text/plain"), "text/plain");
+    for active in ["text/html; charset=utf-8", "image/svg+xml", "application/xhtml+xml", "text/xml", "application/javascript", "TEXT/HTML", ""] {
+        assert_eq!(served_type(active), "application/octet-stream", "{}", active);
+    }
+}
@@ -0,0 +1,90 @@
+use scheduler::jobs::change::{diff, fingerprint, validate};
+use scheduler::models::fetch::{ChangeMode, ChangeOptions, CreateApiData, DiffOp};
+use scheduler::repository::fetch::FetchDataRepository;
+use scheduler::utils::jsonpath;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+#[test]
+fn test_fingerprint() {
+    let ignore = vec!["$.meta.requested_at".to_string()];
+    let a = fingerprint(200, r#"{"id":1,"items":["a"],"meta":{"requested_at":"10:00"}}"#, &ignore);
+    let b = fingerprint(200, r#"{"meta":{"requested_at":"10:05"},"items":["a"],"id":1}"#, &ignore);
+    let c = fingerprint(200, r#"{"id":2,"items":["a"],"meta":{"requested_at":"10:00"}}"#, &ignore);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.len(), 64);
+    assert_eq!(fingerprint(200, "plain", &[]), fingerprint(200, "plain", &[]));
+    // Same body with another status is a change
+    assert_ne!(fingerprint(200, "plain", &[]), fingerprint(503, "plain", &[]));
+
+    let mut value = json!({ "items": [{ "ts": 1, "v": 1 }, { "ts": 2, "v": 2 }] });
+    assert_eq!(jsonpath::remove(&mut value, "$.items[*].ts").unwrap(), 2);
+    assert_eq!(value, json!({ "items": [{ "v": 1 }, { "v": 2 }] }));
+
+    let invalid = ChangeOptions { mode: ChangeMode::Flag, ignore: vec!["items".to_string()] };
+    assert!(validate(&invalid).is_err());
+}
+
+#[test]
+fn test_diff() {
+    let old = json!({ "id": 1, "name": "a", "tags": ["x", "y"] });
+    let new = json!({ "id": 1, "name": "b", "tags": ["x"], "active": true });
+
+    let changes = diff(&old, &new);
+    let ops: Vec<(DiffOp, &str)> = changes.iter().map(|c| (c.op.clone(), c.path.as_str())).collect();
+    assert_eq!(ops, vec![
+        (DiffOp::Add, "/active"),
+        (DiffOp::Replace, "/name"),
+        (DiffOp::Remove, "/tags/1"),
+    ]);
+    assert_eq!(changes[1].old, Some(json!("a")));
+    assert_eq!(changes[1].new, Some(json!("b")));
+
+    assert!(diff(&old, &old).is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_latest_scoped_to_matrix_row() {
+    let pool = PgPoolOptions::new().connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL")).await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let name = format!("change-{}", uuid::Uuid::new_v4());
+    let user_id: i32 = sqlx::query_scalar("INSERT INTO users (username, email, password) VALUES ($1, $1, '') RETURNING id")
+        .bind(&name).fetch_one(&pool).await.unwrap();
+    let execute_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api_execute (user_id, name) VALUES ($1, $2) RETURNING id")
+        .bind(user_id).bind(&name).fetch_one(&pool).await.unwrap();
+    let fetch_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api (name, endpoint, description, execute_id) VALUES ($1, 'https://example.com', '', $2) RETURNING id")
+        .bind(&name).bind(execute_id).fetch_one(&pool).await.unwrap();
+
+    let repo = FetchDataRepository::new(pool);
+    let mut ids = Vec::new();
+    for matrix_row in [None, Some(0), Some(1)] {
+        let data = repo.create(CreateApiData {
+            fetch_id,
+            name: name.clone(),
+            status_code: Some(200),
+            response: Some("{}".to_string()),
+            response_headers: Some(json!({})),
+            assertions: None,
+            passed: None,
+            schema_errors: None,
+            body_hash: Some(fingerprint(200, "{}", &[])),
+            changed: Some(true),
+            content_type: None,
+            encoding: None,
+            truncated: None,
+            blob_key: None,
+            blob_size: None,
+            matrix_row,
+        }).await.unwrap();
+        ids.push(data.id);
+    }
+
+    // Rows of a matrix run never stand in for the regular run or for each other
+    assert_eq!(repo.find_latest(fetch_id, None).await.unwrap().unwrap().id, ids[0]);
+    assert_eq!(repo.find_latest(fetch_id, Some(0)).await.unwrap().unwrap().id, ids[1]);
+    assert_eq!(repo.find_latest(fetch_id, Some(1)).await.unwrap().unwrap().id, ids[2]);
+    assert!(repo.find_latest(fetch_id, Some(2)).await.unwrap().is_none());
+}
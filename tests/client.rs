@@ -0,0 +1,21 @@
+use scheduler::jobs::client::build_client;
+use scheduler::models::fetch::{ClientOptions, RedirectMode};
+
+#[test]
+fn test_build_client_options() {
+    let options = ClientOptions {
+        timeout: Some(30),
+        redirect: Some(RedirectMode::Limit),
+        max_redirects: Some(3),
+        proxy: Some("socks5://127.0.0.1:1080".to_string()),
+        user_agent: Some("probe/1.0".to_string()),
+        ..Default::default()
+    };
+    assert!(build_client(&options).is_ok());
+
+    let half_identity = ClientOptions { client_cert: Some("cert".to_string()), ..Default::default() };
+    assert!(build_client(&half_identity).is_err());
+
+    let bad_ca = ClientOptions { ca_bundle: Some("not a pem".to_string()), ..Default::default() };
+    assert!(build_client(&bad_ca).is_err());
+}
@@ -0,0 +1,43 @@
+use scheduler::jobs::dependency::{creates_cycle, is_final};
+use scheduler::jobs::template::{TemplateContext, render_text};
+use scheduler::models::fetch::UpstreamResult;
+use serde_json::{Map, json};
+
+#[test]
+fn test_creates_cycle() {
+    let edges = [(1, 2), (2, 3), (4, 3)];
+
+    assert!(creates_cycle(&edges, 3, 1));
+    assert!(creates_cycle(&edges, 2, 2));
+    assert!(!creates_cycle(&edges, 1, 4));
+    assert!(!creates_cycle(&edges, 3, 5));
+}
+
+#[test]
+fn test_retried_failure_triggers_once() {
+    let max_attempts = 5;
+
+    let failed = (1..=max_attempts).filter(|&attempt| is_final(true, attempt, max_attempts)).count();
+    assert_eq!(failed, 1);
+    assert!(is_final(true, max_attempts, max_attempts));
+
+    // A success on any attempt ends the job, so it triggers right away
+    assert!(is_final(false, 1, max_attempts));
+    assert!(is_final(false, 3, max_attempts));
+}
+
+#[test]
+fn test_upstream_template() {
+    let mut ctx = TemplateContext { now: chrono::Utc::now(), last_run_at: None, run_number: 1, variables: Map::new() };
+    assert_eq!(render_text("id={{upstream.id}}", &ctx).unwrap(), "id=");
+
+    ctx.with_upstream(&UpstreamResult {
+        id: 9,
+        name: "login".to_string(),
+        status_code: Some(200),
+        response: json!({ "data": { "token": "abc" } }).to_string(),
+        error: None,
+    });
+    assert_eq!(render_text("{{upstream.id}}:{{upstream.status_code}}", &ctx).unwrap(), "9:200");
+    assert_eq!(render_text("Bearer {{upstream.response | $.data.token}}", &ctx).unwrap(), "Bearer abc");
+}
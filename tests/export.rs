@@ -0,0 +1,58 @@
+use chrono::{TimeZone, Utc};
+use scheduler::jobs::export::{header, parse_columns, row};
+use scheduler::models::fetch::{ApiData, BodyEncoding, ExportColumn, ExportFormat};
+use serde_json::json;
+
+fn data() -> ApiData {
+    let created_at = Utc.with_ymd_and_hms(2026, 4, 1, 12, 0, 0).unwrap();
+    ApiData {
+        id: 7,
+        fetch_id: 1,
+        name: "row".to_string(),
+        status_code: 200,
+        response: r#"{"price":19.5,"name":"a, b","items":[{"id":1},{"id":2}]}"#.to_string(),
+        response_headers: json!({}),
+        assertions: None,
+        passed: Some(true),
+        schema_errors: None,
+        body_hash: None,
+        changed: None,
+        content_type: None,
+        encoding: BodyEncoding::Text,
+        truncated: false,
+        blob_key: None,
+        blob_size: None,
+        matrix_row: None,
+        updated_at: created_at,
+        created_at,
+    }
+}
+
+#[test]
+fn test_export_columns() {
+    let columns = parse_columns("price=$.price, $.items[*].id").unwrap();
+    assert_eq!(columns, vec![
+        ExportColumn { name: "price".to_string(), path: "$.price".to_string() },
+        ExportColumn { name: "$.items[*].id".to_string(), path: "$.items[*].id".to_string() },
+    ]);
+
+    assert!(parse_columns("id=$.id").is_err());
+    assert!(parse_columns("a=$.a,a=$.b").is_err());
+    assert!(parse_columns("a=price").is_err());
+}
+
+#[test]
+fn test_export_rows() {
+    let columns = parse_columns("price=$.price,name=$.name,ids=$.items[*].id").unwrap();
+
+    let csv = [header(ExportFormat::Csv, &columns), row(ExportFormat::Csv, &columns, &data())].concat();
+    assert_eq!(String::from_utf8(csv).unwrap(),
+        "id,created_at,status_code,passed,changed,price,name,ids\n7,2026-04-01T12:00:00+00:00,200,true,,19.5,\"a, b\",\"[1,2]\"\n");
+
+    assert!(header(ExportFormat::Ndjson, &columns).is_empty());
+    let line = row(ExportFormat::Ndjson, &[], &data());
+    assert_eq!(line.last(), Some(&b'\n'));
+    let value: serde_json::Value = serde_json::from_slice(&line).unwrap();
+    assert_eq!(value["response"]["price"], json!(19.5));
+    assert_eq!(value["passed"], json!(true));
+}
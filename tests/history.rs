@@ -0,0 +1,59 @@
+use reqwest::Method;
+use scheduler::jobs::{client::base_builder, error::{ErrorKind, FetchError, kind_of}, history::{RunReport, trigger_of}, probe::ProbeJobs, rest::request_response};
+use scheduler::models::fetch::{Api, BodyLimitOptions, FetchResult, RunOutcome, RunTrigger, UpstreamResult};
+use serde_json::json;
+use tokio::net::TcpListener;
+
+fn result(status_code: i16) -> FetchResult {
+    FetchResult { status_code, headers: json!({}), response: String::new(), ..Default::default() }
+}
+
+#[test]
+fn test_run_report() {
+    let report = RunReport::from_result(&result(200), Some(3));
+    assert_eq!(report.outcome, RunOutcome::Success);
+    assert_eq!(report.data_id, Some(3));
+
+    let report = RunReport::from_result(&result(503), None);
+    assert_eq!(report.outcome, RunOutcome::Failure);
+    assert_eq!(report.error_kind.as_deref(), Some("status"));
+
+    let report = RunReport::from_error(ErrorKind::Timeout, "error sending request: operation timed out");
+    assert_eq!(report.outcome, RunOutcome::Error);
+    assert_eq!(report.error_kind.as_deref(), Some("timeout"));
+
+    // Kind comes from the error, never from words in the message
+    let error = anyhow::Error::from(FetchError::new(ErrorKind::Script, "Pre-request script failed: boom"));
+    assert_eq!(kind_of(&error), ErrorKind::Script);
+    let error = anyhow::Error::from(FetchError::from("Upstream said: connect timeout".to_string()));
+    assert_eq!(kind_of(&error), ErrorKind::Request);
+    assert_eq!(kind_of(&anyhow::anyhow!("token expired")), ErrorKind::Request);
+    assert_eq!(kind_of(&anyhow::Error::from(sqlx::Error::RowNotFound)), ErrorKind::Database);
+}
+
+#[tokio::test]
+async fn test_connection_error_kind() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let url = format!("http://{}/", addr);
+    let limit = BodyLimitOptions::default().resolve(1024);
+    let error = request_response(base_builder().build().unwrap(), &url, Method::GET, &None, None, &None, &None, limit).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Connection);
+
+    let error = ProbeJobs::new(5).request_response(&addr.to_string(), &None, None).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Connection);
+}
+
+#[test]
+fn test_run_trigger() {
+    let mut api: Api = serde_json::from_value(json!({
+        "id": 1, "name": "test", "type": "rest", "endpoint": "https://example.com",
+        "description": "", "execute_id": 1, "is_active": true, "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap();
+    assert_eq!(trigger_of(&api), RunTrigger::Schedule);
+
+    api.triggered_by = Some(UpstreamResult { id: 2, name: "up".into(), status_code: Some(200), response: String::new(), error: None });
+    assert_eq!(trigger_of(&api), RunTrigger::Dependency);
+}
@@ -0,0 +1,68 @@
+use chrono::Utc;
+use scheduler::jobs::matrix::{parse_csv, validate};
+use scheduler::jobs::template::{TemplateContext, render_text};
+use scheduler::models::fetch::MatrixOptions;
+use scheduler::repository::fetch::FetchMatrixRepository;
+use serde_json::{Map, json};
+use sqlx::postgres::PgPoolOptions;
+
+#[test]
+fn test_parse_csv() {
+    let rows = parse_csv("region, host\neu, eu.example.com\nus, us.example.com\n").unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1]["region"], json!("us"));
+    assert_eq!(rows[0]["host"], json!("eu.example.com"));
+    assert!(parse_csv("a,b\n1,2,3\n").is_err());
+}
+
+#[test]
+fn test_validate_matrix() {
+    let options: MatrixOptions = serde_json::from_value(json!({ "rows": [{ "id": 1 }, { "id": 2 }] })).unwrap();
+    assert!(validate(&options).is_ok());
+
+    let nested: MatrixOptions = serde_json::from_value(json!({ "rows": [{ "id": [1] }] })).unwrap();
+    assert!(validate(&nested).is_err());
+
+    let both: MatrixOptions = serde_json::from_value(json!({ "rows": [{ "id": 1 }], "dataset_id": 3 })).unwrap();
+    assert!(validate(&both).is_err());
+    assert!(validate(&MatrixOptions { rows: Some(vec![]), dataset_id: None }).is_err());
+}
+
+#[test]
+fn test_render_row() {
+    let mut ctx = TemplateContext { now: Utc::now(), last_run_at: None, run_number: 1, variables: Map::new() };
+    let row: Map<String, serde_json::Value> = serde_json::from_value(json!({ "host": "eu.example.com", "port": 8080 })).unwrap();
+    ctx.with_row(&row);
+
+    assert_eq!(render_text("https://{{row.host}}:{{row.port}}/health", &ctx).unwrap(), "https://eu.example.com:8080/health");
+    assert!(render_text("{{row.missing}}", &ctx).is_err());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_row_counted_once() {
+    let pool = PgPoolOptions::new().connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL")).await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let name = format!("matrix-{}", uuid::Uuid::new_v4());
+    let user_id: i32 = sqlx::query_scalar("INSERT INTO users (username, email, password) VALUES ($1, $1, '') RETURNING id")
+        .bind(&name).fetch_one(&pool).await.unwrap();
+    let execute_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api_execute (user_id, name) VALUES ($1, $2) RETURNING id")
+        .bind(user_id).bind(&name).fetch_one(&pool).await.unwrap();
+    let fetch_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api (name, endpoint, description, execute_id) VALUES ($1, 'https://example.com', '', $2) RETURNING id")
+        .bind(&name).bind(execute_id).fetch_one(&pool).await.unwrap();
+
+    let repo = FetchMatrixRepository::new(pool);
+    let run = repo.create(fetch_id, 2, &name).await.unwrap();
+    assert!(repo.claim_row(run.id, 0).await.unwrap());
+    assert!(repo.claim_row(run.id, 1).await.unwrap());
+
+    // A retried row job does not count the row again
+    let first = repo.record(run.id, 0, true, json!({ "index": 0 })).await.unwrap().unwrap();
+    assert_eq!((first.succeeded, first.failed), (1, 0));
+    assert!(repo.record(run.id, 0, false, json!({ "index": 0 })).await.unwrap().is_none());
+
+    let last = repo.record(run.id, 1, false, json!({ "index": 1 })).await.unwrap().unwrap();
+    assert_eq!((last.succeeded, last.failed), (1, 1));
+    assert!(last.finished_at.is_some());
+}
@@ -0,0 +1,49 @@
+use scheduler::jobs::metric::{extract, validate};
+use scheduler::models::fetch::{FetchResult, MetricExtractor, MetricValue};
+use serde_json::json;
+
+fn extractors(value: serde_json::Value) -> Vec<MetricExtractor> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_metric_extract() {
+    let metrics = extractors(json!([
+        { "name": "depth", "type": "jsonpath", "path": "$.queue.depth" },
+        { "name": "price", "type": "jsonpath", "path": "$.price" },
+        { "name": "state", "type": "jsonpath", "path": "$.queue.state", "kind": "string" },
+        { "name": "missing", "type": "jsonpath", "path": "$.nope" },
+    ]));
+    let result = FetchResult {
+        status_code: 200,
+        response: r#"{"queue":{"depth":42,"state":"busy"},"price":"19.5"}"#.to_string(),
+        ..Default::default()
+    };
+
+    assert_eq!(extract(&metrics, &result), vec![
+        MetricValue { name: "depth".to_string(), value_num: Some(42.0), value_text: None },
+        MetricValue { name: "price".to_string(), value_num: Some(19.5), value_text: None },
+        MetricValue { name: "state".to_string(), value_num: None, value_text: Some("busy".to_string()) },
+    ]);
+
+    let metrics = extractors(json!([
+        { "name": "temp", "type": "regex", "pattern": "temperature=(-?[0-9.]+)" },
+        { "name": "unit", "type": "regex", "pattern": "unit=(\\w+)", "kind": "string" },
+    ]));
+    let result = FetchResult { status_code: 200, response: "temperature=-3.5 unit=C".to_string(), ..Default::default() };
+    let values = extract(&metrics, &result);
+    assert_eq!(values[0].value_num, Some(-3.5));
+    assert_eq!(values[1].value_text.as_deref(), Some("C"));
+}
+
+#[test]
+fn test_metric_validate() {
+    assert!(validate(&extractors(json!([{ "name": "a", "type": "jsonpath", "path": "$.a" }]))).is_ok());
+    assert!(validate(&extractors(json!([{ "name": "a", "type": "jsonpath", "path": "a" }]))).is_err());
+    assert!(validate(&extractors(json!([{ "name": "a", "type": "regex", "pattern": "(x", }]))).is_err());
+    assert!(validate(&extractors(json!([{ "name": "a", "type": "regex", "pattern": "(x)", "group": 2 }]))).is_err());
+    assert!(validate(&extractors(json!([
+        { "name": "a", "type": "jsonpath", "path": "$.a" },
+        { "name": "a", "type": "jsonpath", "path": "$.b" },
+    ]))).is_err());
+}
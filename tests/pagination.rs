@@ -0,0 +1,54 @@
+use axum::http::Uri;
+use chrono::{TimeZone, Utc};
+use scheduler::models::fetch::{ApiData, BodyEncoding, DataCursor, DataSort, SortOrder};
+use scheduler::utils::response::{Pagination, WebResponse};
+use serde_json::json;
+
+#[test]
+fn test_data_cursor() {
+    let created_at = Utc.with_ymd_and_hms(2026, 4, 1, 12, 0, 0).unwrap();
+    let data = ApiData {
+        id: 42,
+        fetch_id: 1,
+        name: "row".to_string(),
+        status_code: 503,
+        response: "{}".to_string(),
+        response_headers: json!({}),
+        assertions: None,
+        passed: None,
+        schema_errors: None,
+        body_hash: None,
+        changed: None,
+        content_type: None,
+        encoding: BodyEncoding::Text,
+        truncated: false,
+        blob_key: None,
+        blob_size: None,
+        matrix_row: None,
+        updated_at: created_at,
+        created_at,
+    };
+
+    let cursor = DataCursor::after(DataSort::CreatedAt, SortOrder::Desc, &data);
+    let decoded = DataCursor::decode(&cursor.encode(), DataSort::CreatedAt, SortOrder::Desc).unwrap();
+    assert_eq!(decoded, cursor);
+    assert_eq!(decoded.at, Some(created_at));
+    assert!(DataCursor::decode(&cursor.encode(), DataSort::StatusCode, SortOrder::Desc).is_err());
+    assert!(DataCursor::decode(&cursor.encode(), DataSort::CreatedAt, SortOrder::Asc).is_err());
+    assert!(DataCursor::decode("not a cursor", DataSort::CreatedAt, SortOrder::Desc).is_err());
+
+    let cursor = DataCursor::after(DataSort::StatusCode, SortOrder::Asc, &data);
+    assert_eq!(DataCursor::decode(&cursor.encode(), DataSort::StatusCode, SortOrder::Asc).unwrap().status, Some(503));
+}
+
+#[test]
+fn test_paginated_response() {
+    let uri: Uri = "/fetch/1/data".parse().unwrap();
+    let pagination = Pagination { limit: 2, has_more: true, next_cursor: Some("abc".to_string()) };
+    let (_, body) = WebResponse::paginated(&uri, "List fetch data", vec![1, 2], pagination);
+    let body = serde_json::to_value(&body.0).unwrap();
+    assert_eq!(body["pagination"], json!({ "limit": 2, "has_more": true, "next_cursor": "abc" }));
+
+    let (_, body) = WebResponse::ok(&uri, "List", vec![1]);
+    assert!(serde_json::to_value(&body.0).unwrap().get("pagination").is_none());
+}
@@ -0,0 +1,35 @@
+use scheduler::jobs::probe::{ProbeJobs, split_host_port};
+use scheduler::models::fetch::{ProbeOptions, ProbeResult};
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+#[test]
+fn test_split_host_port() {
+    assert_eq!(split_host_port("example.com:443"), Ok(("example.com".to_string(), 443)));
+    assert_eq!(split_host_port("tcp://[::1]:8080"), Ok(("::1".to_string(), 8080)));
+    assert!(split_host_port("example.com").is_err());
+    assert!(split_host_port(":22").is_err());
+}
+
+#[tokio::test]
+async fn test_probe_tcp_banner() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 4];
+        socket.read_exact(&mut buf).await.unwrap();
+        socket.write_all(b"+PONG\r\n").await.unwrap();
+    });
+
+    let options = ProbeOptions { read_banner: true, ..Default::default() };
+    let result = ProbeJobs::new(5)
+        .request_response(&addr.to_string(), &Some("PING".to_string()), Some(options))
+        .await
+        .unwrap();
+    let probe: ProbeResult = serde_json::from_str(&result.response).unwrap();
+
+    assert_eq!(result.status_code, 200);
+    assert_eq!(probe.port, addr.port());
+    assert_eq!(probe.banner.as_deref(), Some("+PONG\r\n"));
+    assert!(probe.tls.is_none());
+}
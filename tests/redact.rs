@@ -0,0 +1,94 @@
+use scheduler::jobs::dependency::upstream_result;
+use scheduler::jobs::redact::{apply, headers, step, validate, variables};
+use scheduler::models::fetch::{Api, BodyEncoding, FetchResult, RedactPattern, RedactionOptions, WorkflowStepResult};
+use serde_json::{Map, json};
+
+fn rules() -> RedactionOptions {
+    let global = RedactionOptions { headers: vec!["Set-Cookie".to_string()], ..Default::default() };
+    RedactionOptions {
+        paths: vec!["$.token".to_string(), "$.users[*].password".to_string()],
+        patterns: vec![
+            RedactPattern { pattern: r"\b\d{4}(\d{8})(\d{4})\b".to_string(), replacement: Some("****-$2".to_string()) },
+            RedactPattern { pattern: r"[\w.]+@[\w.]+".to_string(), replacement: None },
+        ],
+        headers: vec!["authorization".to_string()],
+    }.with(&global)
+}
+
+#[test]
+fn test_redact_response() {
+    let mut result = FetchResult {
+        response: json!({
+            "token": "abc",
+            "users": [{ "email": "a@example.com", "password": "x", "card": "4111111111111111" }],
+        }).to_string(),
+        headers: json!({ "set-cookie": "sid=1", "x-contact": "ops@example.com", "content-type": "application/json" }),
+        ..Default::default()
+    };
+    apply(&rules(), &mut result);
+
+    let body: serde_json::Value = serde_json::from_str(&result.response).unwrap();
+    assert_eq!(body, json!({ "users": [{ "email": "********", "card": "****-1111" }] }));
+    assert_eq!(result.headers, json!({ "set-cookie": "********", "x-contact": "********", "content-type": "application/json" }));
+
+    // Binary bodies are left alone
+    let mut result = FetchResult { response: "a@b.c".to_string(), encoding: BodyEncoding::Base64, ..Default::default() };
+    apply(&rules(), &mut result);
+    assert_eq!(result.response, "a@b.c");
+}
+
+#[test]
+fn test_redact_rules() {
+    let logged = headers(&rules(), &Some(json!({ "Authorization": "Bearer t", "Accept": "*/*" })));
+    assert_eq!(logged, json!({ "Authorization": "********", "Accept": "*/*" }));
+
+    assert!(validate(&rules()).is_ok());
+    assert!(validate(&RedactionOptions { paths: vec!["token".to_string()], ..Default::default() }).is_err());
+    let pattern = RedactPattern { pattern: "(".to_string(), replacement: None };
+    assert!(validate(&RedactionOptions { patterns: vec![pattern], ..Default::default() }).is_err());
+}
+
+#[test]
+fn test_redact_upstream_payload() {
+    let fetch: Api = serde_json::from_value(json!({
+        "id": 1, "name": "test", "type": "rest", "method": "get",
+        "endpoint": "https://example.com/items",
+        "description": "", "execute_id": 1, "is_active": true,
+        "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap();
+    let result = FetchResult {
+        status_code: 200,
+        response: json!({ "token": "abc", "contact": "ops@example.com" }).to_string(),
+        ..Default::default()
+    };
+
+    let upstream = upstream_result(&rules(), &fetch, Ok(&result));
+    let body: serde_json::Value = serde_json::from_str(&upstream.response).unwrap();
+    assert_eq!(body, json!({ "contact": "********" }));
+
+    let upstream = upstream_result(&rules(), &fetch, Err("failed for ops@example.com"));
+    assert_eq!(upstream.error.as_deref(), Some("failed for ********"));
+}
+
+#[test]
+fn test_redact_script_variables_and_steps() {
+    let mut vars = Map::new();
+    vars.insert("owner".to_string(), json!("ops@example.com"));
+    vars.insert("ids".to_string(), json!([1, "a@b.c"]));
+    variables(&rules(), &mut vars);
+    assert_eq!(vars.get("owner"), Some(&json!("********")));
+    assert_eq!(vars.get("ids"), Some(&json!([1, "********"])));
+
+    let mut result = WorkflowStepResult {
+        name: "login".to_string(),
+        status_code: Some(200),
+        duration_ms: 1,
+        headers: json!({ "Authorization": "Bearer t", "Accept": "*/*" }),
+        response: json!({ "token": "abc", "id": 1 }).to_string(),
+        extracted: vec!["token".to_string()],
+        error: None,
+    };
+    step(&rules(), &mut result);
+    assert_eq!(result.headers, json!({ "Authorization": "********", "Accept": "*/*" }));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&result.response).unwrap(), json!({ "id": 1 }));
+}
@@ -0,0 +1,44 @@
+use scheduler::models::fetch::{ApiBody, MultipartPart};
+use scheduler::utils::reqwest::json_to_query;
+use serde_json::json;
+
+#[test]
+fn test_query_pairs() {
+    let query = Some(json!({ "tag": ["a", "b"], "limit": 10, "skip": null }));
+    let mut pairs = json_to_query(&query);
+    pairs.sort();
+
+    assert_eq!(pairs, vec![
+        ("limit".to_string(), "10".to_string()),
+        ("tag".to_string(), "a".to_string()),
+        ("tag".to_string(), "b".to_string()),
+    ]);
+}
+
+#[test]
+fn test_body_modes() {
+    let body: ApiBody = serde_json::from_value(json!({
+        "mode": "multipart",
+        "parts": [
+            { "type": "text", "name": "note", "value": "hello" },
+            { "type": "file", "name": "file", "filename": "a.txt", "content": "aGVsbG8=" }
+        ]
+    })).unwrap();
+
+    match body {
+        ApiBody::Multipart { parts } => assert!(matches!(parts[1], MultipartPart::File { .. })),
+        _ => panic!("expected multipart body"),
+    }
+    assert!(serde_json::from_value::<ApiBody>(json!({ "mode": "xml" })).is_err());
+}
+
+#[test]
+fn test_custom_method() {
+    use scheduler::jobs::rest::to_method;
+    use scheduler::models::fetch::ApiMethod;
+
+    assert_eq!(to_method(&Some(ApiMethod::Head), &None).unwrap(), reqwest::Method::HEAD);
+    assert_eq!(to_method(&Some(ApiMethod::Custom), &Some("PROPFIND".into())).unwrap().as_str(), "PROPFIND");
+    assert!(to_method(&Some(ApiMethod::Custom), &None).is_err());
+    assert!(to_method(&Some(ApiMethod::Custom), &Some("BAD VERB".into())).is_err());
+}
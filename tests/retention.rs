@@ -0,0 +1,52 @@
+use scheduler::jobs::retention::{prune_history, validate};
+use scheduler::models::fetch::{ApiOptions, RetentionOptions};
+use scheduler::repository::fetch::{FetchMetricRepository, FetchRunRepository};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+#[test]
+fn test_retention_policy() {
+    let defaults = RetentionOptions { keep_last: Some(1000), max_age_days: Some(30), max_bytes: None };
+    let options: ApiOptions = serde_json::from_value(json!({ "retention": { "keep_last": 50, "max_bytes": 1048576 } })).unwrap();
+
+    let policy = options.retention.unwrap().or(&defaults);
+    assert_eq!(policy, RetentionOptions { keep_last: Some(50), max_age_days: Some(30), max_bytes: Some(1048576) });
+    assert!(RetentionOptions::default().or(&RetentionOptions::default()).is_empty());
+
+    assert!(validate(&policy).is_ok());
+    assert!(validate(&RetentionOptions { keep_last: Some(0), ..Default::default() }).is_err());
+    assert!(validate(&RetentionOptions { max_age_days: Some(-1), ..Default::default() }).is_err());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_prune_history() {
+    let pool = PgPoolOptions::new().connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL")).await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let name = format!("retention-{}", uuid::Uuid::new_v4());
+    let user_id: i32 = sqlx::query_scalar("INSERT INTO users (username, email, password) VALUES ($1, $1, '') RETURNING id")
+        .bind(&name).fetch_one(&pool).await.unwrap();
+    let execute_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api_execute (user_id, name) VALUES ($1, $2) RETURNING id")
+        .bind(user_id).bind(&name).fetch_one(&pool).await.unwrap();
+    let fetch_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api (name, endpoint, description, execute_id) VALUES ($1, 'https://example.com', '', $2) RETURNING id")
+        .bind(&name).bind(execute_id).fetch_one(&pool).await.unwrap();
+
+    // Five runs and two series of five values, one day apart, the oldest 40 days ago
+    for day in [40, 3, 2, 1, 0] {
+        sqlx::query("INSERT INTO fetch_runs (fetch_id, outcome, started_at, finished_at, duration_ms) VALUES ($1, 'success', NOW() - $2 * INTERVAL '1 day', NOW() - $2 * INTERVAL '1 day', 1)")
+            .bind(fetch_id).bind(day as f64).execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO fetch_metrics (fetch_id, name, value_num, recorded_at) SELECT $1, n, 1, NOW() - $2 * INTERVAL '1 day' FROM UNNEST(ARRAY['latency', 'count']) AS n")
+            .bind(fetch_id).bind(day as f64).execute(&pool).await.unwrap();
+    }
+
+    let (run_repo, metric_repo) = (FetchRunRepository::new(pool.clone()), FetchMetricRepository::new(pool.clone()));
+    let policy = RetentionOptions { keep_last: Some(3), max_age_days: Some(30), max_bytes: Some(1) };
+    // Batch of one drains every row past the policy
+    let (runs, metrics) = prune_history(&run_repo, &metric_repo, fetch_id, &policy, 1).await.unwrap();
+    assert_eq!((runs, metrics), (2, 4));
+
+    let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fetch_runs WHERE fetch_id = $1").bind(fetch_id).fetch_one(&pool).await.unwrap();
+    assert_eq!(left, 3);
+    let per_series: Vec<i64> = sqlx::query_scalar("SELECT COUNT(*) FROM fetch_metrics WHERE fetch_id = $1 GROUP BY name").bind(fetch_id).fetch_all(&pool).await.unwrap();
+    assert_eq!(per_series, vec![3, 3]);
+}
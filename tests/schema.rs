@@ -0,0 +1,43 @@
+use scheduler::jobs::schema::{check, validate};
+use scheduler::models::fetch::{FetchResult, SchemaOptions};
+use serde_json::json;
+
+fn options(fail_run: Option<bool>) -> SchemaOptions {
+    SchemaOptions {
+        document: json!({
+            "type": "object",
+            "required": ["id", "items"],
+            "properties": {
+                "id": { "type": "integer" },
+                "items": { "type": "array", "items": { "type": "string" } }
+            }
+        }),
+        fail_run,
+    }
+}
+
+#[test]
+fn test_schema_check() {
+    assert!(check(&options(None), r#"{"id":1,"items":["a"]}"#).errors.is_empty());
+
+    let result = check(&options(None), r#"{"id":"1","items":["a",2]}"#);
+    let mut paths: Vec<&str> = result.errors.iter().map(|e| e.instance_path.as_str()).collect();
+    paths.sort();
+    assert_eq!(paths, vec!["/id", "/items/1"]);
+    assert!(result.enforced);
+
+    assert_eq!(check(&options(None), "plain text").errors.len(), 1);
+}
+
+#[test]
+fn test_schema_fail_run() {
+    let mut result = FetchResult { status_code: 200, headers: json!({}), response: r#"{"id":1}"#.to_string(), ..Default::default() };
+    result.schema = Some(check(&options(Some(false)), &result.response));
+    assert!(result.is_success());
+
+    result.schema = Some(check(&options(None), &result.response));
+    assert_eq!(result.passed(), Some(false));
+    assert!(!result.is_success());
+
+    assert!(validate(&SchemaOptions { document: json!({ "type": "nope" }), fail_run: None }).is_err());
+}
@@ -0,0 +1,99 @@
+use scheduler::jobs::{history::RunReport, script::{post_response, pre_request, validate}};
+use scheduler::models::fetch::{Api, ApiMethod, FetchResult, RunOutcome, ScriptOptions};
+use serde_json::{Map, json};
+
+fn fetch() -> Api {
+    serde_json::from_value(json!({
+        "id": 1, "name": "test", "type": "rest", "method": "get",
+        "endpoint": "https://example.com/items",
+        "description": "", "execute_id": 1, "is_active": true,
+        "payload": "{\"a\":1}",
+        "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap()
+}
+
+fn options(pre: Option<&str>, post: Option<&str>) -> ScriptOptions {
+    ScriptOptions { pre_request: pre.map(String::from), post_response: post.map(String::from), timeout_ms: Some(500) }
+}
+
+#[test]
+fn test_pre_request_signing() {
+    let mut api = fetch();
+    let mut headers = None;
+    let script = r#"
+        request.method = "POST";
+        request.url += "?page=2";
+        request.headers["X-Signature"] = hmac_sha256("key", request.payload);
+    "#;
+    pre_request(&options(Some(script), None), &mut api, &mut headers).unwrap();
+
+    assert_eq!(api.method, Some(ApiMethod::Post));
+    assert_eq!(api.endpoint, "https://example.com/items?page=2");
+    assert_eq!(headers.unwrap()["X-Signature"], json!(signature()));
+}
+
+fn signature() -> String {
+    use ring::hmac;
+    let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, b"key"), b"{\"a\":1}");
+    tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_post_response() {
+    let mut result = FetchResult { status_code: 200, headers: json!({}), response: r#"{"items":[1,2,3],"next":"abc"}"#.to_string(), ..Default::default() };
+    let variables: Map<String, serde_json::Value> = serde_json::from_value(json!({ "cursor": "" })).unwrap();
+    let script = r#"
+        let data = json_parse(response.body);
+        variables.cursor = data.next;
+        response.body = json_stringify(#{ count: data.items.len() });
+    "#;
+    let changed = post_response(&options(None, Some(script)), &mut result, &variables).unwrap();
+
+    assert_eq!(result.response, r#"{"count":3}"#);
+    assert_eq!(changed.get("cursor"), Some(&json!("abc")));
+
+    let failing = options(None, Some(r#"if response.status == 200 { throw "unexpected"; }"#));
+    assert_eq!(post_response(&failing, &mut result, &variables).unwrap_err(), "Post-response script failed: unexpected");
+}
+
+#[test]
+fn test_post_response_marks_failed() {
+    let mut result = FetchResult { status_code: 200, headers: json!({}), response: r#"{"state":"degraded"}"#.to_string(), ..Default::default() };
+    assert_eq!(result.passed(), None);
+
+    let script = r#"
+        if json_parse(response.body).state != "ok" {
+            response.failed = true;
+            response.reason = "state is degraded";
+        }
+    "#;
+    post_response(&options(None, Some(script)), &mut result, &Map::new()).unwrap();
+
+    // Stored like failed assertions, not an error that retries the request
+    assert_eq!(result.script_failure.as_deref(), Some("state is degraded"));
+    assert_eq!(result.passed(), Some(false));
+    assert!(!result.is_success());
+    let report = RunReport::from_result(&result, None);
+    assert_eq!(report.outcome, RunOutcome::Failure);
+    assert_eq!(report.error_kind.as_deref(), Some("check"));
+    assert_eq!(report.error_message.as_deref(), Some("state is degraded"));
+
+    let mut result = FetchResult { status_code: 200, ..Default::default() };
+    post_response(&options(None, Some("response.failed = true;")), &mut result, &Map::new()).unwrap();
+    assert_eq!(result.script_failure.as_deref(), Some("Marked failed by post-response script"));
+    let mut result = FetchResult { status_code: 200, ..Default::default() };
+    post_response(&options(None, Some("response.status = 200;")), &mut result, &Map::new()).unwrap();
+    assert_eq!(result.script_failure, None);
+}
+
+#[test]
+fn test_sandbox_limits() {
+    let mut api = fetch();
+    let mut headers = None;
+    assert!(pre_request(&options(Some("loop {}"), None), &mut api, &mut headers).is_err());
+    assert!(pre_request(&options(Some(r#"import "fs" as fs;"#), None), &mut api, &mut headers).is_err());
+
+    assert!(validate(&options(Some("let x = ;"), None)).is_err());
+    assert!(validate(&ScriptOptions { timeout_ms: Some(60_000), ..Default::default() }).is_err());
+    assert!(validate(&options(Some("request.url = \"x\";"), Some("response.status = 201;"))).is_ok());
+}
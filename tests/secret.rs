@@ -0,0 +1,122 @@
+use scheduler::jobs::secret::{protect, referenced_names, references_secrets, resolve};
+use scheduler::repository::secret::SecretRepository;
+use scheduler::models::{fetch::Api, secret::{CreateSecret, Secret}};
+use scheduler::utils::crypto::SecretCipher;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+const KEY_V1: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+const KEY_V2: &str = "ICEiIyQlJicoKSorLC0uLzAxMjM0NTY3ODk6Ozw9Pj8=";
+
+fn fetch() -> Api {
+    serde_json::from_value(json!({
+        "id": 1, "name": "test", "type": "rest", "method": "post",
+        "endpoint": "https://example.com/{{secret.TENANT}}/items",
+        "description": "", "execute_id": 1, "is_active": true,
+        "payload": "{\"token\": \"{{ secret.API_TOKEN }}\"}",
+        "query": { "key": ["{{secret.API_TOKEN}}"] },
+        "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap()
+}
+
+#[test]
+fn test_cipher_rotation() {
+    let old = SecretCipher::new(KEY_V1, 1, "").unwrap();
+    let (data, version) = old.encrypt("s3cr3t", "1:TOKEN").unwrap();
+    assert_eq!(version, 1);
+
+    let current = SecretCipher::new(KEY_V2, 2, &format!("1:{}", KEY_V1)).unwrap();
+    assert_eq!(current.decrypt(&data, 1, "1:TOKEN").unwrap(), "s3cr3t");
+    assert!(current.decrypt(&data, 1, "2:TOKEN").is_err());
+    assert!(current.decrypt(&data, 2, "1:TOKEN").is_err());
+    assert!(SecretCipher::new("c2hvcnQ=", 1, "").is_err());
+}
+
+#[test]
+fn test_protect_placeholders() {
+    let mut api = fetch();
+    let mut headers = Some(json!({ "Authorization": "Bearer {{secret.API_TOKEN}}" }));
+
+    let names: Vec<String> = referenced_names(&api, &headers).into_iter().collect();
+    assert_eq!(names, vec!["API_TOKEN", "TENANT"]);
+
+    // Markers replace every placeholder, templates rendered next can't see or reach them
+    protect(&mut api, &mut headers);
+    assert!(referenced_names(&api, &headers).is_empty());
+    assert!(api.endpoint.starts_with("https://example.com/\u{1}secret:"));
+    assert!(!api.payload.as_deref().unwrap().contains("{{"));
+    assert!(!headers.unwrap()["Authorization"].as_str().unwrap().contains("{{"));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_resolve_creator_secrets() {
+    let pool = PgPoolOptions::new().connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL")).await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let secret_repo = SecretRepository::new(pool.clone());
+    let cipher = SecretCipher::new(KEY_V1, 1, "").unwrap();
+
+    let mut users = Vec::new();
+    for owner in ["creator", "member"] {
+        let name = format!("{}-{}", owner, uuid::Uuid::new_v4());
+        let user_id: i32 = sqlx::query_scalar("INSERT INTO users (username, email, password) VALUES ($1, $1, '') RETURNING id")
+            .bind(&name).fetch_one(&pool).await.unwrap();
+        users.push(user_id);
+    }
+    let (creator, member) = (users[0], users[1]);
+    for (user_id, name, value) in [(creator, "API_TOKEN", "abc"), (creator, "TENANT", "acme"), (member, "API_TOKEN", "other")] {
+        let (ciphertext, key_version) = cipher.encrypt(value, &Secret::aad(user_id, name)).unwrap();
+        secret_repo.create(CreateSecret { user_id, name: name.to_string(), description: None, ciphertext, key_version }).await.unwrap();
+    }
+
+    let mut api = Api { created_by: Some(creator), ..fetch() };
+    let mut headers = Some(json!({ "Authorization": "Bearer {{secret.API_TOKEN}}" }));
+    let refs = protect(&mut api, &mut headers);
+    resolve(Some(&cipher), &secret_repo, &refs, &mut api, &mut headers).await.unwrap();
+    assert_eq!(api.endpoint, "https://example.com/acme/items");
+    assert_eq!(api.payload.as_deref(), Some("{\"token\": \"abc\"}"));
+    assert_eq!(api.query, Some(json!({ "key": ["abc"] })));
+    assert_eq!(headers, Some(json!({ "Authorization": "Bearer abc" })));
+
+    // Secrets of other users never fill in, a missing one fails the whole request
+    let mut api = Api { created_by: Some(member), ..fetch() };
+    let refs = protect(&mut api, &mut None);
+    let error = resolve(Some(&cipher), &secret_repo, &refs, &mut api, &mut None).await.unwrap_err();
+    assert_eq!(error, "Secret 'TENANT' not found");
+
+    // Marker of another run copied into a rendered value
+    let mut api = Api { created_by: Some(creator), ..fetch() };
+    let refs = protect(&mut api, &mut None);
+    let mut headers = Some(json!({ "X-Copy": "\u{1}secret:0000:API_TOKEN\u{1}" }));
+    let error = resolve(Some(&cipher), &secret_repo, &refs, &mut api, &mut headers).await.unwrap_err();
+    assert_eq!(error, "Secret marker comes from a rendered value");
+}
+
+#[tokio::test]
+async fn test_rendered_placeholder_rejected() {
+    let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+    let secret_repo = SecretRepository::new(pool);
+
+    // Stored placeholders become markers, a rendered value adding one is rejected before any lookup
+    let mut api = fetch();
+    let mut headers = None;
+    let refs = protect(&mut api, &mut headers);
+    assert!(referenced_names(&api, &headers).is_empty());
+    assert!(!api.endpoint.contains("{{"));
+    api.query = Some(json!({ "leak": "{{secret.API_TOKEN}}" }));
+    let error = resolve(None, &secret_repo, &refs, &mut api, &mut headers).await.unwrap_err();
+    assert!(error.contains("rendered value"));
+
+    let mut plain: scheduler::models::fetch::Api = serde_json::from_value(json!({
+        "id": 2, "name": "plain", "type": "rest", "endpoint": "https://example.com",
+        "description": "", "execute_id": 1, "is_active": true, "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap();
+    let refs = protect(&mut plain, &mut headers);
+    assert!(resolve(None, &secret_repo, &refs, &mut plain, &mut headers).await.is_ok());
+
+    plain.options = Some(json!({ "workflow": { "steps": [{ "endpoint": "https://x/{{secret.TOKEN}}" }] } }));
+    assert!(references_secrets(&plain, &None));
+    plain.options = None;
+    assert!(!references_secrets(&plain, &None));
+    assert!(references_secrets(&plain, &Some(json!({ "X-Key": "{{ secret.KEY }}" }))));
+}
@@ -0,0 +1,69 @@
+use scheduler::jobs::{client::base_builder, sse::{SseJobs, SseParser, last_event_id}};
+use scheduler::models::fetch::{BodyLimitOptions, LimitAction, SseEvent};
+use std::time::Duration;
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+/// Serve one response, each chunk written apart
+async fn serve(status: &'static str, chunks: &'static [&'static [u8]]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        let head = format!("HTTP/1.1 {}\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n", status);
+        socket.write_all(head.as_bytes()).await.unwrap();
+        for chunk in chunks {
+            socket.write_all(chunk).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    });
+
+    format!("http://localhost:{}/", port)
+}
+
+#[test]
+fn test_sse_parser_events() {
+    let mut parser = SseParser::default();
+    let mut events = parser.feed(b": keep-alive\n\nevent: update\ndata: first\ndata: second\nid: 1\n\n");
+    events.extend(parser.feed(b"data: {\"value\":2}\r\nid: 2\r"));
+    events.extend(parser.feed(b"\n\r\n"));
+    events.extend(parser.feed(b"data: incomplete"));
+
+    assert_eq!(events, vec![
+        SseEvent { event: "update".into(), data: "first\nsecond".into(), id: Some("1".into()) },
+        SseEvent { event: "message".into(), data: "{\"value\":2}".into(), id: Some("2".into()) },
+    ]);
+}
+
+#[test]
+fn test_sse_last_event_id() {
+    let stored = r#"[{"event":"message","data":"a","id":"7"},{"event":"message","data":"b"}]"#;
+
+    assert_eq!(last_event_id(stored), Some("7".to_string()));
+    assert_eq!(last_event_id("not json"), None);
+}
+
+#[tokio::test]
+async fn test_sse_body_limit() {
+    let sse = SseJobs::new(base_builder().build().unwrap(), 5, 10);
+    let limit = BodyLimitOptions { max_bytes: Some(12), ..Default::default() }.resolve(1024);
+
+    // Events before the limit are kept
+    let url = serve("200 OK", &[b"data: a\n\n", b"data: bbbbbbbb\n\n"]).await;
+    let result = sse.request_response(&url, &None, &None, None, None, None, limit).await.unwrap();
+    assert!(result.truncated);
+    assert_eq!(last_event_id(&result.response), None);
+    assert_eq!(serde_json::from_str::<Vec<SseEvent>>(&result.response).unwrap().len(), 1);
+
+    let url = serve("200 OK", &[b"data: a\n\n", b"data: bbbbbbbb\n\n"]).await;
+    let fail = BodyLimitOptions { max_bytes: Some(12), on_limit: LimitAction::Fail, spill: None }.resolve(1024);
+    let error = sse.request_response(&url, &None, &None, None, None, None, fail).await.unwrap_err();
+    assert_eq!(error.message, "Response body exceeds 12 bytes");
+
+    // Error response read within the limit too
+    let url = serve("500 Internal Server Error", &[b"0123456789abcdef"]).await;
+    let result = sse.request_response(&url, &None, &None, None, None, None, limit).await.unwrap();
+    assert_eq!((result.status_code, result.response.as_str(), result.truncated), (500, "0123456789ab", true));
+}
@@ -0,0 +1,91 @@
+use chrono::{TimeZone, Utc};
+use scheduler::jobs::template::{TemplateContext, render_text, validate_variables};
+use scheduler::models::fetch::ApiEnvironment;
+use scheduler::repository::fetch::{FetchEnvironmentRepository, FetchRepository, FetchRunRepository};
+use serde_json::{Map, json};
+use sqlx::postgres::PgPoolOptions;
+
+fn context() -> TemplateContext {
+    let variables: Map<String, serde_json::Value> = serde_json::from_value(json!({ "region": "eu", "limit": 50 })).unwrap();
+    TemplateContext {
+        now: Utc.with_ymd_and_hms(2026, 3, 1, 12, 30, 0).unwrap(),
+        last_run_at: None,
+        run_number: 7,
+        variables,
+    }
+}
+
+#[test]
+fn test_render_builtins() {
+    let ctx = context();
+
+    assert_eq!(render_text("{{now}}", &ctx).unwrap(), "2026-03-01T12:30:00+00:00");
+    assert_eq!(render_text("{{ now | unix }}", &ctx).unwrap(), "1772368200");
+    assert_eq!(render_text("{{now | %d/%m/%Y}}", &ctx).unwrap(), "01/03/2026");
+    assert_eq!(render_text("since={{last_run_at}}&n={{run_number}}", &ctx).unwrap(), "since=&n=7");
+    assert_eq!(render_text("{{uuid}}", &ctx).unwrap().len(), 36);
+
+    let value: i64 = render_text("{{random_int | 5..6}}", &ctx).unwrap().parse().unwrap();
+    assert!((5..=6).contains(&value));
+}
+
+#[test]
+fn test_render_variables() {
+    let ctx = context();
+
+    assert_eq!(render_text("/{{region}}/items?limit={{limit}}", &ctx).unwrap(), "/eu/items?limit=50");
+    assert_eq!(render_text("Bearer {{secret.TOKEN}}", &ctx).unwrap(), "Bearer {{secret.TOKEN}}");
+    assert!(render_text("{{missing}}", &ctx).is_err());
+    assert!(render_text("{{now | %Q}}", &ctx).is_err());
+
+    let reserved: Map<String, serde_json::Value> = serde_json::from_value(json!({ "now": "x" })).unwrap();
+    assert!(validate_variables(&reserved).is_err());
+}
+
+#[test]
+fn test_environment_masked() {
+    let env = ApiEnvironment {
+        id: 1,
+        user_id: 1,
+        name: "prod".to_string(),
+        description: None,
+        variables: json!({ "api_key": "k-123", "region": "eu" }),
+        updated_at: Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap(),
+    };
+
+    let masked = env.masked();
+    assert_eq!(masked.variables, json!({ "api_key": "********", "region": "********" }));
+    assert_eq!(masked.variables_map().len(), 2);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_script_variables_kept_apart() {
+    let pool = PgPoolOptions::new().connect(&std::env::var("DATABASE_URL").expect("DATABASE_URL")).await.unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let name = format!("script-{}", uuid::Uuid::new_v4());
+    let user_id: i32 = sqlx::query_scalar("INSERT INTO users (username, email, password) VALUES ($1, $1, '') RETURNING id")
+        .bind(&name).fetch_one(&pool).await.unwrap();
+    let execute_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api_execute (user_id, name) VALUES ($1, $2) RETURNING id")
+        .bind(user_id).bind(&name).fetch_one(&pool).await.unwrap();
+    let fetch_id: i32 = sqlx::query_scalar("INSERT INTO fetch_api (name, endpoint, description, execute_id, options) VALUES ($1, 'https://example.com', '', $2, $3) RETURNING id")
+        .bind(&name).bind(execute_id).bind(json!({ "variables": { "cursor": "", "region": "eu" } }))
+        .fetch_one(&pool).await.unwrap();
+
+    let (fetch_repo, run_repo, env_repo) = (FetchRepository::new(pool.clone()), FetchRunRepository::new(pool.clone()), FetchEnvironmentRepository::new(pool));
+    run_repo.save_script_variables(fetch_id, json!({ "cursor": "abc" })).await.unwrap();
+    run_repo.save_script_variables(fetch_id, json!({ "page": 2 })).await.unwrap();
+
+    // Saved values win at render time, the definition keeps what the user set
+    let fetch = fetch_repo.get_by_id(&fetch_id).await.unwrap();
+    let ctx = TemplateContext::load(&run_repo, &env_repo, &fetch, None).await.unwrap();
+    assert_eq!(ctx.variables["cursor"], "abc");
+    assert_eq!(ctx.variables["region"], "eu");
+    assert_eq!(ctx.variables["page"], 2);
+    assert_eq!(fetch.options.as_ref().unwrap()["variables"], json!({ "cursor": "", "region": "eu" }));
+
+    run_repo.clear_script_variables(fetch_id).await.unwrap();
+    let ctx = TemplateContext::load(&run_repo, &env_repo, &fetch, None).await.unwrap();
+    assert_eq!(ctx.variables["cursor"], "");
+    assert!(ctx.variables.get("page").is_none());
+}
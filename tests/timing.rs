@@ -0,0 +1,32 @@
+use reqwest::Method;
+use scheduler::jobs::{client::base_builder, rest::request_response, timing::millis};
+use scheduler::models::fetch::BodyLimitOptions;
+use std::time::Duration;
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+#[tokio::test]
+async fn test_rest_timing() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = socket.read(&mut buf).await.unwrap();
+        socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 11\r\nconnection: close\r\n\r\nhello world").await.unwrap();
+    });
+
+    let client = base_builder().build().unwrap();
+    let url = format!("http://localhost:{}/", port);
+    let payload = Some("ping".to_string());
+    let result = request_response(client, &url, Method::POST, &payload, None, &None, &None, BodyLimitOptions::default().resolve(1024)).await.unwrap();
+    let timing = result.timing.unwrap();
+
+    assert_eq!(result.response, "hello world");
+    assert!(timing.connect_ms.is_some());
+    assert_eq!(timing.dns_ms, None);
+    assert_eq!(timing.tls_ms, None);
+    assert!(timing.ttfb_ms.unwrap() <= timing.total_ms.unwrap());
+    assert_eq!(timing.request_bytes, Some(4));
+    assert_eq!(timing.response_bytes, Some(11));
+    assert_eq!(millis(Duration::from_micros(1500)), 1.5);
+}
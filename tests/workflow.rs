@@ -0,0 +1,74 @@
+use scheduler::jobs::{error::ErrorKind, template::TemplateContext, workflow::{extract, prepare_step, validate}};
+use scheduler::models::fetch::{Api, FetchResult, StepExtract, WorkflowOptions, WorkflowStep};
+use scheduler::repository::secret::SecretRepository;
+use scheduler::utils::jsonpath::select;
+use serde_json::json;
+
+#[test]
+fn test_jsonpath_select() {
+    let value = json!({ "data": { "items": [ { "id": 1 }, { "id": 2 } ], "a.b": "dot" } });
+
+    assert_eq!(select(&value, "$.data.items[0].id").unwrap(), vec![&json!(1)]);
+    assert_eq!(select(&value, "$.data.items[-1].id").unwrap(), vec![&json!(2)]);
+    assert_eq!(select(&value, "$.data.items[*].id").unwrap(), vec![&json!(1), &json!(2)]);
+    assert_eq!(select(&value, "$.data['a.b']").unwrap(), vec![&json!("dot")]);
+    assert!(select(&value, "$.data.missing").unwrap().is_empty());
+    assert!(select(&value, "data.items").is_err());
+}
+
+#[test]
+fn test_step_extract() {
+    let result = FetchResult {
+        status_code: 200,
+        headers: json!({ "Location": "/orders/42" }),
+        response: json!({ "token": "abc", "user": { "id": 7 } }).to_string(),
+        ..Default::default()
+    };
+    let rule = |value: serde_json::Value| serde_json::from_value::<StepExtract>(value).unwrap();
+
+    assert_eq!(extract(&rule(json!({ "name": "token", "jsonpath": "$.token" })), &result).unwrap(), "abc");
+    assert_eq!(extract(&rule(json!({ "name": "uid", "jsonpath": "$.user.id" })), &result).unwrap(), "7");
+    assert_eq!(extract(&rule(json!({ "name": "order", "source": "header", "header": "location", "regex": "/orders/(\\d+)" })), &result).unwrap(), "42");
+    assert_eq!(extract(&rule(json!({ "name": "code", "source": "status" })), &result).unwrap(), "200");
+    assert!(extract(&rule(json!({ "name": "x", "jsonpath": "$.missing" })), &result).is_err());
+}
+
+#[test]
+fn test_validate_steps() {
+    let options: WorkflowOptions = serde_json::from_value(json!({ "steps": [
+        { "name": "login", "endpoint": "https://example.com/login", "method": "post",
+          "extract": [ { "name": "token", "jsonpath": "$.token" } ] },
+        { "name": "orders", "endpoint": "https://example.com/orders", "headers": { "Authorization": "Bearer {{token}}" } }
+    ] })).unwrap();
+    assert!(validate(&options).is_ok());
+
+    let invalid: WorkflowOptions = serde_json::from_value(json!({ "steps": [
+        { "name": "login", "endpoint": "https://example.com", "extract": [ { "name": "now", "jsonpath": "$.token" } ] }
+    ] })).unwrap();
+    assert!(validate(&invalid).is_err());
+    assert!(validate(&WorkflowOptions::default()).is_err());
+}
+
+#[tokio::test]
+async fn test_extracted_value_cannot_add_secret() {
+    let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+    let secret_repo = SecretRepository::new(pool);
+    let fetch: Api = serde_json::from_value(json!({
+        "id": 1, "name": "flow", "type": "workflow", "endpoint": "https://example.com",
+        "description": "", "execute_id": 1, "is_active": true, "updated_at": "2026-01-01T00:00:00Z"
+    })).unwrap();
+    let step: WorkflowStep = serde_json::from_value(json!({
+        "name": "orders", "endpoint": "https://example.com/orders", "headers": { "Authorization": "Bearer {{token}}" }
+    })).unwrap();
+
+    // Extracted by an earlier step from a response the upstream controls
+    let mut ctx = TemplateContext { now: chrono::Utc::now(), last_run_at: None, run_number: 1, variables: serde_json::Map::new() };
+    ctx.variables.insert("token".to_string(), json!("{{secret.API_TOKEN}}"));
+    let error = prepare_step(None, &secret_repo, &fetch, &None, &step, &ctx).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Secret);
+    assert!(error.message.contains("rendered value"));
+
+    ctx.variables.insert("token".to_string(), json!("abc"));
+    let (_, headers) = prepare_step(None, &secret_repo, &fetch, &None, &step, &ctx).await.unwrap();
+    assert_eq!(headers, Some(json!({ "Authorization": "Bearer abc" })));
+}